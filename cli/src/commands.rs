@@ -0,0 +1,411 @@
+use crate::files::{call, read, read_party, write, CliError, Export};
+use dkls23::protocols::Party;
+use ffi_tss::dkls23::protocols::derivation::{
+    dkls_party_derive_from_path, PartyDeriveFromPathIn,
+};
+use ffi_tss::dkls23::protocols::re_key::{dkls_re_key, RekeyIn, RekeyOut};
+use ffi_tss::dkls23::protocols::router::{
+    route_dkg_phase1, route_dkg_phase2, route_dkg_phase3, route_sign_phase1,
+    route_sign_phase2, route_sign_phase3, RoutingError,
+};
+use ffi_tss::dkls23::protocols::signing::{
+    dkls_verify_ecdsa_signature, VerifyIn, VerifyOut,
+};
+use ffi_tss::dkls23::protocols::{dkg, signing};
+use ffi_tss::dkls23::session::seal::SealedMessage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Every command takes one input file per party and writes the output of
+// each party to `party-{index}.json` in the output directory. When the
+// outputs of all the parties are there, the messages for each party are
+// also written to `to-party-{index}.json`, under the names of the input
+// fields of the next phase. The outputs keep the secret state of their
+// party, and must only go back to that party.
+
+fn run<I: DeserializeOwned + Serialize>(
+    function: &str,
+    export: Export,
+    inputs: &[PathBuf],
+    mut index: impl FnMut(&I) -> u8,
+) -> Result<BTreeMap<u8, Value>, CliError> {
+    let mut outputs = BTreeMap::new();
+    for path in inputs {
+        let input: I = read(path)?;
+        let index = index(&input);
+        if outputs.contains_key(&index) {
+            return Err(CliError::Input {
+                description: format!("Two inputs are for party {}!", index),
+            });
+        }
+        outputs.insert(index, call(function, export, &input)?);
+    }
+    Ok(outputs)
+}
+
+fn write_all(
+    out: &Path,
+    prefix: &str,
+    values: &BTreeMap<u8, Value>,
+) -> Result<Vec<PathBuf>, CliError> {
+    values
+        .iter()
+        .map(|(index, value)| {
+            write(out, &format!("{}-{}.json", prefix, index), value)
+        })
+        .collect()
+}
+
+fn typed<O: DeserializeOwned>(
+    outputs: &BTreeMap<u8, Value>,
+) -> Result<BTreeMap<u8, O>, CliError> {
+    outputs
+        .iter()
+        .map(|(&index, output)| {
+            serde_json::from_value(output.clone())
+                .map(|output| (index, output))
+                .map_err(|error| CliError::Input {
+                    description: format!(
+                        "The output of party {} is invalid: {}",
+                        index, error
+                    ),
+                })
+        })
+        .collect()
+}
+
+fn routing(error: RoutingError) -> CliError {
+    CliError::Call {
+        function: String::from("route"),
+        error: serde_json::to_value(error).unwrap(),
+    }
+}
+
+// The sealed messages of each output, keyed by the party that output
+// them, as the router takes them. Empty if the phase was run without
+// identity keys.
+fn sealed(
+    outputs: &BTreeMap<u8, Value>,
+) -> Result<BTreeMap<u8, Vec<SealedMessage>>, CliError> {
+    outputs
+        .iter()
+        .filter(|(_, output)| output.get("sealed").is_some())
+        .map(|(&index, output)| {
+            serde_json::from_value(output["sealed"].clone())
+                .map(|messages| (index, messages))
+                .map_err(|error| CliError::Input {
+                    description: format!(
+                        "The sealed messages of party {} are invalid: {}",
+                        index, error
+                    ),
+                })
+        })
+        .collect()
+}
+
+// Adds to each recipient the sealed messages the router gave it.
+fn with_sealed(
+    mut recipients: BTreeMap<u8, Value>,
+    sealed: BTreeMap<u8, Vec<SealedMessage>>,
+) -> BTreeMap<u8, Value> {
+    for (index, messages) in sealed {
+        if let Some(recipient) = recipients.get_mut(&index) {
+            recipient["sealed"] = json!(messages);
+        }
+    }
+    recipients
+}
+
+fn dkg_recipients(
+    phase: u8,
+    outputs: &BTreeMap<u8, Value>,
+) -> Result<BTreeMap<u8, Value>, CliError> {
+    let sealed = sealed(outputs)?;
+    let recipients = match phase {
+        1 => {
+            let routed =
+                route_dkg_phase1(&typed(outputs)?, &sealed).map_err(routing)?;
+            let recipients = routed
+                .poly_fragments
+                .into_iter()
+                .map(|(index, fragments)| {
+                    (index, json!({ "poly_fragments": fragments }))
+                })
+                .collect();
+            with_sealed(recipients, routed.sealed)
+        }
+        2 => {
+            let routed =
+                route_dkg_phase2(&typed(outputs)?, &sealed).map_err(routing)?;
+            let recipients = routed
+                .zero_received_phase2
+                .iter()
+                .map(|(&index, received)| {
+                    let recipient = json!({
+                        "proofs_commitments": routed.proofs_commitments,
+                        "zero_received_phase2": received,
+                        "bip_broadcast_2to4": routed.bip_broadcast_2to4,
+                    });
+                    (index, recipient)
+                })
+                .collect();
+            with_sealed(recipients, routed.sealed)
+        }
+        3 => {
+            let routed =
+                route_dkg_phase3(&typed(outputs)?, &sealed).map_err(routing)?;
+            let recipients = routed
+                .zero_received_phase3
+                .iter()
+                .map(|(&index, received)| {
+                    let recipient = json!({
+                        "zero_received_phase3": received,
+                        "mul_received": routed.mul_received[&index],
+                        "bip_broadcast_3to4": routed.bip_broadcast_3to4,
+                    });
+                    (index, recipient)
+                })
+                .collect();
+            with_sealed(recipients, routed.sealed)
+        }
+        _ => BTreeMap::new(),
+    };
+    Ok(recipients)
+}
+
+fn sign_recipients(
+    phase: u8,
+    outputs: &BTreeMap<u8, Value>,
+) -> Result<BTreeMap<u8, Value>, CliError> {
+    let received = match phase {
+        1 => {
+            let routed = route_sign_phase1(&typed(outputs)?, &sealed(outputs)?)
+                .map_err(routing)?;
+            let recipients = routed
+                .received
+                .into_iter()
+                .map(|(index, received)| {
+                    (index, json!({ "received": received }))
+                })
+                .collect();
+            with_sealed(recipients, routed.sealed)
+        }
+        2 => {
+            let routed = route_sign_phase2(&typed(outputs)?, &sealed(outputs)?)
+                .map_err(routing)?;
+            let recipients = routed
+                .received
+                .into_iter()
+                .map(|(index, received)| {
+                    (index, json!({ "received": received }))
+                })
+                .collect();
+            with_sealed(recipients, routed.sealed)
+        }
+        3 => {
+            let routed =
+                route_sign_phase3(&typed(outputs)?).map_err(routing)?;
+            let recipient = json!({
+                "x_coord": routed.x_coord,
+                "received": routed.received,
+            });
+            outputs
+                .keys()
+                .map(|&index| (index, recipient.clone()))
+                .collect()
+        }
+        _ => BTreeMap::new(),
+    };
+    Ok(received)
+}
+
+pub fn dkg_phase(
+    phase: u8,
+    inputs: &[PathBuf],
+    out: &Path,
+) -> Result<Vec<PathBuf>, CliError> {
+    let (outputs, share_count) = match phase {
+        1 => {
+            let mut share_count = 0;
+            let outputs = run(
+                "dkls_dkg_phase1",
+                dkg::dkls_dkg_phase1,
+                inputs,
+                |input: &dkg::Phase1In| {
+                    share_count = input.session.parameters.share_count;
+                    input.session.party_index
+                },
+            )?;
+            (outputs, share_count)
+        }
+        2 => {
+            let mut share_count = 0;
+            let outputs = run(
+                "dkls_dkg_phase2",
+                dkg::dkls_dkg_phase2,
+                inputs,
+                |input: &dkg::Phase2In| {
+                    share_count = input.session.parameters.share_count;
+                    input.session.party_index
+                },
+            )?;
+            (outputs, share_count)
+        }
+        3 => {
+            let mut share_count = 0;
+            let outputs = run(
+                "dkls_dkg_phase3",
+                dkg::dkls_dkg_phase3,
+                inputs,
+                |input: &dkg::Phase3In| {
+                    share_count = input.session.parameters.share_count;
+                    input.session.party_index
+                },
+            )?;
+            (outputs, share_count)
+        }
+        4 => {
+            let outputs = run(
+                "dkls_dkg_phase4",
+                dkg::dkls_dkg_phase4,
+                inputs,
+                |input: &dkg::Phase4In| input.session.party_index,
+            )?;
+            (outputs, 0)
+        }
+        _ => return Err(phase_error(phase)),
+    };
+
+    let mut written = write_all(out, "party", &outputs)?;
+    if phase < 4 && outputs.len() == share_count as usize {
+        written.extend(write_all(
+            out,
+            "to-party",
+            &dkg_recipients(phase, &outputs)?,
+        )?);
+    }
+    Ok(written)
+}
+
+pub fn sign_phase(
+    phase: u8,
+    inputs: &[PathBuf],
+    out: &Path,
+) -> Result<Vec<PathBuf>, CliError> {
+    // The signers, as the first input names them.
+    let mut signers: Vec<u8> = Vec::new();
+    let mut index = |party: &Party, counterparties: &[u8]| {
+        if signers.is_empty() {
+            signers = counterparties.to_vec();
+            signers.push(party.party_index);
+            signers.sort_unstable();
+        }
+        party.party_index
+    };
+    let outputs = match phase {
+        1 => run(
+            "dkls_sign_phase1",
+            signing::dkls_sign_phase1,
+            inputs,
+            |input: &signing::Phase1In| {
+                index(&input.party, &input.sign_data.counterparties)
+            },
+        )?,
+        2 => run(
+            "dkls_sign_phase2",
+            signing::dkls_sign_phase2,
+            inputs,
+            |input: &signing::Phase2In| {
+                index(&input.party, &input.sign_data.counterparties)
+            },
+        )?,
+        3 => run(
+            "dkls_sign_phase3",
+            signing::dkls_sign_phase3,
+            inputs,
+            |input: &signing::Phase3In| {
+                index(&input.party, &input.sign_data.counterparties)
+            },
+        )?,
+        4 => run(
+            "dkls_sign_phase4",
+            signing::dkls_sign_phase4,
+            inputs,
+            |input: &signing::Phase4In| {
+                index(&input.party, &input.sign_data.counterparties)
+            },
+        )?,
+        _ => return Err(phase_error(phase)),
+    };
+
+    let mut written = write_all(out, "party", &outputs)?;
+    if phase < 4 && outputs.keys().copied().eq(signers) {
+        written.extend(write_all(
+            out,
+            "to-party",
+            &sign_recipients(phase, &outputs)?,
+        )?);
+    }
+    Ok(written)
+}
+
+fn phase_error(phase: u8) -> CliError {
+    CliError::Input {
+        description: format!("There is no phase {}!", phase),
+    }
+}
+
+// Derives every party file at the path, as a DKG phase 4 output.
+pub fn derive(
+    path: &str,
+    inputs: &[PathBuf],
+    out: &Path,
+) -> Result<Vec<PathBuf>, CliError> {
+    let mut outputs = BTreeMap::new();
+    for input in inputs {
+        let party = read_party(input)?;
+        let index = party.party_index;
+        let derive_in = PartyDeriveFromPathIn {
+            party,
+            path: path.to_string(),
+        };
+        let output = call(
+            "dkls_party_derive_from_path",
+            dkls_party_derive_from_path,
+            &derive_in,
+        )?;
+        if outputs.insert(index, output).is_some() {
+            return Err(CliError::Input {
+                description: format!("Two inputs are for party {}!", index),
+            });
+        }
+    }
+    write_all(out, "party", &outputs)
+}
+
+// Splits a key into a party file for each party.
+pub fn re_key(input: &Path, out: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let re_key_in: RekeyIn = read(input)?;
+    let output = call("dkls_re_key", dkls_re_key, &re_key_in)?;
+    let re_key_out: RekeyOut = serde_json::from_value(output).unwrap();
+    let parties = re_key_out
+        .parties
+        .into_iter()
+        .map(|party| (party.party_index, json!({ "party": party })))
+        .collect();
+    write_all(out, "party", &parties)
+}
+
+pub fn verify(input: &Path) -> Result<bool, CliError> {
+    let verify_in: VerifyIn = read(input)?;
+    let output = call(
+        "dkls_verify_ecdsa_signature",
+        dkls_verify_ecdsa_signature,
+        &verify_in,
+    )?;
+    let verify_out: VerifyOut = serde_json::from_value(output).unwrap();
+    Ok(verify_out.valid)
+}
@@ -0,0 +1,115 @@
+use dkls23::protocols::Party;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::ffi::CString;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::raw::c_char;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+// Every input is read into the In type of its export before the export
+// is called, so that a malformed file is reported with its path instead
+// of aborting inside the library.
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    Io { path: PathBuf, description: String },
+    Json { path: PathBuf, description: String },
+    // The export returned {"error": ...}.
+    Call { function: String, error: Value },
+    // Inputs that do not fit together, such as two files for one party.
+    Input { description: String },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Io { path, description } => {
+                write!(f, "{}: {}", path.display(), description)
+            }
+            CliError::Json { path, description } => {
+                write!(f, "{}: invalid JSON: {}", path.display(), description)
+            }
+            CliError::Call { function, error } => {
+                write!(f, "{} failed: {}", function, error)
+            }
+            CliError::Input { description } => write!(f, "{}", description),
+        }
+    }
+}
+
+pub type Export = extern "C" fn(*const c_char) -> *const c_char;
+
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    let bytes = std::fs::read(path).map_err(|error| CliError::Io {
+        path: path.to_path_buf(),
+        description: error.to_string(),
+    })?;
+    serde_json::from_slice(&bytes).map_err(|error| CliError::Json {
+        path: path.to_path_buf(),
+        description: error.to_string(),
+    })
+}
+
+// A party file is the output of DKG phase 4, {"party": ...}, or the
+// party on its own.
+pub fn read_party(path: &Path) -> Result<Party, CliError> {
+    let value: Value = read(path)?;
+    let party = match value {
+        Value::Object(mut object) if object.contains_key("party") => {
+            object.remove("party").unwrap()
+        }
+        value => value,
+    };
+    serde_json::from_value(party).map_err(|error| CliError::Json {
+        path: path.to_path_buf(),
+        description: error.to_string(),
+    })
+}
+
+// Writes pretty JSON to `dir/name`, creating the directory. The outputs
+// keep secret shares, so only the owner can read them, and a file that
+// is already there is never overwritten.
+pub fn write<T: Serialize>(
+    dir: &Path,
+    name: &str,
+    value: &T,
+) -> Result<PathBuf, CliError> {
+    let path = dir.join(name);
+    let io_error = |error: std::io::Error| CliError::Io {
+        path: path.clone(),
+        description: error.to_string(),
+    };
+    std::fs::create_dir_all(dir).map_err(io_error)?;
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&path).map_err(io_error)?;
+    let json = serde_json::to_vec_pretty(value).unwrap();
+    file.write_all(&json).map_err(io_error)?;
+    Ok(path)
+}
+
+// Calls an export the way a C host would.
+pub fn call<T: Serialize>(
+    function: &str,
+    export: Export,
+    input: &T,
+) -> Result<Value, CliError> {
+    let data = CString::new(serde_json::to_string(input).unwrap()).unwrap();
+    let json_out = export(data.as_ptr());
+    let json_out = unsafe { CString::from_raw(json_out as *mut c_char) };
+    let output: Value = serde_json::from_slice(json_out.as_bytes()).unwrap();
+    match output.get("error") {
+        Some(error) => Err(CliError::Call {
+            function: function.to_string(),
+            error: error.clone(),
+        }),
+        None => Ok(output),
+    }
+}
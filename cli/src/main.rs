@@ -0,0 +1,100 @@
+use clap::{Parser, Subcommand};
+use libtss_cli::commands;
+use libtss_cli::files::{read_party, CliError};
+use libtss_cli::summary::PartySummary;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "libtss", about = "Runs libtss ceremonies from JSON files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a DKG phase, with one input file per party.
+    Dkg {
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        phase: u8,
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
+    /// Runs a signing phase, with one input file per signer.
+    Sign {
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        phase: u8,
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
+    /// Derives party files at a BIP-32 path, such as m/0/1.
+    Derive {
+        #[arg(long)]
+        path: String,
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        #[arg(required = true)]
+        parties: Vec<PathBuf>,
+    },
+    /// Splits a secret key into party files, from a re_key input file.
+    ReKey {
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        input: PathBuf,
+    },
+    /// Verifies a signature, from a verify input file.
+    Verify { input: PathBuf },
+    /// Prints a party file without its secrets.
+    Inspect {
+        #[arg(long)]
+        json: bool,
+        party: PathBuf,
+    },
+}
+
+fn run(command: Command) -> Result<(), CliError> {
+    let written = match command {
+        Command::Dkg { phase, out, inputs } => {
+            commands::dkg_phase(phase, &inputs, &out)?
+        }
+        Command::Sign { phase, out, inputs } => {
+            commands::sign_phase(phase, &inputs, &out)?
+        }
+        Command::Derive { path, out, parties } => {
+            commands::derive(&path, &parties, &out)?
+        }
+        Command::ReKey { out, input } => commands::re_key(&input, &out)?,
+        Command::Verify { input } => {
+            if commands::verify(&input)? {
+                println!("valid");
+                return Ok(());
+            }
+            println!("invalid");
+            std::process::exit(1);
+        }
+        Command::Inspect { json, party } => {
+            let summary = PartySummary::new(&read_party(&party)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+            } else {
+                println!("{}", summary);
+            }
+            return Ok(());
+        }
+    };
+    for path in written {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run(Cli::parse().command) {
+        eprintln!("libtss: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,57 @@
+use dkls23::protocols::Party;
+use ffi_tss::k256::elliptic_curve::sec1::ToEncodedPoint;
+use serde::Serialize;
+use std::fmt;
+
+// What can be shown about a party without its secrets: the key share,
+// the zero shares and the multiplication state are left out.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct PartySummary {
+    pub party_index: u8,
+    pub threshold: u8,
+    pub share_count: u8,
+    pub session_id: String,
+    // The compressed SEC1 public key in hex.
+    pub public_key: String,
+    pub eth_address: String,
+    pub depth: u8,
+    pub child_number: u32,
+    pub parent_fingerprint: String,
+    pub chain_code: String,
+}
+
+impl PartySummary {
+    pub fn new(party: &Party) -> PartySummary {
+        let derivation = &party.derivation_data;
+        PartySummary {
+            party_index: party.party_index,
+            threshold: party.parameters.threshold,
+            share_count: party.parameters.share_count,
+            session_id: hex::encode(&party.session_id),
+            public_key: hex::encode(party.pk.to_encoded_point(true).as_bytes()),
+            eth_address: party.eth_address.clone(),
+            depth: derivation.depth,
+            child_number: derivation.child_number,
+            parent_fingerprint: hex::encode(derivation.parent_fingerprint),
+            chain_code: hex::encode(derivation.chain_code),
+        }
+    }
+}
+
+impl fmt::Display for PartySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "party:              {}", self.party_index)?;
+        writeln!(
+            f,
+            "threshold:          {} of {}",
+            self.threshold, self.share_count
+        )?;
+        writeln!(f, "session id:         {}", self.session_id)?;
+        writeln!(f, "public key:         {}", self.public_key)?;
+        writeln!(f, "eth address:        {}", self.eth_address)?;
+        writeln!(f, "depth:              {}", self.depth)?;
+        writeln!(f, "child number:       {}", self.child_number)?;
+        writeln!(f, "parent fingerprint: {}", self.parent_fingerprint)?;
+        write!(f, "chain code:         {}", self.chain_code)
+    }
+}
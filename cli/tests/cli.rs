@@ -0,0 +1,311 @@
+use dkls23::protocols::Party;
+use dkls23::utilities::rng;
+use ffi_tss::k256::ecdsa::SigningKey;
+use libtss_cli::commands::{derive, dkg_phase, re_key, sign_phase, verify};
+use libtss_cli::files::{read, read_party, write, CliError};
+use libtss_cli::summary::PartySummary;
+use rand::Rng;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+fn temp_dir() -> PathBuf {
+    let name = hex::encode(rng::get_rng().gen::<[u8; 8]>());
+    std::env::temp_dir().join(format!("libtss-cli-{}", name))
+}
+
+fn output(dir: &Path, prefix: &str, index: u8) -> Value {
+    read(&dir.join(format!("{}-{}.json", prefix, index))).unwrap()
+}
+
+// Writes the input of each party, made of its part of `fields` and of
+// the messages routed to it, and returns the paths. Routed sealed
+// messages are added to those `fields` carries over from earlier phases.
+fn inputs(
+    dir: &Path,
+    name: &str,
+    parties: &[u8],
+    fields: impl Fn(u8) -> Value,
+) -> Vec<PathBuf> {
+    parties
+        .iter()
+        .map(|&index| {
+            let mut input = fields(index);
+            if let Ok(Value::Object(routed)) =
+                read(&dir.join(format!("to-party-{}.json", index)))
+            {
+                for (key, value) in routed {
+                    match (&mut input[&key], value) {
+                        (Value::Array(sealed), Value::Array(more))
+                            if key == "sealed" =>
+                        {
+                            sealed.extend(more)
+                        }
+                        (field, value) => *field = value,
+                    }
+                }
+            }
+            let name = format!("{}-in-{}.json", name, index);
+            write(dir, &name, &input).unwrap()
+        })
+        .collect()
+}
+
+// The identity keys of parties 1 and 2, each with the public key of the
+// other.
+fn identities() -> [Value; 2] {
+    let keys = [1, 2].map(|_| rng::get_rng().gen::<[u8; 32]>());
+    let public_key = |secret: &[u8; 32]| {
+        let key = SigningKey::from_slice(secret).unwrap();
+        hex::encode(key.verifying_key().to_encoded_point(true).as_bytes())
+    };
+    [
+        json!({
+            "key": hex::encode(keys[0]),
+            "peers": { "2": public_key(&keys[1]) },
+        }),
+        json!({
+            "key": hex::encode(keys[1]),
+            "peers": { "1": public_key(&keys[0]) },
+        }),
+    ]
+}
+
+// A 2-of-2 DKG run only through files, as on an air-gapped laptop. The
+// parties seal their messages if they are given an identity.
+fn run_dkg(dir: &Path, identity: &impl Fn(u8) -> Value) -> Vec<PathBuf> {
+    let session_id = rng::get_rng().gen::<[u8; 32]>().to_vec();
+    let session = |index: u8| {
+        json!({
+            "parameters": { "threshold": 2, "share_count": 2 },
+            "party_index": index,
+            "session_id": session_id,
+        })
+    };
+    let phase = |phase: u8| dir.join(format!("phase{}", phase));
+
+    let paths = inputs(
+        dir,
+        "dkg1",
+        &[1, 2],
+        |i| json!({ "session": session(i), "identity": identity(i) }),
+    );
+    dkg_phase(1, &paths, &phase(1)).unwrap();
+
+    let paths = inputs(
+        &phase(1),
+        "dkg2",
+        &[1, 2],
+        |i| json!({ "session": session(i), "identity": identity(i) }),
+    );
+    dkg_phase(2, &paths, &phase(2)).unwrap();
+
+    let paths = inputs(&phase(2), "dkg3", &[1, 2], |i| {
+        let phase2 = output(&phase(2), "party", i);
+        json!({
+            "session": session(i),
+            "zero_kept": phase2["zero_keep"],
+            "bip_kept": phase2["bip_keep"],
+            "identity": identity(i),
+        })
+    });
+    dkg_phase(3, &paths, &phase(3)).unwrap();
+
+    // The phase 2 messages are carried over to phase 4, sealed ones
+    // included.
+    let paths = inputs(&phase(3), "dkg4", &[1, 2], |i| {
+        let phase2 = output(&phase(2), "party", i);
+        let routed2 = output(&phase(2), "to-party", i);
+        let phase3 = output(&phase(3), "party", i);
+        json!({
+            "session": session(i),
+            "poly_point": phase2["poly_point"],
+            "proofs_commitments": routed2["proofs_commitments"],
+            "zero_received_phase2": routed2["zero_received_phase2"],
+            "bip_broadcast_2to4": routed2["bip_broadcast_2to4"],
+            "zero_kept": phase3["zero_keep"],
+            "mul_kept": phase3["mul_keep"],
+            "identity": identity(i),
+            "sealed": routed2.get("sealed").cloned().unwrap_or(json!([])),
+        })
+    });
+    dkg_phase(4, &paths, &phase(4)).unwrap()
+}
+
+// Runs the DKG and a signature through files and checks the signature.
+fn run_ceremony(dir: &Path, identity: impl Fn(u8) -> Value) {
+    let parties = run_dkg(dir, &identity);
+    assert_eq!(parties.len(), 2);
+    let party = |index: u8| read_party(&parties[index as usize - 1]).unwrap();
+    assert_eq!(party(1).pk, party(2).pk);
+
+    let message_hash = rng::get_rng().gen::<[u8; 32]>();
+    let sign_data = |index: u8| {
+        json!({
+            "sign_id": b"cli".to_vec(),
+            "counterparties": [3 - index],
+            "message_hash": message_hash,
+        })
+    };
+    let phase = |phase: u8| dir.join(format!("sign{}", phase));
+
+    let paths = inputs(dir, "sign1", &[1, 2], |i| {
+        json!({
+            "party": party(i),
+            "sign_data": sign_data(i),
+            "identity": identity(i),
+        })
+    });
+    sign_phase(1, &paths, &phase(1)).unwrap();
+
+    let paths = inputs(&phase(1), "sign2", &[1, 2], |i| {
+        let phase1 = output(&phase(1), "party", i);
+        json!({
+            "party": party(i),
+            "sign_data": sign_data(i),
+            "unique_kept": phase1["unique_keep"],
+            "kept": phase1["keep"],
+            "identity": identity(i),
+        })
+    });
+    sign_phase(2, &paths, &phase(2)).unwrap();
+
+    let paths = inputs(&phase(2), "sign3", &[1, 2], |i| {
+        let phase2 = output(&phase(2), "party", i);
+        json!({
+            "party": party(i),
+            "sign_data": sign_data(i),
+            "unique_kept": phase2["unique_keep"],
+            "kept": phase2["keep"],
+            "identity": identity(i),
+        })
+    });
+    sign_phase(3, &paths, &phase(3)).unwrap();
+
+    let paths = inputs(&phase(3), "sign4", &[1, 2], |i| {
+        json!({
+            "party": party(i),
+            "sign_data": sign_data(i),
+            "normalize": true,
+        })
+    });
+    sign_phase(4, &paths, &phase(4)).unwrap();
+
+    let signature = output(&phase(4), "party", 1);
+    assert_eq!(signature, output(&phase(4), "party", 2));
+    let verify_in = json!({
+        "msg": message_hash,
+        "pk": party(1).pk,
+        "x_coord": output(&phase(3), "to-party", 1)["x_coord"],
+        "signature": signature["signature"],
+    });
+    let path = write(dir, "verify.json", &verify_in).unwrap();
+    assert_eq!(verify(&path), Ok(true));
+}
+
+#[test]
+fn test_dkg_sign_and_verify() {
+    let dir = temp_dir();
+    run_ceremony(&dir, |_| Value::Null);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_sealed_ceremony() {
+    let dir = temp_dir();
+    let identities = identities();
+    run_ceremony(&dir, |i| identities[i as usize - 1].clone());
+
+    // The point-to-point messages were only routed sealed.
+    let routed = output(&dir.join("phase3"), "to-party", 1);
+    assert_eq!(routed["zero_received_phase3"], json!([]));
+    assert_eq!(routed["mul_received"], json!([]));
+    assert_eq!(routed["sealed"].as_array().unwrap().len(), 2);
+    let routed = output(&dir.join("sign1"), "to-party", 2);
+    assert_eq!(routed["received"], json!([]));
+    assert_eq!(routed["sealed"][0]["sender"], 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_re_key_derive_and_inspect() {
+    let dir = temp_dir();
+    let re_key_in = json!({
+        "parameters": { "threshold": 2, "share_count": 3 },
+        "session_id": rng::get_rng().gen::<[u8; 32]>().to_vec(),
+        "secret_key": rng::get_rng().gen::<[u8; 32]>(),
+        "option_chain_code": null,
+    });
+    let path = write(&dir, "re_key.json", &re_key_in).unwrap();
+    let parties = re_key(&path, &dir.join("parties")).unwrap();
+    assert_eq!(parties.len(), 3);
+
+    let derived = derive("m/0/1", &parties, &dir.join("derived")).unwrap();
+    let party: Party = read_party(&derived[0]).unwrap();
+    let summary = PartySummary::new(&party);
+    assert_eq!(summary.depth, 2);
+    assert_eq!(summary.child_number, 1);
+    assert_eq!(
+        summary.public_key,
+        PartySummary::new(&read_party(&derived[2]).unwrap()).public_key
+    );
+
+    // Neither form of the summary carries the key share.
+    let poly_point = serde_json::to_value(party.poly_point).unwrap();
+    let poly_point = poly_point.as_str().unwrap();
+    assert!(!serde_json::to_string(&summary)
+        .unwrap()
+        .contains(poly_point));
+    assert!(!summary.to_string().contains(poly_point));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_errors() {
+    let dir = temp_dir();
+    let missing = dir.join("missing.json");
+    assert!(matches!(
+        dkg_phase(1, &[missing], &dir),
+        Err(CliError::Io { .. })
+    ));
+
+    let garbage = dir.join("garbage.json");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&garbage, "{").unwrap();
+    assert!(matches!(
+        dkg_phase(1, &[garbage], &dir),
+        Err(CliError::Json { .. })
+    ));
+
+    let session = json!({
+        "session": {
+            "parameters": { "threshold": 2, "share_count": 2 },
+            "party_index": 1,
+            "session_id": rng::get_rng().gen::<[u8; 32]>().to_vec(),
+        },
+    });
+    let path = write(&dir, "dkg1.json", &session).unwrap();
+    assert!(matches!(
+        dkg_phase(1, &[path.clone(), path.clone()], &dir),
+        Err(CliError::Input { .. })
+    ));
+
+    // Outputs are only for the owner, and are never overwritten.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    assert!(matches!(
+        write(&dir, "dkg1.json", &session),
+        Err(CliError::Io { .. })
+    ));
+    assert!(matches!(
+        dkg_phase(5, &[path], &dir),
+        Err(CliError::Input { .. })
+    ));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
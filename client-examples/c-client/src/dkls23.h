@@ -16,6 +16,24 @@ const char* dkls_sign_phase4(const char* phase4_in);
 // Veriy ECDSA signature
 const char* dkls_verify_ecdsa_signature(const char* verify_in);
 
+// Full runs over callbacks of the host
+#include <stddef.h>
+#include <stdint.h>
+
+#define DKLS_RECV_OK 0
+#define DKLS_RECV_EMPTY 1
+#define DKLS_RECV_TOO_SMALL 2
+
+typedef struct {
+    void* user_data;
+    int32_t (*send)(void* user_data, uint8_t receiver, const uint8_t* bytes, size_t length);
+    int32_t (*recv)(void* user_data, uint32_t timeout_ms, uint8_t* sender, uint8_t* buffer, size_t capacity, size_t* length);
+    int32_t (*cancelled)(void* user_data);
+} dkls_callbacks;
+
+const char* dkls_dkg_run(const char* run_in, const dkls_callbacks* callbacks);
+const char* dkls_sign_run(const char* run_in, const dkls_callbacks* callbacks);
+
 void dkg(const char* ins[], const char* outs[]);
 void sign(const char* ins[], const char* outs[]);
 void verify(const char* ins[], const char* outs[]);
@@ -23,12 +23,64 @@ extern const char* dkls_sign_phase4(const char* data);
 
 extern const char* dkls_verify_ecdsa_signature(const char* data);
 
+extern const char* dkls_abort_message(const char* data);
+extern const char* dkls_verify_abort(const char* data);
+
+extern const char* dkls_session_id_commit(const char* data);
+extern const char* dkls_session_id_reveal(const char* data);
+extern const char* dkls_session_id_finish(const char* data);
+
+extern const char* dkls_pair_commit(const char* data);
+extern const char* dkls_pair_reveal(const char* data);
+extern const char* dkls_pair_finish(const char* data);
+
+extern const char* dkls_dkg_echo(const char* data);
+extern const char* dkls_sign_echo(const char* data);
+
+extern const char* dkls_route_dkg_phase1(const char* data);
+extern const char* dkls_route_dkg_phase2(const char* data);
+extern const char* dkls_route_dkg_phase3(const char* data);
+extern const char* dkls_route_sign_phase1(const char* data);
+extern const char* dkls_route_sign_phase2(const char* data);
+extern const char* dkls_route_sign_phase3(const char* data);
+
+extern const char* dkls_resume_dkg(const char* data);
+extern const char* dkls_resume_sign(const char* data);
+extern const char* dkls_resume_receive(const char* data);
+extern const char* dkls_resume_advance(const char* data);
+extern const char* dkls_resume_status(const char* data);
+extern const char* dkls_resume_abort(const char* data);
+extern const char* dkls_resume_receive_abort(const char* data);
+extern const char* dkls_resume_finish(const char* data);
+extern const char* dkls_resume_remove(const char* data);
+
 extern const char* dkls_derive_from_path(const char* data);
 extern const char* dkls_party_derive_from_path(const char* data);
 extern const char* dkls_derive_child(const char* data);
 extern const char* dkls_party_derive_child(const char* data);
+extern const char* dkls_parse_path(const char* data);
+
+extern const char* dkls_addresses(const char* data);
+extern const char* dkls_party_addresses(const char* data);
+
+extern const char* dkls_xpub(const char* data);
+extern const char* dkls_party_xpub(const char* data);
+extern const char* dkls_parse_xpub(const char* data);
+extern const char* dkls_public_derive_from_path(const char* data);
+extern const char* dkls_public_derive_child(const char* data);
+extern const char* dkls_derive_batch(const char* data);
 
 extern const char* dkls_re_key(const char* data);
+extern const char* dkls_import_wallet(const char* data);
+
+extern const char* musig2_key_sort(const char* data);
+extern const char* musig2_key_agg(const char* data);
+extern const char* musig2_nonce_gen(const char* data);
+extern const char* musig2_nonce_agg(const char* data);
+extern const char* musig2_partial_sign(const char* data);
+extern const char* musig2_partial_verify(const char* data);
+extern const char* musig2_partial_sig_agg(const char* data);
+extern const char* musig2_verify_schnorr_signature(const char* data);
 */
 import "C"
 import (
@@ -87,6 +139,112 @@ func VerifyECDSASignature(data string) string {
     return callFFIFunc(C.ffi_func(C.dkls_verify_ecdsa_signature), data);
 }
 
+// Abort
+func AbortMessage(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_abort_message), data);
+}
+
+func VerifyAbort(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_verify_abort), data);
+}
+
+// Session ids
+func SessionIdCommit(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_session_id_commit), data);
+}
+
+func SessionIdReveal(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_session_id_reveal), data);
+}
+
+func SessionIdFinish(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_session_id_finish), data);
+}
+
+// Pairing
+func PairCommit(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_pair_commit), data);
+}
+
+func PairReveal(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_pair_reveal), data);
+}
+
+func PairFinish(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_pair_finish), data);
+}
+
+// Echo broadcast
+func DkgEcho(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_dkg_echo), data);
+}
+
+func SignEcho(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_sign_echo), data);
+}
+
+// Routing
+func RouteDkgPhase1(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_dkg_phase1), data);
+}
+
+func RouteDkgPhase2(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_dkg_phase2), data);
+}
+
+func RouteDkgPhase3(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_dkg_phase3), data);
+}
+
+func RouteSignPhase1(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_sign_phase1), data);
+}
+
+func RouteSignPhase2(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_sign_phase2), data);
+}
+
+func RouteSignPhase3(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_route_sign_phase3), data);
+}
+
+// Resumable sessions
+func ResumeDkg(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_dkg), data);
+}
+
+func ResumeSign(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_sign), data);
+}
+
+func ResumeReceive(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_receive), data);
+}
+
+func ResumeAdvance(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_advance), data);
+}
+
+func ResumeStatus(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_status), data);
+}
+
+func ResumeAbort(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_abort), data);
+}
+
+func ResumeReceiveAbort(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_receive_abort), data);
+}
+
+func ResumeFinish(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_finish), data);
+}
+
+func ResumeRemove(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_resume_remove), data);
+}
+
 // Derivation
 func DeriveFromPath(data string) string {
     return callFFIFunc(C.ffi_func(C.dkls_derive_from_path), data);
@@ -104,7 +262,83 @@ func PartyDeriveChild(data string) string {
     return callFFIFunc(C.ffi_func(C.dkls_party_derive_child), data);
 }
 
+func ParsePath(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_parse_path), data);
+}
+
+// Addresses
+func Addresses(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_addresses), data);
+}
+
+func PartyAddresses(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_party_addresses), data);
+}
+
+// Extended public keys
+func Xpub(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_xpub), data);
+}
+
+func PartyXpub(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_party_xpub), data);
+}
+
+func ParseXpub(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_parse_xpub), data);
+}
+
+func PublicDeriveFromPath(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_public_derive_from_path), data);
+}
+
+func PublicDeriveChild(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_public_derive_child), data);
+}
+
+func DeriveBatch(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_derive_batch), data);
+}
+
 // Re-key
 func ReKey(data string) string {
     return callFFIFunc(C.ffi_func(C.dkls_re_key), data);
 }
+
+// Import
+func ImportWallet(data string) string {
+    return callFFIFunc(C.ffi_func(C.dkls_import_wallet), data);
+}
+
+// MuSig2
+func MuSig2KeySort(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_key_sort), data);
+}
+
+func MuSig2KeyAgg(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_key_agg), data);
+}
+
+func MuSig2NonceGen(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_nonce_gen), data);
+}
+
+func MuSig2NonceAgg(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_nonce_agg), data);
+}
+
+func MuSig2PartialSign(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_partial_sign), data);
+}
+
+func MuSig2PartialVerify(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_partial_verify), data);
+}
+
+func MuSig2PartialSigAgg(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_partial_sig_agg), data);
+}
+
+func MuSig2VerifySchnorrSignature(data string) string {
+    return callFFIFunc(C.ffi_func(C.musig2_verify_schnorr_signature), data);
+}
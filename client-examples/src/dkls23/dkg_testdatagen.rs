@@ -46,6 +46,9 @@ pub fn dkg_input_gen(input_filename: &str, output_filename: &str) {
     inputs.push(
         serde_json::to_string(&Phase1In {
             session: all_data[0].clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
         })
         .unwrap(),
     );
@@ -84,6 +87,10 @@ pub fn dkg_input_gen(input_filename: &str, output_filename: &str) {
         serde_json::to_string(&Phase2In {
             session: all_data[0].clone(),
             poly_fragments: poly_fragments[0].clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
+            sealed: Vec::new(),
         })
         .unwrap(),
     );
@@ -154,6 +161,9 @@ pub fn dkg_input_gen(input_filename: &str, output_filename: &str) {
             session: all_data[0].clone(),
             zero_kept: zero_kept_2to3[0].clone(),
             bip_kept: bip_kept_2to3[0].clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
         })
         .unwrap(),
     );
@@ -241,6 +251,12 @@ pub fn dkg_input_gen(input_filename: &str, output_filename: &str) {
             mul_received: mul_received_3to4[0].clone(),
             bip_broadcast_2to4: bip_broadcast_2to4.clone(),
             bip_broadcast_3to4: bip_broadcast_3to4.clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
+            sealed: Vec::new(),
+            echo: false,
+            echoes: Vec::new(),
         })
         .unwrap(),
     );
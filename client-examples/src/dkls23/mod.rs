@@ -1,4 +1,20 @@
 pub mod dkg_testdatagen;
 pub mod sign_testdatagen;
+pub mod test_abort;
+pub mod test_address;
+pub mod test_bip32;
+pub mod test_blame;
 pub mod test_dkg;
+pub mod test_echo;
+pub mod test_import;
+pub mod test_pairing;
+pub mod test_reconstruct;
+pub mod test_resume;
+pub mod test_router;
+pub mod test_run;
+pub mod test_seal;
+pub mod test_session;
+pub mod test_session_id;
 pub mod test_sign;
+pub mod test_sign_at_path;
+pub mod test_transport;
@@ -75,6 +75,10 @@ pub fn sign_input_gen(input_filename: &str, output_filename: &str) {
         serde_json::to_string(&Phase1In {
             party: parties[0].clone(),
             sign_data: all_data[&1].clone(),
+            derivation_path: None,
+            transcript: None,
+            id_nonce: None,
+            identity: None,
         })
         .unwrap(),
     );
@@ -128,9 +132,14 @@ pub fn sign_input_gen(input_filename: &str, output_filename: &str) {
         serde_json::to_string(&Phase2In {
             party: parties[0].clone(),
             sign_data: all_data[&1].clone(),
+            derivation_path: None,
             unique_kept: unique_kept_1to2[&1].clone(),
             kept: kept_1to2[&1].clone(),
             received: received_1to2[&1].clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
+            sealed: Vec::new(),
         })
         .unwrap(),
     );
@@ -192,9 +201,14 @@ pub fn sign_input_gen(input_filename: &str, output_filename: &str) {
         serde_json::to_string(&Phase3In {
             party: parties[0].clone(),
             sign_data: all_data[&1].clone(),
+            derivation_path: None,
             unique_kept: unique_kept_2to3[&1].clone(),
             kept: kept_2to3[&1].clone(),
             received: received_2to3[&1].clone(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
+            sealed: Vec::new(),
         })
         .unwrap(),
     );
@@ -243,9 +257,16 @@ pub fn sign_input_gen(input_filename: &str, output_filename: &str) {
         serde_json::to_string(&Phase4In {
             party: parties[0].clone(),
             sign_data: all_data[&1].clone(),
+            derivation_path: None,
             x_coord: x_coord.clone(),
             received: broadcast_3to4.clone(),
+            broadcasts: BTreeMap::new(),
             normalize: true,
+            echo: false,
+            echoes: Vec::new(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
         })
         .unwrap(),
     );
@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::Parameters;
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::abort::{
+        dkls_abort_message, dkls_verify_abort,
+    };
+    use ffi_tss::dkls23::protocols::resume::{
+        dkls_resume_abort, dkls_resume_advance, dkls_resume_dkg,
+        dkls_resume_receive_abort, dkls_resume_status, ResumeStatusOut,
+    };
+    use ffi_tss::dkls23::session::Closure;
+    use ffi_tss::k256::ecdsa::SigningKey;
+    use rand::Rng;
+    use serde_json::{json, Value};
+    use std::path::PathBuf;
+
+    // An identity key pair in hex.
+    fn identity() -> (String, String) {
+        let secret = rng::get_rng().gen::<[u8; 32]>();
+        let key = SigningKey::from_slice(&secret).unwrap();
+        let public_key = key.verifying_key().to_encoded_point(true);
+        (hex::encode(secret), hex::encode(public_key.as_bytes()))
+    }
+
+    fn temp_dir() -> PathBuf {
+        let name = hex::encode(rng::get_rng().gen::<[u8; 8]>());
+        std::env::temp_dir().join(format!("libtss-{}", name))
+    }
+
+    fn session(party_index: u8, session_id: &[u8]) -> SessionData {
+        SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 3,
+            },
+            party_index,
+            session_id: session_id.to_vec(),
+        }
+    }
+
+    #[test]
+    pub fn test_dkls_abort_message() {
+        let (key, public_key) = identity();
+        let (_, other_public_key) = identity();
+
+        let abort = call(
+            dkls_abort_message,
+            json!({
+                "session_id": [1, 2, 3],
+                "sender": 2,
+                "phase": 1,
+                "reason": "Timed out",
+                "key": key,
+            }),
+        );
+        let json_out = call(
+            dkls_verify_abort,
+            json!({ "abort": abort, "public_key": public_key }),
+        );
+        assert_eq!(json_out["valid"], true);
+
+        // Another key or a changed message does not verify.
+        let json_out = call(
+            dkls_verify_abort,
+            json!({ "abort": abort, "public_key": other_public_key }),
+        );
+        assert_eq!(json_out["valid"], false);
+        let mut changed = abort.clone();
+        changed["sender"] = json!(3);
+        let json_out = call(
+            dkls_verify_abort,
+            json!({ "abort": changed, "public_key": public_key }),
+        );
+        assert_eq!(json_out["valid"], false);
+        println!("DKLs23::AbortMessage: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_deadline() {
+        let dir = temp_dir();
+        let key = hex::encode(rng::get_rng().gen::<[u8; 32]>());
+        let store = json!({ "dir": dir, "key": key });
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+
+        // No time at all for the first phase.
+        call(
+            dkls_resume_dkg,
+            json!({
+                "store": store,
+                "id": "expired",
+                "session": session(1, &session_id),
+                "timeouts": { "phases": [0] },
+            }),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let json_out = call(
+            dkls_resume_status,
+            json!({ "store": store, "id": "expired" }),
+        );
+        let status: ResumeStatusOut = serde_json::from_value(json_out).unwrap();
+        assert!(matches!(
+            status.closure,
+            Some(Closure::Expired { phase: 0, .. })
+        ));
+        assert!(!status.ready);
+
+        let json_out = call(
+            dkls_resume_advance,
+            json!({ "store": store, "id": "expired" }),
+        );
+        assert!(json_out["error"]["Session"]["Closed"].is_object());
+
+        std::fs::remove_dir_all(dir).unwrap();
+        println!("DKLs23::SessionDeadline: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_abort() {
+        let dir = temp_dir();
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let stores: Vec<Value> = (0..2)
+            .map(|_| {
+                let key = hex::encode(rng::get_rng().gen::<[u8; 32]>());
+                json!({ "dir": dir.join(key.clone()), "key": key })
+            })
+            .collect();
+        for (i, store) in stores.iter().enumerate() {
+            call(
+                dkls_resume_dkg,
+                json!({
+                    "store": store,
+                    "id": "dkg",
+                    "session": session(i as u8 + 1, &session_id),
+                }),
+            );
+        }
+        let identities = [identity(), identity()];
+
+        // Party 2 gives up and tells party 1.
+        let json_out = call(
+            dkls_resume_abort,
+            json!({
+                "store": stores[1],
+                "id": "dkg",
+                "key": identities[1].0,
+                "reason": "User cancelled",
+            }),
+        );
+        let abort = json_out["abort"].clone();
+
+        // Signed with another key, the abort is rejected.
+        let json_out = call(
+            dkls_resume_receive_abort,
+            json!({
+                "store": stores[0],
+                "id": "dkg",
+                "abort": abort,
+                "public_key": identities[0].1,
+            }),
+        );
+        assert_eq!(json_out["error"]["Session"]["InvalidAbort"]["sender"], 2);
+
+        let json_out = call(
+            dkls_resume_receive_abort,
+            json!({
+                "store": stores[0],
+                "id": "dkg",
+                "abort": abort,
+                "public_key": identities[1].1,
+            }),
+        );
+        let status: ResumeStatusOut = serde_json::from_value(json_out).unwrap();
+        assert_eq!(
+            status.closure,
+            Some(Closure::Aborted {
+                index: 2,
+                reason: String::from("User cancelled"),
+            })
+        );
+
+        // Neither party goes on.
+        for store in &stores {
+            let json_out = call(
+                dkls_resume_advance,
+                json!({ "store": store, "id": "dkg" }),
+            );
+            assert!(json_out["error"]["Session"]["Closed"].is_object());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+        println!("DKLs23::SessionAbort: Passed!");
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::derivation::DerivData;
+    use ffi_tss::dkls23::protocols::address::{dkls_addresses, AddressesIn};
+    use ffi_tss::dkls23::protocols::batch::{derive_batch, BatchScan};
+    use ffi_tss::dkls23::utilities::address::{
+        p2wpkh_address, AddressKind, Addresses, Network,
+    };
+    use ffi_tss::dkls23::utilities::bip32::ExtendedPublicKey;
+    use ffi_tss::k256::{AffinePoint, Scalar};
+
+    // Test vector 1 from BIP-32, at m/0'.
+    const XPUB: &str = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+
+    #[test]
+    pub fn test_dkls_addresses() {
+        // Addresses of the key whose secret is one, i.e. the generator.
+        let addresses =
+            Addresses::new(&AffinePoint::GENERATOR, Some("cosmos")).unwrap();
+
+        assert_eq!(
+            addresses.pk_compressed,
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        assert_eq!(
+            addresses.eth_address,
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+        assert_eq!(
+            addresses.btc_mainnet.p2pkh,
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+        assert_eq!(
+            addresses.btc_mainnet.p2sh_p2wpkh,
+            "3JvL6Ymt8MVWiCNHC7oWU6nLeHNJKLZGLN"
+        );
+        assert_eq!(
+            addresses.btc_mainnet.p2wpkh,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        assert_eq!(
+            addresses.btc_testnet.p2pkh,
+            "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r"
+        );
+        assert_eq!(
+            addresses.btc_testnet.p2sh_p2wpkh,
+            "2NAUYAHhujozruyzpsFRP63mbrdaU5wnEpN"
+        );
+        assert_eq!(
+            addresses.btc_testnet.p2wpkh,
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+        );
+        assert_eq!(
+            addresses.cosmos_address.unwrap(),
+            "cosmos1w508d6qejxtdg4y5r3zarvary0c5xw7k6ah60c"
+        );
+
+        // The same key hash under another chain prefix.
+        let osmosis = AddressKind::Cosmos {
+            prefix: String::from("osmo"),
+        };
+        assert_eq!(
+            osmosis.address(&AffinePoint::GENERATOR).unwrap(),
+            "osmo1w508d6qejxtdg4y5r3zarvary0c5xw7kjxy2e2"
+        );
+        let invalid = AddressKind::Cosmos {
+            prefix: String::from("bad prefix"),
+        };
+        assert!(invalid.address(&AffinePoint::GENERATOR).is_err());
+
+        // The export returns the error instead of aborting.
+        let addresses_in = AddressesIn {
+            data: DerivData {
+                depth: 0,
+                child_number: 0,
+                parent_fingerprint: [0; 4],
+                poly_point: Scalar::ONE,
+                pk: AffinePoint::GENERATOR,
+                chain_code: [0; 32],
+            },
+            cosmos_prefix: Some(String::from("bad prefix")),
+        };
+        let json_out = call_ffi(
+            dkls_addresses,
+            &serde_json::to_string(&addresses_in).unwrap(),
+        );
+        let error_out: serde_json::Value =
+            serde_json::from_str(&json_out).unwrap();
+        assert_eq!(
+            error_out["error"]["Other"]["description"],
+            "Invalid bech32 prefix!"
+        );
+        println!("DKLs23::Addresses: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_derive_batch() {
+        let (account, _) = ExtendedPublicKey::decode(XPUB).unwrap();
+        let base = account.derive_from_path("m/0").unwrap();
+        let kind = AddressKind::P2wpkh {
+            network: Network::Mainnet,
+        };
+
+        let entries = derive_batch(
+            &base,
+            &BatchScan::Range { start: 2, count: 5 },
+            &kind,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 5);
+        for entry in &entries {
+            let path = format!("m/0/{}", entry.index);
+            let child = account.derive_from_path(&path).unwrap();
+            assert_eq!(
+                entry.address,
+                p2wpkh_address(&child.pk, Network::Mainnet)
+            );
+        }
+
+        // With a gap limit of 5 and index 4 used, the scan stops at 9.
+        let scan = BatchScan::GapLimit {
+            start: 0,
+            gap_limit: 5,
+            used: vec![entries[2].address.clone()],
+        };
+        let scanned = derive_batch(&base, &scan, &kind).unwrap();
+        let indices: Vec<u32> = scanned.iter().map(|e| e.index).collect();
+        assert_eq!(indices, (0..=9).collect::<Vec<u32>>());
+
+        let hardened = BatchScan::Range {
+            start: 0x7FFFFFFF,
+            count: 2,
+        };
+        assert!(derive_batch(&base, &hardened, &kind).is_err());
+        println!("DKLs23::Batch derivation: Passed!");
+    }
+}
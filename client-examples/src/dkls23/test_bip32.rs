@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::{re_key::re_key, Parameters};
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::public_derivation::{
+        dkls_public_derive_from_path, PublicDeriveFromPathIn,
+        PublicDeriveFromPathOut, PublicKeySource,
+    };
+    use ffi_tss::dkls23::protocols::xpub::dkls_parse_xpub;
+    use ffi_tss::dkls23::utilities::bip32::{
+        ExtendedKeyVersion, ExtendedPrivateKey, ExtendedPublicKey,
+        XPRV_VERSION, XPUB_VERSION,
+    };
+    use ffi_tss::dkls23::utilities::path::{
+        parse_hardened_path, validate_path, DerivationError,
+    };
+    use ffi_tss::k256::{elliptic_curve::Field, Scalar};
+    use rand::Rng;
+
+    // Test vector 1 from BIP-32.
+    const SEED: &str = "000102030405060708090a0b0c0d0e0f";
+    const XPRV: &str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+    const XPUB: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    // Test vector 2 from BIP-32, which has a non-hardened first child.
+    const SEED_2: &str = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+    const XPUB_2_M_0: &str = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH";
+
+    #[test]
+    pub fn test_dkls_extended_keys() {
+        let seed = hex::decode(SEED).unwrap();
+        let xprv = ExtendedPrivateKey::from_seed(&seed).unwrap();
+        assert_eq!(xprv.encode(XPRV_VERSION), XPRV);
+        assert_eq!(xprv.public_key().encode(XPUB_VERSION), XPUB);
+
+        let (decoded, version) = ExtendedPublicKey::decode(XPUB).unwrap();
+        assert_eq!(
+            ExtendedKeyVersion::from_bytes(version),
+            ExtendedKeyVersion::Xpub
+        );
+        assert_eq!(decoded.pk, xprv.public_key().pk);
+        assert_eq!(decoded.chain_code, xprv.chain_code);
+
+        // A private key is not accepted as a public one.
+        assert!(ExtendedPublicKey::decode(XPRV).is_err());
+
+        // The export returns the error instead of aborting.
+        let json_out = call_ffi(
+            dkls_parse_xpub,
+            &serde_json::json!({ "xpub": XPRV }).to_string(),
+        );
+        let error_out: serde_json::Value =
+            serde_json::from_str(&json_out).unwrap();
+        assert_eq!(
+            error_out["error"]["Other"]["description"],
+            "Not an extended public key!"
+        );
+        println!("DKLs23::Extended keys: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_public_derivation() {
+        let seed = hex::decode(SEED_2).unwrap();
+        let xpub = ExtendedPrivateKey::from_seed(&seed).unwrap().public_key();
+        let child = xpub.derive_from_path("m/0").unwrap();
+        assert_eq!(child.encode(XPUB_VERSION), XPUB_2_M_0);
+        assert!(xpub.derive_from_path("m/0'").is_err());
+
+        // Public derivation agrees with the derivation done by the parties.
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 2,
+        };
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let secret_key = Scalar::random(rng::get_rng());
+        let chain_code = rng::get_rng().gen::<[u8; 32]>();
+        let parties =
+            re_key(&parameters, &session_id, &secret_key, Some(chain_code));
+
+        let path = "m/0/5/1";
+        let party = parties[0].derive_from_path(path).unwrap();
+        let key = ExtendedPublicKey::from(&parties[0].derivation_data)
+            .derive_from_path(path)
+            .unwrap();
+        assert_eq!(key.pk, party.derivation_data.pk);
+        assert_eq!(key.chain_code, party.derivation_data.chain_code);
+        assert_eq!(key.depth, party.derivation_data.depth);
+        assert_eq!(key.child_number, party.derivation_data.child_number);
+        assert_eq!(
+            key.parent_fingerprint,
+            party.derivation_data.parent_fingerprint
+        );
+
+        // The export gives the same key when the source is a party.
+        let derive_in = PublicDeriveFromPathIn {
+            source: PublicKeySource {
+                party: Some(parties[0].clone()),
+                xpub: None,
+                pk: None,
+                chain_code: None,
+            },
+            path: String::from(path),
+            cosmos_prefix: None,
+        };
+        let json_out = call_ffi(
+            dkls_public_derive_from_path,
+            &serde_json::to_string(&derive_in).unwrap(),
+        );
+        let derive_out: PublicDeriveFromPathOut =
+            serde_json::from_str(&json_out).unwrap();
+        assert_eq!(derive_out.key.pk, party.derivation_data.pk);
+        assert_eq!(derive_out.key.chain_code, party.derivation_data.chain_code);
+        println!("DKLs23::Public derivation: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_path_errors() {
+        assert_eq!(
+            parse_hardened_path("m/44'/60h/0").unwrap(),
+            vec![0x8000002C, 0x8000003C, 0]
+        );
+        assert!(validate_path("m", 0, false).unwrap().is_empty());
+
+        let malformed = |segment: &str| DerivationError::MalformedPath {
+            segment: String::from(segment),
+        };
+        assert_eq!(validate_path("0/1", 0, false), Err(malformed("0")));
+        assert_eq!(validate_path("m/1/", 0, false), Err(malformed("")));
+        assert_eq!(validate_path("m/-1", 0, false), Err(malformed("-1")));
+        assert_eq!(validate_path("m/1''", 0, true), Err(malformed("1''")));
+
+        assert_eq!(
+            validate_path("m/0/44'", 0, false),
+            Err(DerivationError::HardenedNotSupported { index: 0x8000002C })
+        );
+        assert_eq!(
+            validate_path("m/2147483648", 0, false),
+            Err(DerivationError::IndexOutOfRange {
+                segment: String::from("2147483648"),
+            })
+        );
+        assert_eq!(
+            validate_path("m/0/1", 254, false),
+            Err(DerivationError::DepthOverflow {
+                depth: 254,
+                length: 2,
+            })
+        );
+
+        let (xpub, _) = ExtendedPublicKey::decode(XPUB).unwrap();
+        assert_eq!(
+            xpub.derive_child(0x80000000).unwrap_err(),
+            DerivationError::HardenedNotSupported { index: 0x80000000 }
+        );
+        println!("DKLs23::Path errors: Passed!");
+    }
+}
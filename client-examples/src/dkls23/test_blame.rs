@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use dkls23::protocols::Abort;
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::session::blame::{BlameReport, Evidence, FailedCheck};
+    use ffi_tss::dkls23::session::seal::{Identity, IdentityKeys};
+    use ffi_tss::dkls23::session::SessionError;
+    use ffi_tss::k256::ecdsa::SigningKey;
+    use rand::Rng;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn abort(index: u8, description: &str) -> Abort {
+        Abort {
+            index,
+            description: String::from(description),
+        }
+    }
+
+    // The secret identity keys of parties 1 and 3, and their public keys.
+    fn identities() -> (BTreeMap<u8, String>, BTreeMap<u8, String>) {
+        let secrets: BTreeMap<u8, String> = [1, 3]
+            .into_iter()
+            .map(|i| (i, hex::encode(rng::get_rng().gen::<[u8; 32]>())))
+            .collect();
+        let peers = secrets
+            .iter()
+            .map(|(&i, secret)| {
+                let bytes = hex::decode(secret).unwrap();
+                let key = SigningKey::from_slice(&bytes).unwrap();
+                let public_key = key.verifying_key().to_encoded_point(true);
+                (i, hex::encode(public_key.as_bytes()))
+            })
+            .collect();
+        (secrets, peers)
+    }
+
+    fn identity(
+        index: u8,
+        secrets: &BTreeMap<u8, String>,
+        peers: &BTreeMap<u8, String>,
+    ) -> Identity {
+        let keys = IdentityKeys {
+            key: secrets[&index].clone(),
+            peers: peers
+                .iter()
+                .filter(|(&peer, _)| peer != index)
+                .map(|(&peer, public_key)| (peer, public_key.clone()))
+                .collect(),
+            pinned: BTreeMap::new(),
+        };
+        Identity::new(index, &keys).unwrap()
+    }
+
+    #[test]
+    pub fn test_dkls_blame_report() {
+        let (secrets, peers) = identities();
+        let party1 = identity(1, &secrets, &peers);
+        let party3 = identity(3, &secrets, &peers);
+
+        // Party 3 seals its commitment for party 1, whose check fails.
+        let sent = json!({ "commitment": "00" });
+        let sealed = party3.seal(b"sign", 1, 1, "sign", &sent).unwrap();
+        let evidence = vec![Evidence::new(3, 1, "sign", &sent)];
+        let mut report = BlameReport::new(
+            b"sign",
+            2,
+            FailedCheck::Signing,
+            abort(1, "Consistency check failed!"),
+            &[3],
+            evidence,
+        );
+        assert_eq!(report.reporter, 1);
+        assert_eq!(report.accused, Some(3));
+        assert_eq!(report.phase, 2);
+        assert_eq!(report.check, FailedCheck::Signing);
+        assert!(report.message_hash.is_some());
+
+        // Without the sealed message, the reporter could have made it up.
+        assert!(!report.verify_evidence(&peers));
+        report.disclose(&party1, &[sealed]);
+        assert!(report.evidence[0].key.is_some());
+        assert!(report.verify_evidence(&peers));
+
+        // Changed evidence no longer matches the hash, nor the seal.
+        let mut changed = report.clone();
+        changed.evidence[0].message = json!({ "commitment": "01" });
+        assert!(!changed.verify_evidence(&peers));
+        let rehashed = BlameReport::new(
+            b"sign",
+            2,
+            FailedCheck::Signing,
+            abort(1, "Consistency check failed!"),
+            &[3],
+            changed.evidence,
+        );
+        assert!(!rehashed.verify_evidence(&peers));
+
+        // Nor does it verify under another key for the sender.
+        let mut wrong = peers.clone();
+        wrong.insert(3, peers[&1].clone());
+        assert!(!report.verify_evidence(&wrong));
+
+        // The report goes out as JSON with the session error.
+        let error = SessionError::Aborted {
+            blame: report.clone(),
+        };
+        let json_out = serde_json::to_value(&error).unwrap();
+        assert_eq!(json_out["Aborted"]["blame"]["accused"], 3);
+        println!("DKLs23::BlameReport: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_blame_unknown_party() {
+        // With several counterparties, DKLs23 does not say which one.
+        let evidence = vec![
+            Evidence::new(2, 1, "sign", &json!({ "commitment": "00" })),
+            Evidence::new(3, 1, "sign", &json!({ "commitment": "01" })),
+        ];
+        let report = BlameReport::new(
+            b"sign",
+            2,
+            FailedCheck::Signing,
+            abort(1, "Party 3 cheated when sending its commitment!"),
+            &[2, 3],
+            evidence,
+        );
+        assert_eq!(report.accused, None);
+        assert_eq!(report.evidence.len(), 2);
+
+        // A failed signature has no messages to show.
+        let report = BlameReport::new(
+            b"sign",
+            4,
+            FailedCheck::Signature,
+            abort(1, "Signature verification failed"),
+            &[2],
+            Vec::new(),
+        );
+        assert_eq!(report.message_hash, None);
+        assert!(!report.verify_evidence(&BTreeMap::new()));
+        println!("DKLs23::BlameUnknownParty: Passed!");
+    }
+}
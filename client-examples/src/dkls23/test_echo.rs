@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+    use dkls23::protocols::signing::Broadcast3to4;
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::session::echo::{
+        check, echo, sign_broadcasts, Echo, EchoError,
+    };
+    use ffi_tss::dkls23::session::seal::{Identity, IdentityKeys, SealError};
+    use ffi_tss::k256::ecdsa::SigningKey;
+    use ffi_tss::k256::Scalar;
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    const SESSION_ID: &[u8] = b"session";
+
+    fn identities(share_count: u8) -> BTreeMap<u8, Identity> {
+        let keys: BTreeMap<u8, SigningKey> = (1..=share_count)
+            .map(|i| {
+                let secret = rng::get_rng().gen::<[u8; 32]>();
+                (i, SigningKey::from_slice(&secret).unwrap())
+            })
+            .collect();
+        keys.iter()
+            .map(|(&i, key)| {
+                let peers = keys
+                    .iter()
+                    .filter(|(&peer, _)| peer != i)
+                    .map(|(&peer, key)| {
+                        let public_key =
+                            key.verifying_key().to_encoded_point(true);
+                        (peer, hex::encode(public_key.as_bytes()))
+                    })
+                    .collect();
+                let keys = IdentityKeys {
+                    key: hex::encode(key.to_bytes()),
+                    peers,
+                    pinned: BTreeMap::new(),
+                };
+                (i, Identity::new(i, &keys).unwrap())
+            })
+            .collect()
+    }
+
+    fn broadcasts(share_count: u8) -> BTreeMap<u8, Vec<u8>> {
+        (1..=share_count)
+            .map(|i| (i, format!("broadcast of {}", i).into_bytes()))
+            .collect()
+    }
+
+    fn others_of(index: u8) -> Vec<u8> {
+        (1..=3).filter(|&i| i != index).collect()
+    }
+
+    #[test]
+    pub fn test_echo_consistent() {
+        let received = broadcasts(3);
+        let echoes: Vec<Echo> = (1..=3)
+            .map(|i| echo(SESSION_ID, i, 4, &received, None))
+            .collect();
+
+        for own in &echoes {
+            let others: Vec<Echo> = echoes
+                .iter()
+                .filter(|echo| echo.sender != own.sender)
+                .cloned()
+                .collect();
+            assert_eq!(
+                check(own, &others_of(own.sender), &others, None),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_echo_names_equivocator() {
+        // Party 3 sends party 2 another broadcast than the others got.
+        let received = broadcasts(3);
+        let mut equivocated = received.clone();
+        equivocated.insert(3, b"another broadcast".to_vec());
+
+        let own = echo(SESSION_ID, 1, 4, &received, None);
+        let echoes = vec![
+            echo(SESSION_ID, 2, 4, &equivocated, None),
+            echo(SESSION_ID, 3, 4, &received, None),
+        ];
+        match check(&own, &others_of(1), &echoes, None) {
+            Err(EchoError::Equivocation {
+                party,
+                phase,
+                digests,
+            }) => {
+                assert_eq!((party, phase), (3, 4));
+                assert_eq!(digests.len(), 3);
+                assert_ne!(digests[&1], digests[&2]);
+                assert_eq!(digests[&1], digests[&3]);
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        assert_eq!(
+            check(&own, &others_of(1), &echoes[..1], None),
+            Err(EchoError::Missing { parties: vec![3] })
+        );
+    }
+
+    #[test]
+    pub fn test_echo_signed() {
+        let identities = identities(3);
+        let received = broadcasts(3);
+        let echoes: BTreeMap<u8, Echo> = identities
+            .iter()
+            .map(|(&i, identity)| {
+                (i, echo(SESSION_ID, i, 4, &received, Some(identity)))
+            })
+            .collect();
+        let others = vec![echoes[&2].clone(), echoes[&3].clone()];
+        assert_eq!(
+            check(&echoes[&1], &others_of(1), &others, Some(&identities[&1])),
+            Ok(())
+        );
+
+        // An echo that the relay changed or made up does not verify.
+        let mut forged =
+            echo(SESSION_ID, 3, 4, &broadcasts(3), Some(&identities[&2]));
+        forged.digests.insert(2, String::from("00"));
+        let others = vec![echoes[&2].clone(), forged];
+        assert_eq!(
+            check(&echoes[&1], &others_of(1), &others, Some(&identities[&1])),
+            Err(EchoError::Signature(SealError::InvalidSignature {
+                sender: 3
+            }))
+        );
+
+        let unsigned = echo(SESSION_ID, 3, 4, &received, None);
+        let others = vec![echoes[&2].clone(), unsigned];
+        assert!(matches!(
+            check(&echoes[&1], &others_of(1), &others, Some(&identities[&1])),
+            Err(EchoError::Signature(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_sign_broadcasts_match_signers() {
+        let broadcast = |i: u64| Broadcast3to4 {
+            u: Scalar::from(i),
+            w: Scalar::from(i + 1),
+        };
+        assert!(matches!(
+            sign_broadcasts(&[1, 2], &BTreeMap::new()),
+            Err(EchoError::InvalidBroadcasts { .. })
+        ));
+
+        // Each broadcast is taken from its sender, not from its position.
+        let received: BTreeMap<u8, Broadcast3to4> =
+            [(3, broadcast(3)), (1, broadcast(1))].into_iter().collect();
+        let broadcasts = sign_broadcasts(&[3, 1], &received).unwrap();
+        assert_eq!(broadcasts[&3], serde_json::to_vec(&broadcast(3)).unwrap());
+        assert!(matches!(
+            sign_broadcasts(&[1, 2], &received),
+            Err(EchoError::InvalidBroadcasts { .. })
+        ));
+    }
+
+    #[test]
+    pub fn test_echo_missing() {
+        // An echo that never came is an error, not a skipped check.
+        let own = echo(SESSION_ID, 1, 4, &broadcasts(3), None);
+        assert_eq!(
+            check(&own, &others_of(1), &[], None),
+            Err(EchoError::Missing {
+                parties: vec![2, 3]
+            })
+        );
+    }
+}
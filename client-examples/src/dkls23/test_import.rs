@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+    use dkls23::protocols::Parameters;
+    use dkls23::utilities::hashes::hash;
+    use ffi_tss::dkls23::protocols::import::{
+        dkls_import_wallet, import_wallet, ImportIn, ImportOut, ImportSource,
+    };
+    use ffi_tss::dkls23::transport::memory::memory_transports;
+    use ffi_tss::dkls23::transport::run_sign;
+    use ffi_tss::dkls23::utilities::bip32::{
+        ExtendedPrivateKey, ExtendedPublicKey, XPRV_VERSION,
+    };
+
+    // The BIP-39 vector of twelve words with the passphrase "TREZOR",
+    // and the root keys of its seed.
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const PASSPHRASE: &str = "TREZOR";
+    const XPRV: &str = "xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF";
+    const XPUB: &str = "xpub661MyMwAqRbcGB88KaFbLGiYAat55APKhtWg4uYMkXAmfuSTbq2QYsn9sKJCj1YqZPafsboef4h4YbXXhNhPwMbkHTpkf3zLhx7HvFw1NDy";
+
+    const PARAMETERS: Parameters = Parameters {
+        threshold: 2,
+        share_count: 3,
+    };
+
+    #[test]
+    pub fn test_dkls_import_mnemonic() {
+        let import_in = ImportIn {
+            parameters: PARAMETERS,
+            session_id: b"import".to_vec(),
+            source: ImportSource::Mnemonic {
+                phrase: String::from(PHRASE),
+                passphrase: String::from(PASSPHRASE),
+            },
+        };
+        let json_out = call_ffi(
+            dkls_import_wallet,
+            &serde_json::to_string(&import_in).unwrap(),
+        );
+        let import_out: ImportOut = serde_json::from_str(&json_out).unwrap();
+        assert_eq!(import_out.xpub, XPUB);
+        assert_eq!(import_out.parties.len(), 3);
+
+        let (xpub, _) = ExtendedPublicKey::decode(XPUB).unwrap();
+        for party in &import_out.parties {
+            assert_eq!(party.pk, xpub.pk);
+            assert_eq!(party.derivation_data.chain_code, xpub.chain_code);
+        }
+
+        // A wrong checksum word is refused.
+        let source = ImportSource::Mnemonic {
+            phrase: PHRASE.replace("about", "abandon"),
+            passphrase: String::new(),
+        };
+        assert!(import_wallet(&PARAMETERS, b"import", &source).is_err());
+
+        // The export returns the error instead of aborting.
+        let import_in = ImportIn {
+            parameters: PARAMETERS,
+            session_id: b"import".to_vec(),
+            source,
+        };
+        let json_out = call_ffi(
+            dkls_import_wallet,
+            &serde_json::to_string(&import_in).unwrap(),
+        );
+        let error_out: serde_json::Value =
+            serde_json::from_str(&json_out).unwrap();
+        assert!(error_out["error"]["Other"]["description"].is_string());
+        println!("DKLs23::Import mnemonic: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_import_xprv() {
+        let source = ImportSource::Xprv {
+            xprv: String::from(XPRV),
+        };
+        let (parties, xpub) =
+            import_wallet(&PARAMETERS, b"import", &source).unwrap();
+        assert_eq!(xpub, XPUB);
+
+        let (xprv, _) = ExtendedPrivateKey::decode(XPRV).unwrap();
+        assert_eq!(xprv.encode(XPRV_VERSION), XPRV);
+        assert!(parties.iter().all(|party| party.pk == xprv.public_key().pk));
+
+        // A public key cannot be imported.
+        let source = ImportSource::Xprv {
+            xprv: String::from(XPUB),
+        };
+        assert!(import_wallet(&PARAMETERS, b"import", &source).is_err());
+        println!("DKLs23::Import xprv: Passed!");
+    }
+
+    #[tokio::test]
+    pub async fn test_dkls_import_and_sign() {
+        let source = ImportSource::Xprv {
+            xprv: String::from(XPRV),
+        };
+        let (parties, _) =
+            import_wallet(&PARAMETERS, b"import", &source).unwrap();
+        let message_hash = hash("Message to sign!".as_bytes(), &[]);
+
+        // Parties 1 and 3 sign with their imported shares.
+        let transports = memory_transports(&[1, 3]);
+        let handles: Vec<_> = transports
+            .into_iter()
+            .map(|(i, mut transport)| {
+                let party = parties[i as usize - 1].clone();
+                let sign_data = SignData {
+                    sign_id: b"import".to_vec(),
+                    counterparties: vec![4 - i],
+                    message_hash,
+                };
+                tokio::spawn(async move {
+                    run_sign(party, sign_data, true, &mut transport)
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut signatures = Vec::new();
+        for handle in handles {
+            signatures.push(handle.await.unwrap());
+        }
+
+        // The signature verifies under the key of the original wallet.
+        let (xpub, _) = ExtendedPublicKey::decode(XPUB).unwrap();
+        assert!(verify_ecdsa_signature(
+            &message_hash,
+            &xpub.pk,
+            &signatures[0].x_coord,
+            &signatures[0].signature,
+        ));
+        assert_eq!(signatures[0].signature, signatures[1].signature);
+        println!("DKLs23::Import and sign: Passed!");
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::pairing::{
+        dkls_pair_commit, dkls_pair_finish, dkls_pair_reveal, PairFinishOut,
+    };
+    use ffi_tss::dkls23::session::pair::{
+        commit, finish, reveal, PairReveal, PairingError, PinnedPeer,
+    };
+    use ffi_tss::dkls23::session::seal::{Identity, IdentityKeys, SealError};
+    use ffi_tss::k256::ecdsa::{SigningKey, VerifyingKey};
+    use rand::Rng;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    // An identity key pair in hex.
+    fn identity() -> (String, String) {
+        let secret = rng::get_rng().gen::<[u8; 32]>();
+        let key = SigningKey::from_slice(&secret).unwrap();
+        let public_key = key.verifying_key().to_encoded_point(true);
+        (hex::encode(secret), hex::encode(public_key.as_bytes()))
+    }
+
+    #[test]
+    pub fn test_dkls_pairing() {
+        let (alice_key, alice_public_key) = identity();
+        let (bob_key, bob_public_key) = identity();
+
+        let alice_1 = call(dkls_pair_commit, json!({ "key": alice_key }));
+        let bob_1 = call(dkls_pair_commit, json!({ "key": bob_key }));
+
+        let alice_2 = call(
+            dkls_pair_reveal,
+            json!({
+                "kept": alice_1["kept"],
+                "commitment": bob_1["commitment"],
+            }),
+        );
+        let bob_2 = call(
+            dkls_pair_reveal,
+            json!({
+                "kept": bob_1["kept"],
+                "commitment": alice_1["commitment"],
+            }),
+        );
+
+        let alice: PairFinishOut = serde_json::from_value(call(
+            dkls_pair_finish,
+            json!({ "kept": alice_2["kept"], "reveal": bob_2["reveal"] }),
+        ))
+        .unwrap();
+        let bob: PairFinishOut = serde_json::from_value(call(
+            dkls_pair_finish,
+            json!({ "kept": bob_2["kept"], "reveal": alice_2["reveal"] }),
+        ))
+        .unwrap();
+
+        assert_eq!(alice.sas, bob.sas);
+        assert_eq!(alice.sas.digits.len(), 6);
+        assert_eq!(alice.sas.emoji.len(), 7);
+        assert_eq!(alice.peer.public_key, bob_public_key);
+        assert_eq!(bob.peer.public_key, alice_public_key);
+    }
+
+    #[test]
+    pub fn test_pairing_wrong_reveal() {
+        let (alice_key, _) = identity();
+        let (bob_key, _) = identity();
+        let (alice_kept, alice_commitment) = commit(&alice_key).unwrap();
+        let (_, bob_commitment) = commit(&bob_key).unwrap();
+        let (alice_kept, _) = reveal(alice_kept, bob_commitment).unwrap();
+
+        // Bob reveals the nonce of another commitment.
+        let (other_kept, _) = commit(&bob_key).unwrap();
+        let other_reveal = PairReveal {
+            nonce: other_kept.nonce,
+        };
+        assert_eq!(
+            finish(alice_kept, other_reveal),
+            Err(PairingError::WrongReveal)
+        );
+
+        let (own_kept, _) = commit(&alice_key).unwrap();
+        assert_eq!(
+            reveal(own_kept, alice_commitment),
+            Err(PairingError::SameKey)
+        );
+    }
+
+    #[test]
+    pub fn test_pairing_man_in_the_middle() {
+        // Mallory pairs with each of Alice and Bob in their place.
+        let (alice_key, _) = identity();
+        let (bob_key, _) = identity();
+        let (mallory_key, _) = identity();
+
+        let pair = |key: &str, other: &str| {
+            let (kept, commitment) = commit(key).unwrap();
+            let (other_kept, other_commitment) = commit(other).unwrap();
+            let (kept, _) = reveal(kept, other_commitment).unwrap();
+            let (_, other_reveal) = reveal(other_kept, commitment).unwrap();
+            finish(kept, other_reveal).unwrap().0
+        };
+        assert_ne!(
+            pair(&alice_key, &mallory_key),
+            pair(&bob_key, &mallory_key)
+        );
+    }
+
+    #[test]
+    pub fn test_pinned_peers() {
+        let (key, _) = identity();
+        let (_, peer_public_key) = identity();
+        let (_, stranger_public_key) = identity();
+        let pinned = PinnedPeer::new(
+            &VerifyingKey::from_sec1_bytes(
+                &hex::decode(&peer_public_key).unwrap(),
+            )
+            .unwrap(),
+        );
+
+        let keys = IdentityKeys {
+            key,
+            peers: BTreeMap::from([
+                (2, peer_public_key.clone()),
+                (3, stranger_public_key),
+            ]),
+            pinned: BTreeMap::from([(2, pinned.clone())]),
+        };
+        assert!(matches!(
+            Identity::new(1, &keys),
+            Err(SealError::Unpinned { index: 3 })
+        ));
+
+        let keys = IdentityKeys {
+            peers: keys.peers.into_iter().take(1).collect(),
+            ..keys
+        };
+        assert!(Identity::new(1, &keys).is_ok());
+
+        // A pinned key is only trusted for the party it was pinned for.
+        let keys = IdentityKeys {
+            peers: BTreeMap::from([(3, peer_public_key)]),
+            ..keys
+        };
+        assert!(matches!(
+            Identity::new(1, &keys),
+            Err(SealError::Unpinned { index: 3 })
+        ));
+    }
+}
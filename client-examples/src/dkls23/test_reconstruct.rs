@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::{re_key::re_key, Parameters};
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::reconstruct::{
+        dkls_reconstruct, reconstruct,
+    };
+    use ffi_tss::k256::{elliptic_curve::Field, Scalar};
+    use rand::Rng;
+
+    #[test]
+    pub fn test_dkls_reconstruct() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let secret_key = Scalar::random(rng::get_rng());
+        let parties = re_key(&parameters, &session_id, &secret_key, None);
+
+        // Any subset of threshold parties recovers the key.
+        let xprv = reconstruct(&parties[1..]).unwrap();
+        assert_eq!(xprv.secret_key, secret_key);
+        let xprv =
+            reconstruct(&[parties[0].clone(), parties[2].clone()]).unwrap();
+        assert_eq!(xprv.secret_key, secret_key);
+
+        // Fewer parties than the threshold are rejected.
+        assert!(reconstruct(&parties[..1]).is_err());
+
+        // Parties from another key are rejected.
+        let other_key = Scalar::random(rng::get_rng());
+        let others = re_key(&parameters, &session_id, &other_key, None);
+        assert!(reconstruct(&[parties[0].clone(), others[1].clone()]).is_err());
+
+        // The export returns the error instead of aborting.
+        let json_out = call_ffi(dkls_reconstruct, r#"{"parties": []}"#);
+        let error_out: serde_json::Value =
+            serde_json::from_str(&json_out).unwrap();
+        assert_eq!(error_out["error"]["description"], "No parties were given!");
+        println!("DKLs23::Reconstruct: Passed!");
+    }
+}
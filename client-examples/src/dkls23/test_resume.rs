@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::Parameters;
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::resume::{
+        dkls_resume_advance, dkls_resume_dkg, dkls_resume_finish,
+        dkls_resume_receive, dkls_resume_remove, dkls_resume_status,
+        ResumeFinishOut, ResumeStatusOut,
+    };
+    use rand::Rng;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        let name = hex::encode(rng::get_rng().gen::<[u8; 8]>());
+        std::env::temp_dir().join(format!("libtss-{}", name))
+    }
+
+    #[test]
+    pub fn test_dkls_resume_store() {
+        let dir = temp_dir();
+        let key = hex::encode(rng::get_rng().gen::<[u8; 32]>());
+        let store = json!({ "dir": dir, "key": key });
+        let session = SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 2,
+            },
+            party_index: 1,
+            session_id: rng::get_rng().gen::<[u8; 32]>().to_vec(),
+        };
+
+        let json_out = call(
+            dkls_resume_dkg,
+            json!({ "store": store, "id": "dkg-1", "session": session }),
+        );
+        let status: ResumeStatusOut = serde_json::from_value(json_out).unwrap();
+        assert_eq!(status.phase, 0);
+        assert!(status.ready);
+
+        // Only the owner can read the checkpoint, and no temporary file
+        // is left behind.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = std::fs::metadata(dir.join("dkg-1.session"));
+            assert_eq!(metadata.unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(!dir.join("dkg-1.tmp").exists());
+
+        // The checkpoint is not overwritten by a new session.
+        let json_out = call(
+            dkls_resume_dkg,
+            json!({ "store": store, "id": "dkg-1", "session": session }),
+        );
+        assert_eq!(json_out["error"]["Store"]["Exists"]["id"], "dkg-1");
+
+        // Ids cannot leave the directory.
+        let json_out = call(
+            dkls_resume_status,
+            json!({ "store": store, "id": "../dkg-1" }),
+        );
+        assert!(json_out["error"]["Store"]["InvalidId"].is_object());
+
+        // Another key cannot read it.
+        let other_key = hex::encode(rng::get_rng().gen::<[u8; 32]>());
+        let json_out = call(
+            dkls_resume_status,
+            json!({ "store": { "dir": dir, "key": other_key }, "id": "dkg-1" }),
+        );
+        assert_eq!(json_out["error"]["Store"]["Decryption"]["id"], "dkg-1");
+
+        // Nor can a file copied to another id.
+        std::fs::copy(dir.join("dkg-1.session"), dir.join("dkg-2.session"))
+            .unwrap();
+        let json_out =
+            call(dkls_resume_status, json!({ "store": store, "id": "dkg-2" }));
+        assert_eq!(json_out["error"]["Store"]["Decryption"]["id"], "dkg-2");
+
+        let json_out =
+            call(dkls_resume_remove, json!({ "store": store, "id": "dkg-1" }));
+        assert_eq!(json_out["removed"], true);
+        let json_out =
+            call(dkls_resume_status, json!({ "store": store, "id": "dkg-1" }));
+        assert_eq!(json_out["error"]["Store"]["NotFound"]["id"], "dkg-1");
+
+        std::fs::remove_dir_all(dir).unwrap();
+        println!("DKLs23::ResumeStore: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_resume_dkg() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let dir = temp_dir();
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+
+        // Every party has its own key, as it would on its own device.
+        let stores: BTreeMap<u8, Value> = (1..=parameters.share_count)
+            .map(|i| {
+                let key = hex::encode(rng::get_rng().gen::<[u8; 32]>());
+                (i, json!({ "dir": dir, "key": key }))
+            })
+            .collect();
+        for (&i, store) in &stores {
+            let session = SessionData {
+                parameters: parameters.clone(),
+                party_index: i,
+                session_id: session_id.to_vec(),
+            };
+            let id = format!("party-{}", i);
+            call(
+                dkls_resume_dkg,
+                json!({ "store": store, "id": id, "session": session }),
+            );
+        }
+
+        for _ in 0..3 {
+            for (&i, store) in &stores {
+                let id = format!("party-{}", i);
+                call(dkls_resume_advance, json!({ "store": store, "id": id }));
+            }
+
+            // Each call starts from the files only, as after a restart.
+            let mut inboxes: BTreeMap<u8, Vec<Value>> = BTreeMap::new();
+            for (&i, store) in &stores {
+                let id = format!("party-{}", i);
+                let json_out = call(
+                    dkls_resume_status,
+                    json!({ "store": store, "id": id }),
+                );
+                let status: ResumeStatusOut =
+                    serde_json::from_value(json_out).unwrap();
+                for message in status.outbox {
+                    let receiver = message["receiver"].as_u64().unwrap() as u8;
+                    inboxes.entry(receiver).or_default().push(message);
+                }
+            }
+            for (&i, store) in &stores {
+                let id = format!("party-{}", i);
+                let messages = &inboxes[&i];
+                call(
+                    dkls_resume_receive,
+                    json!({ "store": store, "id": id, "messages": messages }),
+                );
+
+                // Messages passed again are skipped.
+                let json_out = call(
+                    dkls_resume_receive,
+                    json!({ "store": store, "id": id, "messages": messages }),
+                );
+                let status: ResumeStatusOut =
+                    serde_json::from_value(json_out).unwrap();
+                assert!(status.ready);
+            }
+        }
+
+        let mut pks = Vec::new();
+        for (&i, store) in &stores {
+            let id = format!("party-{}", i);
+            call(dkls_resume_advance, json!({ "store": store, "id": id }));
+            let json_out =
+                call(dkls_resume_finish, json!({ "store": store, "id": id }));
+            let finish_out: ResumeFinishOut =
+                serde_json::from_value(json_out).unwrap();
+            pks.push(finish_out.party.unwrap().pk);
+            assert!(!dir.join(format!("{}.session", id)).exists());
+        }
+        assert!(pks.iter().all(|pk| *pk == pks[0]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+        println!("DKLs23::ResumeDkg: Passed!");
+    }
+}
@@ -0,0 +1,271 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::dkg::{ProofCommitment, SessionData};
+    use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+    use dkls23::protocols::{Parameters, Party};
+    use dkls23::utilities::{hashes::hash, rng};
+    use ffi_tss::dkls23::protocols::router::{
+        dkls_route_dkg_phase1, dkls_route_dkg_phase2, dkls_route_dkg_phase3,
+        route_sign_phase1, route_sign_phase2, route_sign_phase3,
+        RouteDkgPhase1Out, RouteDkgPhase2Out, RouteDkgPhase3Out, RoutingError,
+    };
+    use ffi_tss::dkls23::protocols::{dkg, signing};
+    use ffi_tss::dkls23::session::echo::{dkg_broadcasts, EchoError};
+    use rand::Rng;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn run_dkg(parameters: &Parameters) -> Vec<Party> {
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let sessions: BTreeMap<u8, SessionData> = (1..=parameters.share_count)
+            .map(|i| {
+                let session = SessionData {
+                    parameters: parameters.clone(),
+                    party_index: i,
+                    session_id: session_id.to_vec(),
+                };
+                (i, session)
+            })
+            .collect();
+
+        let mut outputs_1: BTreeMap<u8, dkg::Phase1Out> = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let json_out = call_ffi(
+                dkg::dkls_dkg_phase1,
+                &json!({ "session": session }).to_string(),
+            );
+            outputs_1.insert(i, serde_json::from_str(&json_out).unwrap());
+        }
+        let json_out = call_ffi(
+            dkls_route_dkg_phase1,
+            &json!({ "outputs": outputs_1 }).to_string(),
+        );
+        let routed_1: RouteDkgPhase1Out =
+            serde_json::from_str(&json_out).unwrap();
+
+        let mut outputs_2: BTreeMap<u8, dkg::Phase2Out> = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let phase2_in = dkg::Phase2In {
+                session: session.clone(),
+                poly_fragments: routed_1.poly_fragments[&i].clone(),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+                sealed: Vec::new(),
+            };
+            let json_out = call_ffi(
+                dkg::dkls_dkg_phase2,
+                &serde_json::to_string(&phase2_in).unwrap(),
+            );
+            outputs_2.insert(i, serde_json::from_str(&json_out).unwrap());
+        }
+        let json_out = call_ffi(
+            dkls_route_dkg_phase2,
+            &json!({ "outputs": outputs_2 }).to_string(),
+        );
+        let routed_2: RouteDkgPhase2Out =
+            serde_json::from_str(&json_out).unwrap();
+
+        let mut outputs_3: BTreeMap<u8, dkg::Phase3Out> = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let phase3_in = dkg::Phase3In {
+                session: session.clone(),
+                zero_kept: outputs_2[&i].zero_keep.clone(),
+                bip_kept: outputs_2[&i].bip_keep.clone(),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+            };
+            let json_out = call_ffi(
+                dkg::dkls_dkg_phase3,
+                &serde_json::to_string(&phase3_in).unwrap(),
+            );
+            outputs_3.insert(i, serde_json::from_str(&json_out).unwrap());
+        }
+        let json_out = call_ffi(
+            dkls_route_dkg_phase3,
+            &json!({ "outputs": outputs_3 }).to_string(),
+        );
+        let routed_3: RouteDkgPhase3Out =
+            serde_json::from_str(&json_out).unwrap();
+
+        // The echo takes each proof by the index it carries, so the order
+        // does not matter, but every party needs exactly one.
+        let broadcasts = |proofs: &[ProofCommitment]| {
+            dkg_broadcasts(
+                parameters.share_count,
+                proofs,
+                &routed_2.bip_broadcast_2to4,
+                &routed_3.bip_broadcast_3to4,
+            )
+        };
+        let mut proofs = routed_2.proofs_commitments.clone();
+        proofs.reverse();
+        assert_eq!(
+            broadcasts(&proofs),
+            broadcasts(&routed_2.proofs_commitments)
+        );
+        proofs[0] = proofs[1].clone();
+        assert!(matches!(
+            broadcasts(&proofs),
+            Err(EchoError::InvalidBroadcasts { .. })
+        ));
+        assert!(matches!(
+            broadcasts(&proofs[1..]),
+            Err(EchoError::InvalidBroadcasts { .. })
+        ));
+
+        let mut parties = Vec::new();
+        for (&i, session) in &sessions {
+            let phase4_in = dkg::Phase4In {
+                session: session.clone(),
+                poly_point: outputs_2[&i].poly_point,
+                proofs_commitments: routed_2.proofs_commitments.clone(),
+                zero_kept: outputs_3[&i].zero_keep.clone(),
+                zero_received_phase2: routed_2.zero_received_phase2[&i].clone(),
+                zero_received_phase3: routed_3.zero_received_phase3[&i].clone(),
+                mul_kept: outputs_3[&i].mul_keep.clone(),
+                mul_received: routed_3.mul_received[&i].clone(),
+                bip_broadcast_2to4: routed_2.bip_broadcast_2to4.clone(),
+                bip_broadcast_3to4: routed_3.bip_broadcast_3to4.clone(),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+                sealed: Vec::new(),
+                echo: false,
+                echoes: Vec::new(),
+            };
+            let json_out = call_ffi(
+                dkg::dkls_dkg_phase4,
+                &serde_json::to_string(&phase4_in).unwrap(),
+            );
+            let phase4_out: dkg::Phase4Out =
+                serde_json::from_str(&json_out).unwrap();
+            parties.push(phase4_out.party);
+        }
+        parties
+    }
+
+    #[test]
+    pub fn test_dkls_router() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let parties = run_dkg(&parameters);
+        assert!(parties.iter().all(|party| party.pk == parties[0].pk));
+
+        // Parties 1 and 3 sign.
+        let signers = [&parties[0], &parties[2]];
+        let sign_id = rng::get_rng().gen::<[u8; 32]>();
+        let message_hash = hash("Message to sign!".as_bytes(), &[]);
+        let sign_data: BTreeMap<u8, SignData> = signers
+            .iter()
+            .map(|party| {
+                let data = SignData {
+                    sign_id: sign_id.to_vec(),
+                    counterparties: signers
+                        .iter()
+                        .map(|other| other.party_index)
+                        .filter(|&index| index != party.party_index)
+                        .collect(),
+                    message_hash,
+                };
+                (party.party_index, data)
+            })
+            .collect();
+
+        let mut outputs_1: BTreeMap<u8, signing::Phase1Out> = BTreeMap::new();
+        for party in signers {
+            let (unique_keep, keep, transmit) =
+                party.sign_phase1(&sign_data[&party.party_index]);
+            let output = signing::Phase1Out {
+                unique_keep,
+                keep,
+                transmit,
+            };
+            outputs_1.insert(party.party_index, output);
+        }
+
+        // Duplicated and misaddressed messages are rejected.
+        let mut message = outputs_1[&1].transmit[0].clone();
+        outputs_1
+            .get_mut(&1)
+            .unwrap()
+            .transmit
+            .push(message.clone());
+        assert_eq!(
+            route_sign_phase1(&outputs_1, &BTreeMap::new()).err(),
+            Some(RoutingError::Duplicate {
+                sender: 1,
+                receiver: 3
+            })
+        );
+        outputs_1.get_mut(&1).unwrap().transmit.pop();
+        message.parties.receiver = 2;
+        outputs_1.get_mut(&1).unwrap().transmit[0] = message.clone();
+        assert_eq!(
+            route_sign_phase1(&outputs_1, &BTreeMap::new()).err(),
+            Some(RoutingError::Misaddressed {
+                from: 1,
+                sender: 1,
+                receiver: 2
+            })
+        );
+        message.parties.receiver = 3;
+        outputs_1.get_mut(&1).unwrap().transmit[0] = message;
+        let routed_1 = route_sign_phase1(&outputs_1, &BTreeMap::new()).unwrap();
+
+        let mut outputs_2: BTreeMap<u8, signing::Phase2Out> = BTreeMap::new();
+        for party in signers {
+            let i = party.party_index;
+            let (unique_keep, keep, transmit) = party
+                .sign_phase2(
+                    &sign_data[&i],
+                    &outputs_1[&i].unique_keep,
+                    &outputs_1[&i].keep,
+                    &routed_1.received[&i],
+                )
+                .unwrap();
+            let output = signing::Phase2Out {
+                unique_keep,
+                keep,
+                transmit,
+            };
+            outputs_2.insert(i, output);
+        }
+        let routed_2 = route_sign_phase2(&outputs_2, &BTreeMap::new()).unwrap();
+
+        let mut outputs_3: BTreeMap<u8, signing::Phase3Out> = BTreeMap::new();
+        for party in signers {
+            let i = party.party_index;
+            let (x_coord, broadcast) = party
+                .sign_phase3(
+                    &sign_data[&i],
+                    &outputs_2[&i].unique_keep,
+                    &outputs_2[&i].keep,
+                    &routed_2.received[&i],
+                )
+                .unwrap();
+            outputs_3.insert(i, signing::Phase3Out { x_coord, broadcast });
+        }
+        let routed_3 = route_sign_phase3(&outputs_3).unwrap();
+
+        let (signature, _) = parties[0]
+            .sign_phase4(
+                &sign_data[&1],
+                &routed_3.x_coord,
+                &routed_3.received,
+                true,
+            )
+            .unwrap();
+        assert!(verify_ecdsa_signature(
+            &message_hash,
+            &parties[0].pk,
+            &routed_3.x_coord,
+            &signature,
+        ));
+        println!("DKLs23::Router: Passed!");
+    }
+}
@@ -0,0 +1,221 @@
+#[cfg(test)]
+mod tests {
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::signing::SignData;
+    use dkls23::protocols::{Parameters, Party};
+    use ffi_tss::dkls23::protocols::run::{dkls_dkg_run, dkls_sign_run};
+    use ffi_tss::dkls23::transport::callback::{
+        DklsCallbacks, RECV_EMPTY, RECV_OK, RECV_TOO_SMALL,
+    };
+    use serde_json::{json, Value};
+    use std::collections::{BTreeMap, VecDeque};
+    use std::ffi::{CStr, CString};
+    use std::os::raw::{c_char, c_void};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time::Duration;
+
+    type Inbox = VecDeque<(u8, Vec<u8>)>;
+
+    // What a host would keep behind its user-data pointer: the party and
+    // the inboxes of everybody.
+    #[derive(Default)]
+    struct Hub {
+        inboxes: Mutex<BTreeMap<u8, Inbox>>,
+        arrived: Condvar,
+    }
+
+    struct Host {
+        index: u8,
+        hub: Arc<Hub>,
+        cancelled: AtomicBool,
+    }
+
+    extern "C" fn send(
+        user_data: *mut c_void,
+        receiver: u8,
+        bytes: *const u8,
+        length: usize,
+    ) -> i32 {
+        let host = unsafe { &*(user_data as *const Host) };
+        let message = unsafe { std::slice::from_raw_parts(bytes, length) };
+        let mut inboxes = host.hub.inboxes.lock().unwrap();
+        inboxes
+            .entry(receiver)
+            .or_default()
+            .push_back((host.index, message.to_vec()));
+        host.hub.arrived.notify_all();
+        0
+    }
+
+    extern "C" fn recv(
+        user_data: *mut c_void,
+        timeout_ms: u32,
+        sender: *mut u8,
+        buffer: *mut u8,
+        capacity: usize,
+        length: *mut usize,
+    ) -> i32 {
+        let host = unsafe { &*(user_data as *const Host) };
+        let inboxes = host.hub.inboxes.lock().unwrap();
+        let (mut inboxes, _) = host
+            .hub
+            .arrived
+            .wait_timeout_while(
+                inboxes,
+                Duration::from_millis(timeout_ms as u64),
+                |inboxes| {
+                    inboxes
+                        .get(&host.index)
+                        .is_none_or(|inbox| inbox.is_empty())
+                },
+            )
+            .unwrap();
+        let inbox = inboxes.entry(host.index).or_default();
+        let (from, message) = match inbox.front() {
+            Some(front) => front.clone(),
+            None => return RECV_EMPTY,
+        };
+        unsafe { *length = message.len() };
+        if message.len() > capacity {
+            return RECV_TOO_SMALL;
+        }
+        inbox.pop_front();
+        unsafe {
+            *sender = from;
+            std::ptr::copy_nonoverlapping(
+                message.as_ptr(),
+                buffer,
+                message.len(),
+            );
+        }
+        RECV_OK
+    }
+
+    extern "C" fn cancelled(user_data: *mut c_void) -> i32 {
+        let host = unsafe { &*(user_data as *const Host) };
+        host.cancelled.load(Ordering::SeqCst) as i32
+    }
+
+    fn run(
+        ffi_func: extern "C" fn(
+            *const c_char,
+            *const DklsCallbacks,
+        ) -> *const c_char,
+        host: &Host,
+        json_in: Value,
+    ) -> Value {
+        let callbacks = DklsCallbacks {
+            user_data: host as *const Host as *mut c_void,
+            send,
+            recv,
+            cancelled: Some(cancelled),
+        };
+        let data = CString::new(json_in.to_string()).unwrap();
+        let json_out = ffi_func(data.as_ptr(), &callbacks);
+        let json_out = unsafe { CString::from_raw(json_out as *mut c_char) };
+        serde_json::from_str(json_out.to_str().unwrap()).unwrap()
+    }
+
+    fn session(party_index: u8) -> SessionData {
+        SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 3,
+            },
+            party_index,
+            session_id: b"host-run".to_vec(),
+        }
+    }
+
+    #[test]
+    pub fn test_dkls_dkg_sign_run() {
+        let hub = Arc::new(Hub::default());
+        let handles: Vec<_> = (1..=3)
+            .map(|index| {
+                let hub = hub.clone();
+                std::thread::spawn(move || {
+                    let host = Host {
+                        index,
+                        hub,
+                        cancelled: AtomicBool::new(false),
+                    };
+                    let json_out = run(
+                        dkls_dkg_run,
+                        &host,
+                        json!({ "session": session(index), "timeout_ms": 60000 }),
+                    );
+                    serde_json::from_value::<Party>(json_out["party"].clone())
+                        .unwrap()
+                })
+            })
+            .collect();
+        let parties: Vec<Party> =
+            handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(parties[0].pk, parties[1].pk);
+        assert_eq!(parties[0].pk, parties[2].pk);
+
+        let hub = Arc::new(Hub::default());
+        let handles: Vec<_> = [(0, 2), (1, 1)]
+            .into_iter()
+            .map(|(i, counterparty)| {
+                let party = parties[i].clone();
+                let hub = hub.clone();
+                std::thread::spawn(move || {
+                    let host = Host {
+                        index: party.party_index,
+                        hub,
+                        cancelled: AtomicBool::new(false),
+                    };
+                    let sign_data = SignData {
+                        sign_id: b"host-run-sign".to_vec(),
+                        counterparties: vec![counterparty],
+                        message_hash: [2; 32],
+                    };
+                    run(
+                        dkls_sign_run,
+                        &host,
+                        json!({
+                            "party": party,
+                            "sign_data": sign_data,
+                            "normalize": true,
+                        }),
+                    )
+                })
+            })
+            .collect();
+        let signatures: Vec<Value> =
+            handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(signatures[0]["signature"], signatures[1]["signature"]);
+        println!("DKLs23::HostRun: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_run_timeout_and_cancel() {
+        // Nobody else takes part.
+        let host = Host {
+            index: 1,
+            hub: Arc::new(Hub::default()),
+            cancelled: AtomicBool::new(false),
+        };
+        let json_out = run(
+            dkls_dkg_run,
+            &host,
+            json!({ "session": session(1), "timeout_ms": 100 }),
+        );
+        assert_eq!(json_out["error"]["Run"]["Transport"], "Timeout");
+
+        host.cancelled.store(true, Ordering::SeqCst);
+        let json_out =
+            run(dkls_dkg_run, &host, json!({ "session": session(1) }));
+        assert_eq!(json_out["error"]["Run"]["Transport"], "Cancelled");
+
+        // Without callbacks nothing runs.
+        let data =
+            CString::new(json!({ "session": session(1) }).to_string()).unwrap();
+        let json_out = dkls_dkg_run(data.as_ptr(), std::ptr::null());
+        let json_out = unsafe { CStr::from_ptr(json_out) }.to_str().unwrap();
+        assert_eq!(json_out, r#"{"error":"NoCallbacks"}"#);
+        println!("DKLs23::HostRunTimeout: Passed!");
+    }
+}
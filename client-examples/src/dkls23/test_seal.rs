@@ -0,0 +1,438 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+    use dkls23::protocols::{Parameters, Party};
+    use dkls23::utilities::rng;
+    use ffi_tss::dkls23::protocols::router::{
+        dkls_route_sign_phase1, dkls_route_sign_phase2, dkls_route_sign_phase3,
+    };
+    use ffi_tss::dkls23::protocols::{dkg, signing};
+    use ffi_tss::dkls23::session::seal::{
+        Identity, IdentityKeys, SealError, SealedMessage,
+    };
+    use ffi_tss::dkls23::transport::memory::memory_transports;
+    use ffi_tss::dkls23::transport::{run_dkg_sealed, run_sign_sealed};
+    use ffi_tss::k256::ecdsa::SigningKey;
+    use rand::Rng;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::os::raw::c_char;
+
+    // The identity keys of every party, each with the public keys of the
+    // others.
+    fn identities(share_count: u8) -> BTreeMap<u8, IdentityKeys> {
+        let keys: BTreeMap<u8, [u8; 32]> = (1..=share_count)
+            .map(|i| (i, rng::get_rng().gen::<[u8; 32]>()))
+            .collect();
+        let public_keys: BTreeMap<u8, String> = keys
+            .iter()
+            .map(|(&i, secret)| {
+                let key = SigningKey::from_slice(secret).unwrap();
+                let public_key = key.verifying_key().to_encoded_point(true);
+                (i, hex::encode(public_key.as_bytes()))
+            })
+            .collect();
+        keys.iter()
+            .map(|(&i, secret)| {
+                let peers = public_keys
+                    .iter()
+                    .filter(|(&peer, _)| peer != i)
+                    .map(|(&peer, public_key)| (peer, public_key.clone()))
+                    .collect();
+                let identity = IdentityKeys {
+                    key: hex::encode(secret),
+                    peers,
+                    pinned: BTreeMap::new(),
+                };
+                (i, identity)
+            })
+            .collect()
+    }
+
+    fn sealed_for(outputs: &[&Value], receiver: u8) -> Vec<SealedMessage> {
+        outputs
+            .iter()
+            .flat_map(|output| {
+                let sealed: Vec<SealedMessage> =
+                    serde_json::from_value(output["sealed"].clone()).unwrap();
+                sealed
+            })
+            .filter(|message| message.receiver == receiver)
+            .collect()
+    }
+
+    // Routes the outputs of a phase, with their sealed messages.
+    fn route(
+        ffi_func: extern "C" fn(*const c_char) -> *const c_char,
+        outputs: &BTreeMap<u8, Value>,
+    ) -> Value {
+        let sealed: BTreeMap<u8, &Value> = outputs
+            .iter()
+            .map(|(&i, output)| (i, &output["sealed"]))
+            .collect();
+        call(ffi_func, json!({ "outputs": outputs, "sealed": sealed }))
+    }
+
+    #[test]
+    pub fn test_seal_open() {
+        let keys = identities(2);
+        let alice = Identity::new(1, &keys[&1]).unwrap();
+        let bob = Identity::new(2, &keys[&2]).unwrap();
+
+        let sealed = alice.seal(b"session", 2, 1, "fragment", &42u32).unwrap();
+        assert_eq!(sealed.sender, 1);
+        assert_eq!(sealed.receiver, 2);
+
+        let opened: u32 = bob.open(b"session", 1, "fragment", &sealed).unwrap();
+        assert_eq!(opened, 42);
+
+        // Only the receiver can open it.
+        assert_eq!(
+            alice.open::<u32>(b"session", 1, "fragment", &sealed),
+            Err(SealError::Misaddressed {
+                sender: 1,
+                receiver: 2
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_seal_binds_context() {
+        let keys = identities(3);
+        let alice = Identity::new(1, &keys[&1]).unwrap();
+        let bob = Identity::new(2, &keys[&2]).unwrap();
+        let sealed = alice.seal(b"session", 2, 1, "zero", &7u8).unwrap();
+
+        assert_eq!(
+            bob.open::<u8>(b"other", 1, "zero", &sealed),
+            Err(SealError::WrongSession { sender: 1 })
+        );
+        assert_eq!(
+            bob.open::<u8>(b"session", 2, "zero", &sealed),
+            Err(SealError::WrongPhase {
+                sender: 1,
+                phase: 1
+            })
+        );
+        assert!(matches!(
+            bob.open::<u8>(b"session", 1, "mul", &sealed),
+            Err(SealError::Misaddressed { .. })
+        ));
+
+        // Relabelling a message breaks the signature.
+        let relabelled = SealedMessage {
+            phase: 2,
+            ..sealed.clone()
+        };
+        assert_eq!(
+            bob.open::<u8>(b"session", 2, "zero", &relabelled),
+            Err(SealError::InvalidSignature { sender: 1 })
+        );
+
+        // So does claiming another sender.
+        let forged = SealedMessage {
+            sender: 3,
+            ..sealed.clone()
+        };
+        assert_eq!(
+            bob.open::<u8>(b"session", 1, "zero", &forged),
+            Err(SealError::InvalidSignature { sender: 3 })
+        );
+    }
+
+    #[test]
+    pub fn test_open_all() {
+        let keys = identities(3);
+        let parties: Vec<Identity> = (1..=3)
+            .map(|i| Identity::new(i, &keys[&i]).unwrap())
+            .collect();
+        let sealed: Vec<SealedMessage> = parties[1..]
+            .iter()
+            .map(|party| {
+                party.seal(b"session", 1, 1, "fragment", &party.index())
+            })
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let opened: BTreeMap<u8, u8> = parties[0]
+            .open_all(b"session", 1, "fragment", &[2, 3], &sealed)
+            .unwrap();
+        assert_eq!(opened, BTreeMap::from([(2, 2), (3, 3)]));
+
+        assert_eq!(
+            parties[0].open_all::<u8>(
+                b"session",
+                1,
+                "fragment",
+                &[2, 3],
+                &sealed[..1],
+            ),
+            Err(SealError::Missing { parties: vec![3] })
+        );
+        let twice = [sealed.clone(), sealed[..1].to_vec()].concat();
+        assert_eq!(
+            parties[0].open_all::<u8>(
+                b"session",
+                1,
+                "fragment",
+                &[2, 3],
+                &twice
+            ),
+            Err(SealError::Duplicate { sender: 2 })
+        );
+    }
+
+    #[test]
+    pub fn test_dkg_sealed() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let identities = identities(parameters.share_count);
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let sessions: BTreeMap<u8, SessionData> = (1..=parameters.share_count)
+            .map(|i| {
+                let session = SessionData {
+                    parameters: parameters.clone(),
+                    party_index: i,
+                    session_id: session_id.to_vec(),
+                };
+                (i, session)
+            })
+            .collect();
+
+        let mut outputs_1 = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let output = call(
+                dkg::dkls_dkg_phase1,
+                json!({ "session": session, "identity": identities[&i] }),
+            );
+            // Only our own fragment is left in the clear.
+            let fragments = output["fragments"].as_array().unwrap();
+            assert_eq!(output["sealed"].as_array().unwrap().len(), 2);
+            assert_ne!(
+                fragments[(i - 1) as usize],
+                fragments[(i % 3) as usize]
+            );
+            outputs_1.insert(i, output);
+        }
+
+        let mut outputs_2 = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let sealed = sealed_for(&outputs_1.values().collect::<Vec<_>>(), i);
+            let output = call(
+                dkg::dkls_dkg_phase2,
+                json!({
+                    "session": session,
+                    "poly_fragments": outputs_1[&i]["fragments"],
+                    "identity": identities[&i],
+                    "sealed": sealed,
+                }),
+            );
+            assert_eq!(output["zero_transmit"], json!([]));
+            outputs_2.insert(i, output);
+        }
+
+        let mut outputs_3 = BTreeMap::new();
+        for (&i, session) in &sessions {
+            let output = call(
+                dkg::dkls_dkg_phase3,
+                json!({
+                    "session": session,
+                    "zero_kept": outputs_2[&i]["zero_keep"],
+                    "bip_kept": outputs_2[&i]["bip_keep"],
+                    "identity": identities[&i],
+                }),
+            );
+            outputs_3.insert(i, output);
+        }
+
+        let proofs_commitments: Vec<&Value> = outputs_2
+            .values()
+            .map(|output| &output["proof_commitment"])
+            .collect();
+        let bip_broadcast_2to4: BTreeMap<u8, &Value> = outputs_2
+            .iter()
+            .map(|(&i, output)| (i, &output["bip_broadcast"]))
+            .collect();
+        let bip_broadcast_3to4: BTreeMap<u8, &Value> = outputs_3
+            .iter()
+            .map(|(&i, output)| (i, &output["bip_broadcast"]))
+            .collect();
+        let mut parties = Vec::new();
+        for (&i, session) in &sessions {
+            let outputs: Vec<&Value> =
+                outputs_2.values().chain(outputs_3.values()).collect();
+            let phase4_in = json!({
+                "session": session,
+                "poly_point": outputs_2[&i]["poly_point"],
+                "proofs_commitments": proofs_commitments,
+                "zero_kept": outputs_3[&i]["zero_keep"],
+                "mul_kept": outputs_3[&i]["mul_keep"],
+                "bip_broadcast_2to4": bip_broadcast_2to4,
+                "bip_broadcast_3to4": bip_broadcast_3to4,
+                "identity": identities[&i],
+                "sealed": sealed_for(&outputs, i),
+            });
+
+            // Without the sealed messages, the phase cannot finish.
+            let mut unsealed = phase4_in.clone();
+            unsealed["sealed"] = json!([]);
+            let json_out = call(dkg::dkls_dkg_phase4, unsealed);
+            assert!(json_out["error"]["Missing"].is_object());
+
+            let json_out = call(dkg::dkls_dkg_phase4, phase4_in);
+            let party: Party =
+                serde_json::from_value(json_out["party"].clone()).unwrap();
+            parties.push(party);
+        }
+
+        assert!(parties.windows(2).all(|w| w[0].pk == w[1].pk));
+    }
+
+    #[tokio::test]
+    pub async fn test_sign_sealed() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let identities = identities(parameters.share_count);
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+
+        // A DKG over a transport, with every message sealed.
+        let handles: Vec<_> = memory_transports(&[1, 2, 3])
+            .into_iter()
+            .map(|(i, mut transport)| {
+                let session = SessionData {
+                    parameters: parameters.clone(),
+                    party_index: i,
+                    session_id: session_id.to_vec(),
+                };
+                let identity = identities[&i].clone();
+                tokio::spawn(async move {
+                    run_dkg_sealed(session, &identity, &mut transport)
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut parties: BTreeMap<u8, Party> = BTreeMap::new();
+        for handle in handles {
+            let party = handle.await.unwrap();
+            parties.insert(party.party_index, party);
+        }
+
+        // Parties 1 and 3 sign with the phase exports and the router.
+        let message_hash = [5; 32];
+        let sign_data = |i: u8| SignData {
+            sign_id: b"sealed".to_vec(),
+            counterparties: vec![4 - i],
+            message_hash,
+        };
+        let mut outputs_1 = BTreeMap::new();
+        for i in [1, 3] {
+            let output = call(
+                signing::dkls_sign_phase1,
+                json!({
+                    "party": parties[&i],
+                    "sign_data": sign_data(i),
+                    "identity": identities[&i],
+                }),
+            );
+            assert_eq!(output["transmit"], json!([]));
+            outputs_1.insert(i, output);
+        }
+        let routed_1 = route(dkls_route_sign_phase1, &outputs_1);
+
+        let mut outputs_2 = BTreeMap::new();
+        for i in [1, 3] {
+            let output = call(
+                signing::dkls_sign_phase2,
+                json!({
+                    "party": parties[&i],
+                    "sign_data": sign_data(i),
+                    "unique_kept": outputs_1[&i]["unique_keep"],
+                    "kept": outputs_1[&i]["keep"],
+                    "identity": identities[&i],
+                    "sealed": routed_1["sealed"][i.to_string()],
+                }),
+            );
+            assert_eq!(output["transmit"], json!([]));
+            outputs_2.insert(i, output);
+        }
+        let routed_2 = route(dkls_route_sign_phase2, &outputs_2);
+
+        let mut outputs_3 = BTreeMap::new();
+        for i in [1, 3] {
+            let output = call(
+                signing::dkls_sign_phase3,
+                json!({
+                    "party": parties[&i],
+                    "sign_data": sign_data(i),
+                    "unique_kept": outputs_2[&i]["unique_keep"],
+                    "kept": outputs_2[&i]["keep"],
+                    "identity": identities[&i],
+                    "sealed": routed_2["sealed"][i.to_string()],
+                }),
+            );
+            outputs_3.insert(i, output);
+        }
+        let routed_3 =
+            call(dkls_route_sign_phase3, json!({ "outputs": outputs_3 }));
+
+        let output = call(
+            signing::dkls_sign_phase4,
+            json!({
+                "party": parties[&1],
+                "sign_data": sign_data(1),
+                "x_coord": routed_3["x_coord"],
+                "received": routed_3["received"],
+                "normalize": true,
+            }),
+        );
+        let x_coord = routed_3["x_coord"].as_str().unwrap();
+        let signature = output["signature"].as_str().unwrap();
+        assert!(verify_ecdsa_signature(
+            &message_hash,
+            &parties[&1].pk,
+            x_coord,
+            signature,
+        ));
+
+        // Parties 2 and 3 sign over a transport, also sealed.
+        let handles: Vec<_> = memory_transports(&[2, 3])
+            .into_iter()
+            .map(|(i, mut transport)| {
+                let party = parties[&i].clone();
+                let sign_data = SignData {
+                    sign_id: b"sealed-run".to_vec(),
+                    counterparties: vec![5 - i],
+                    message_hash,
+                };
+                let identity = identities[&i].clone();
+                tokio::spawn(async move {
+                    run_sign_sealed(
+                        party,
+                        sign_data,
+                        true,
+                        &identity,
+                        &mut transport,
+                    )
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            let signature = handle.await.unwrap();
+            assert!(verify_ecdsa_signature(
+                &message_hash,
+                &parties[&2].pk,
+                &signature.x_coord,
+                &signature.signature,
+            ));
+        }
+    }
+}
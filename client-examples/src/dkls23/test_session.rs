@@ -0,0 +1,225 @@
+#[cfg(test)]
+mod tests {
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+    use dkls23::protocols::{Parameters, Party};
+    use dkls23::utilities::{hashes::hash, rng};
+    use ffi_tss::dkls23::session::dkg::{DkgMessage, DkgSession, DkgState};
+    use ffi_tss::dkls23::session::sign::{
+        SignMessage, SignPayload, SignSession, SignState,
+    };
+    use ffi_tss::dkls23::session::SessionError;
+    use ffi_tss::k256::ecdsa::SigningKey;
+    use ffi_tss::k256::Scalar;
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    // Delivers every message and checks that all sessions can go on.
+    fn deliver_dkg(
+        sessions: &mut BTreeMap<u8, DkgSession>,
+        messages: Vec<DkgMessage>,
+    ) {
+        for message in messages {
+            let receiver = message.receiver;
+            sessions
+                .get_mut(&receiver)
+                .unwrap()
+                .receive(message)
+                .unwrap();
+        }
+        assert!(sessions.values().all(|session| session.is_ready()));
+    }
+
+    fn run_dkg(parameters: &Parameters) -> Vec<Party> {
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let mut sessions: BTreeMap<u8, DkgSession> = (1..=parameters
+            .share_count)
+            .map(|i| {
+                let data = SessionData {
+                    parameters: parameters.clone(),
+                    party_index: i,
+                    session_id: session_id.to_vec(),
+                };
+                (i, DkgSession::new(data))
+            })
+            .collect();
+
+        for _ in 0..3 {
+            let mut messages = Vec::new();
+            for session in sessions.values_mut() {
+                messages.extend(session.advance().unwrap());
+            }
+            deliver_dkg(&mut sessions, messages);
+        }
+        for session in sessions.values_mut() {
+            assert!(session.advance().unwrap().is_empty());
+        }
+
+        sessions
+            .into_values()
+            .map(|session| session.finish().unwrap())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_dkls_session() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let parties = run_dkg(&parameters);
+        assert!(parties.iter().all(|party| party.pk == parties[0].pk));
+
+        // Parties 1 and 3 sign.
+        let sign_id = rng::get_rng().gen::<[u8; 32]>();
+        let message_hash = hash("Message to sign!".as_bytes(), &[]);
+        let mut sessions: BTreeMap<u8, SignSession> = [(1, 3), (3, 1)]
+            .into_iter()
+            .map(|(i, other)| {
+                let sign_data = SignData {
+                    sign_id: sign_id.to_vec(),
+                    counterparties: vec![other],
+                    message_hash,
+                };
+                let party = parties[(i - 1) as usize].clone();
+                (i, SignSession::new(party, sign_data, true))
+            })
+            .collect();
+
+        // Phases run in order and only once everything has arrived.
+        assert!(matches!(
+            sessions.get(&1).unwrap().clone().finish(),
+            Err(SessionError::WrongPhase { .. })
+        ));
+        let mut messages: Vec<SignMessage> = Vec::new();
+        for session in sessions.values_mut() {
+            messages.extend(session.advance().unwrap());
+        }
+        assert_eq!(
+            sessions.get_mut(&1).unwrap().advance().err(),
+            Some(SessionError::NotReady {
+                round: 1,
+                missing: vec![3]
+            })
+        );
+
+        for round in 1..=3 {
+            for message in std::mem::take(&mut messages) {
+                let receiver = message.receiver;
+                let session = sessions.get_mut(&receiver).unwrap();
+                session.receive(message.clone()).unwrap();
+
+                // A message is only taken once.
+                assert_eq!(
+                    session.receive(message.clone()).err(),
+                    Some(SessionError::Duplicate {
+                        sender: 4 - receiver,
+                        round
+                    })
+                );
+
+                // And a different one for the same round is refused.
+                let mut changed = message;
+                if let SignPayload::Round3 { x_coord, .. } =
+                    &mut changed.payload
+                {
+                    x_coord.push('0');
+                    assert_eq!(
+                        session.receive(changed).err(),
+                        Some(SessionError::Equivocation {
+                            sender: 4 - receiver,
+                            round
+                        })
+                    );
+                }
+            }
+            for session in sessions.values_mut() {
+                messages.extend(session.advance().unwrap());
+            }
+        }
+        assert!(messages.is_empty());
+
+        let signatures: Vec<_> = sessions
+            .into_values()
+            .map(|session| session.finish().unwrap())
+            .collect();
+        assert_eq!(signatures[0].signature, signatures[1].signature);
+        assert!(verify_ecdsa_signature(
+            &message_hash,
+            &parties[0].pk,
+            &signatures[0].x_coord,
+            &signatures[0].signature,
+        ));
+        println!("DKLs23::Session: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_close() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 3,
+        };
+        let key = SigningKey::from_slice(&[7; 32]).unwrap();
+
+        // A DKG aborted after phase 2 keeps no seed or chain code.
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let mut sessions: BTreeMap<u8, DkgSession> = (1..=3)
+            .map(|i| {
+                let data = SessionData {
+                    parameters: parameters.clone(),
+                    party_index: i,
+                    session_id: session_id.to_vec(),
+                };
+                (i, DkgSession::new(data))
+            })
+            .collect();
+        for _ in 0..2 {
+            let mut messages = Vec::new();
+            for session in sessions.values_mut() {
+                messages.extend(session.advance().unwrap());
+            }
+            deliver_dkg(&mut sessions, messages);
+        }
+        let session = sessions.get_mut(&1).unwrap();
+        let mut state = session.state().clone();
+        state.zeroize();
+        match state {
+            DkgState::Phase2Done {
+                zero_kept,
+                bip_kept,
+            } => {
+                assert!(zero_kept.values().all(|keep| keep.seed == [0; 32]));
+                assert_eq!(bip_kept.aux_chain_code, [0; 32]);
+            }
+            _ => panic!("The DKG should be after phase 2!"),
+        }
+        session.abort(&key, "Closed by the test");
+        assert!(matches!(session.state(), DkgState::Closed { phase: 2, .. }));
+
+        // A signature aborted after phase 1 keeps no nonce.
+        let parties = run_dkg(&parameters);
+        let sign_data = SignData {
+            sign_id: rng::get_rng().gen::<[u8; 32]>().to_vec(),
+            counterparties: vec![2],
+            message_hash: hash("Message to sign!".as_bytes(), &[]),
+        };
+        let mut session = SignSession::new(parties[0].clone(), sign_data, true);
+        session.advance().unwrap();
+        let mut state = session.state().clone();
+        state.zeroize();
+        match state {
+            SignState::Phase1Done { unique_kept, kept } => {
+                assert_eq!(unique_kept.instance_key, Scalar::ZERO);
+                assert_eq!(unique_kept.inversion_mask, Scalar::ZERO);
+                assert!(kept.values().all(|keep| keep.chi == Scalar::ZERO));
+            }
+            _ => panic!("The signature should be after phase 1!"),
+        }
+        session.abort(&key, "Closed by the test");
+        assert!(matches!(
+            session.state(),
+            SignState::Closed { phase: 1, .. }
+        ));
+        println!("DKLs23::SessionClose: Passed!");
+    }
+}
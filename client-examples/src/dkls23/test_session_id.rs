@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::Parameters;
+    use ffi_tss::dkls23::protocols::dkg::{dkls_dkg_phase1, dkls_dkg_phase2};
+    use ffi_tss::dkls23::protocols::session_id::{
+        dkls_session_id_commit, dkls_session_id_finish, dkls_session_id_reveal,
+        IdFinishOut,
+    };
+    use serde_json::{json, Value};
+
+    fn context() -> Value {
+        json!({
+            "purpose": "Dkg",
+            "threshold": 2,
+            "share_count": 3,
+            "parties": [1, 2, 3],
+        })
+    }
+
+    // Runs the commitments and returns what every party kept, with the
+    // reveals of all of them.
+    fn commit_and_reveal() -> (Vec<Value>, Vec<Value>) {
+        let mut kepts = Vec::new();
+        let mut commitments = Vec::new();
+        for i in 1..=3 {
+            let json_out = call(
+                dkls_session_id_commit,
+                json!({ "context": context(), "party_index": i }),
+            );
+            kepts.push(json_out["kept"].clone());
+            commitments.push(json_out["commitment"].clone());
+        }
+
+        let mut reveals = Vec::new();
+        for kept in kepts.iter_mut() {
+            let json_out = call(
+                dkls_session_id_reveal,
+                json!({ "kept": kept, "commitments": commitments }),
+            );
+            *kept = json_out["kept"].clone();
+            reveals.push(json_out["reveal"].clone());
+        }
+        (kepts, reveals)
+    }
+
+    // Finishes the run of the first party.
+    fn finish(kepts: &[Value], reveals: &[Value]) -> IdFinishOut {
+        let json_out = call(
+            dkls_session_id_finish,
+            json!({ "kept": kepts[0], "reveals": reveals }),
+        );
+        serde_json::from_value(json_out).unwrap()
+    }
+
+    #[test]
+    pub fn test_dkls_session_id() {
+        let (kepts, reveals) = commit_and_reveal();
+        let finish_outs: Vec<IdFinishOut> = kepts
+            .iter()
+            .map(|kept| {
+                let json_out = call(
+                    dkls_session_id_finish,
+                    json!({ "kept": kept, "reveals": reveals }),
+                );
+                serde_json::from_value(json_out).unwrap()
+            })
+            .collect();
+        for finish_out in &finish_outs {
+            assert_eq!(finish_out.session_id.len(), 32);
+            assert_eq!(finish_out.session_id, finish_outs[0].session_id);
+            assert_eq!(
+                finish_out.transcript.session_id().unwrap(),
+                finish_out.session_id
+            );
+        }
+
+        // Another run gives another id.
+        let (kepts, reveals) = commit_and_reveal();
+        let json_out = call(
+            dkls_session_id_finish,
+            json!({ "kept": kepts[0], "reveals": reveals }),
+        );
+        let other: IdFinishOut = serde_json::from_value(json_out).unwrap();
+        assert_ne!(other.session_id, finish_outs[0].session_id);
+        println!("DKLs23::SessionId: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_id_cheating() {
+        // Nobody reveals before all the commitments are in.
+        let json_out = call(
+            dkls_session_id_commit,
+            json!({ "context": context(), "party_index": 1 }),
+        );
+        let json_out = call(
+            dkls_session_id_reveal,
+            json!({
+                "kept": json_out["kept"],
+                "commitments": [json_out["commitment"]],
+            }),
+        );
+        assert_eq!(json_out["error"]["Missing"]["parties"], json!([2, 3]));
+
+        // A party that changes its nonce after seeing the others is named.
+        let (kepts, mut reveals) = commit_and_reveal();
+        reveals[2]["nonce"] = json!(hex::encode([7u8; 32]));
+        let json_out = call(
+            dkls_session_id_finish,
+            json!({ "kept": kepts[0], "reveals": reveals }),
+        );
+        assert_eq!(json_out["error"]["WrongReveal"]["sender"], 3);
+
+        // Nor can our own commitment be swapped for another one.
+        let mut commitments = Vec::new();
+        let mut kepts = Vec::new();
+        for i in 1..=3 {
+            let json_out = call(
+                dkls_session_id_commit,
+                json!({ "context": context(), "party_index": i }),
+            );
+            kepts.push(json_out["kept"].clone());
+            commitments.push(json_out["commitment"].clone());
+        }
+        commitments[0]["commitment"] = commitments[1]["commitment"].clone();
+        let json_out = call(
+            dkls_session_id_reveal,
+            json!({ "kept": kepts[0], "commitments": commitments }),
+        );
+        assert_eq!(json_out["error"]["WrongReveal"]["sender"], 1);
+        println!("DKLs23::SessionIdCheating: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_id_dkg() {
+        let (kepts, reveals) = commit_and_reveal();
+        let finish_out = finish(&kepts, &reveals);
+        assert_eq!(finish_out.nonce, kepts[0]["nonce"]);
+        let mut session = SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 3,
+            },
+            party_index: 1,
+            session_id: finish_out.session_id,
+        };
+
+        let json_out = call(
+            dkls_dkg_phase1,
+            json!({
+                "session": session,
+                "transcript": finish_out.transcript,
+                "id_nonce": finish_out.nonce,
+            }),
+        );
+        assert!(json_out["fragments"].is_array());
+
+        // The transcript does not stand for any other id.
+        session.session_id = vec![1, 2, 3];
+        let json_out = call(
+            dkls_dkg_phase1,
+            json!({
+                "session": session,
+                "transcript": finish_out.transcript,
+                "id_nonce": finish_out.nonce,
+            }),
+        );
+        assert_eq!(json_out["error"], "Mismatch");
+
+        // Nor can a later phase be called with another id.
+        let json_out = call(
+            dkls_dkg_phase2,
+            json!({
+                "session": session,
+                "poly_fragments": [],
+                "transcript": finish_out.transcript,
+                "id_nonce": finish_out.nonce,
+            }),
+        );
+        assert_eq!(json_out["error"], "Mismatch");
+        println!("DKLs23::SessionIdDkg: Passed!");
+    }
+
+    #[test]
+    pub fn test_dkls_session_id_replay() {
+        let (kepts, reveals) = commit_and_reveal();
+        let old = finish(&kepts, &reveals);
+        let (kepts, reveals) = commit_and_reveal();
+        let current = finish(&kepts, &reveals);
+        let session = |session_id: &[u8]| SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 3,
+            },
+            party_index: 1,
+            session_id: session_id.to_vec(),
+        };
+
+        // An old transcript with its old id is consistent, but does not
+        // hold the nonce of this run.
+        let json_out = call(
+            dkls_dkg_phase1,
+            json!({
+                "session": session(&old.session_id),
+                "transcript": old.transcript,
+                "id_nonce": current.nonce,
+            }),
+        );
+        assert_eq!(json_out["error"], "NotOurs");
+
+        // Nor does a transcript that another party made by itself, for
+        // every party.
+        let (forged_kepts, forged_reveals) = commit_and_reveal();
+        let forged = finish(&forged_kepts, &forged_reveals);
+        let json_out = call(
+            dkls_dkg_phase1,
+            json!({
+                "session": session(&forged.session_id),
+                "transcript": forged.transcript,
+                "id_nonce": current.nonce,
+            }),
+        );
+        assert_eq!(json_out["error"], "NotOurs");
+
+        // A transcript is not checked without our nonce.
+        let json_out = call(
+            dkls_dkg_phase1,
+            json!({
+                "session": session(&current.session_id),
+                "transcript": current.transcript,
+            }),
+        );
+        assert_eq!(json_out["error"], "NotOurs");
+        println!("DKLs23::SessionIdReplay: Passed!");
+    }
+}
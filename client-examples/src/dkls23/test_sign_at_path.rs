@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call_ffi;
+    use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+    use dkls23::protocols::{re_key::re_key, Parameters};
+    use dkls23::utilities::{hashes::hash, rng};
+    use ffi_tss::dkls23::protocols::signing::{
+        dkls_sign_phase1, dkls_sign_phase2, dkls_sign_phase3, dkls_sign_phase4,
+        Phase1In, Phase1Out, Phase2In, Phase2Out, Phase3In, Phase3Out,
+        Phase4In, Phase4Out,
+    };
+    use ffi_tss::k256::{elliptic_curve::Field, Scalar};
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    pub fn test_dkls_sign_at_path() {
+        let parameters = Parameters {
+            threshold: 2,
+            share_count: 2,
+        };
+        let session_id = rng::get_rng().gen::<[u8; 32]>();
+        let secret_key = Scalar::random(rng::get_rng());
+        let chain_code = rng::get_rng().gen::<[u8; 32]>();
+        let parties =
+            re_key(&parameters, &session_id, &secret_key, Some(chain_code));
+
+        // Every party signs with its master share and only gives the path.
+        // Both write it their own way, which is still the same path.
+        let path = String::from("m/0/5");
+        let paths: BTreeMap<u8, String> =
+            BTreeMap::from([(1, path.clone()), (2, String::from("m/0/05"))]);
+        let sign_id = rng::get_rng().gen::<[u8; 32]>();
+        let message_hash = hash("Message to sign!".as_bytes(), &[]);
+        let sign_data: BTreeMap<u8, SignData> = (1..=2)
+            .map(|i| {
+                let data = SignData {
+                    sign_id: sign_id.to_vec(),
+                    counterparties: vec![3 - i],
+                    message_hash,
+                };
+                (i, data)
+            })
+            .collect();
+
+        let mut out_1to2: BTreeMap<u8, Phase1Out> = BTreeMap::new();
+        for party in &parties {
+            let phase1_in = Phase1In {
+                party: party.clone(),
+                sign_data: sign_data[&party.party_index].clone(),
+                derivation_path: Some(paths[&party.party_index].clone()),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+            };
+            let json_out = call_ffi(
+                dkls_sign_phase1,
+                &serde_json::to_string(&phase1_in).unwrap(),
+            );
+            out_1to2.insert(
+                party.party_index,
+                serde_json::from_str(&json_out).unwrap(),
+            );
+        }
+
+        let mut out_2to3: BTreeMap<u8, Phase2Out> = BTreeMap::new();
+        for party in &parties {
+            let i = party.party_index;
+            let phase2_in = Phase2In {
+                party: party.clone(),
+                sign_data: sign_data[&i].clone(),
+                derivation_path: Some(paths[&i].clone()),
+                unique_kept: out_1to2[&i].unique_keep.clone(),
+                kept: out_1to2[&i].keep.clone(),
+                received: out_1to2
+                    .values()
+                    .flat_map(|out| out.transmit.clone())
+                    .filter(|message| message.parties.receiver == i)
+                    .collect(),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+                sealed: Vec::new(),
+            };
+            let json_out = call_ffi(
+                dkls_sign_phase2,
+                &serde_json::to_string(&phase2_in).unwrap(),
+            );
+            out_2to3.insert(i, serde_json::from_str(&json_out).unwrap());
+        }
+
+        let mut out_3to4: BTreeMap<u8, Phase3Out> = BTreeMap::new();
+        for party in &parties {
+            let i = party.party_index;
+            let phase3_in = Phase3In {
+                party: party.clone(),
+                sign_data: sign_data[&i].clone(),
+                derivation_path: Some(paths[&i].clone()),
+                unique_kept: out_2to3[&i].unique_keep.clone(),
+                kept: out_2to3[&i].keep.clone(),
+                received: out_2to3
+                    .values()
+                    .flat_map(|out| out.transmit.clone())
+                    .filter(|message| message.parties.receiver == i)
+                    .collect(),
+                transcript: None,
+                id_nonce: None,
+                identity: None,
+                sealed: Vec::new(),
+            };
+            let json_out = call_ffi(
+                dkls_sign_phase3,
+                &serde_json::to_string(&phase3_in).unwrap(),
+            );
+            out_3to4.insert(i, serde_json::from_str(&json_out).unwrap());
+        }
+
+        let phase4_in = Phase4In {
+            party: parties[0].clone(),
+            sign_data: sign_data[&1].clone(),
+            derivation_path: Some(paths[&1].clone()),
+            x_coord: out_3to4[&1].x_coord.clone(),
+            received: Vec::new(),
+            broadcasts: out_3to4
+                .iter()
+                .map(|(&i, out)| (i, out.broadcast.clone()))
+                .collect(),
+            normalize: true,
+            echo: false,
+            echoes: Vec::new(),
+            transcript: None,
+            id_nonce: None,
+            identity: None,
+        };
+        let json_out = call_ffi(
+            dkls_sign_phase4,
+            &serde_json::to_string(&phase4_in).unwrap(),
+        );
+        let phase4_out: Phase4Out = serde_json::from_str(&json_out).unwrap();
+
+        // The signature verifies under the derived key.
+        let derived = parties[0].derive_from_path(&path).unwrap();
+        assert!(verify_ecdsa_signature(
+            &message_hash,
+            &derived.pk,
+            &out_3to4[&1].x_coord,
+            &phase4_out.signature,
+        ));
+        assert!(!verify_ecdsa_signature(
+            &message_hash,
+            &parties[0].pk,
+            &out_3to4[&1].x_coord,
+            &phase4_out.signature,
+        ));
+        println!("DKLs23::Sign at derivation path: Passed!");
+    }
+}
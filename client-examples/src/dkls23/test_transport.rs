@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use dkls23::protocols::dkg::SessionData;
+    use dkls23::protocols::signing::SignData;
+    use dkls23::protocols::{Parameters, Party};
+    use ffi_tss::dkls23::transport::memory::memory_transports;
+    use ffi_tss::dkls23::transport::tcp::TcpTransport;
+    use ffi_tss::dkls23::transport::{
+        run_dkg, run_sign, RunError, Transport, TransportError,
+    };
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn session(party_index: u8) -> SessionData {
+        SessionData {
+            parameters: Parameters {
+                threshold: 2,
+                share_count: 3,
+            },
+            party_index,
+            session_id: b"transport".to_vec(),
+        }
+    }
+
+    fn sign_data(counterparty: u8) -> SignData {
+        SignData {
+            sign_id: b"transport-sign".to_vec(),
+            counterparties: vec![counterparty],
+            message_hash: [1; 32],
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_dkls_memory_transport() {
+        let mut transports = memory_transports(&[1, 2]);
+        let mut second = transports.remove(&2).unwrap();
+        let mut first = transports.remove(&1).unwrap();
+
+        first.send(2, b"hello".to_vec()).await.unwrap();
+        assert_eq!(second.receive().await, Ok((1, b"hello".to_vec())));
+        assert_eq!(
+            first.send(3, Vec::new()).await,
+            Err(TransportError::UnknownParty { index: 3 })
+        );
+
+        // Once the others are gone, nobody waits forever.
+        drop(first);
+        assert_eq!(second.receive().await, Err(TransportError::Closed));
+
+        // Nor does a run that is missing a party.
+        let mut transports = memory_transports(&[1, 2, 3]);
+        let mut transport = transports.remove(&1).unwrap();
+        drop(transports);
+        let result = run_dkg(session(1), &mut transport).await;
+        assert!(matches!(
+            result,
+            Err(RunError::Transport(TransportError::Closed))
+        ));
+        println!("DKLs23::MemoryTransport: Passed!");
+    }
+
+    #[tokio::test]
+    pub async fn test_dkls_run_memory() {
+        let transports = memory_transports(&[1, 2, 3]);
+        let handles: Vec<_> = transports
+            .into_iter()
+            .map(|(i, mut transport)| {
+                tokio::spawn(async move {
+                    run_dkg(session(i), &mut transport).await.unwrap()
+                })
+            })
+            .collect();
+        let mut parties: BTreeMap<u8, Party> = BTreeMap::new();
+        for handle in handles {
+            let party = handle.await.unwrap();
+            parties.insert(party.party_index, party);
+        }
+        assert_eq!(parties[&1].pk, parties[&2].pk);
+        assert_eq!(parties[&1].pk, parties[&3].pk);
+
+        let transports = memory_transports(&[1, 3]);
+        let handles: Vec<_> = transports
+            .into_iter()
+            .map(|(i, mut transport)| {
+                let party = parties[&i].clone();
+                let counterparty = 4 - i;
+                tokio::spawn(async move {
+                    run_sign(
+                        party,
+                        sign_data(counterparty),
+                        true,
+                        &mut transport,
+                    )
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+        let mut signatures = Vec::new();
+        for handle in handles {
+            signatures.push(handle.await.unwrap());
+        }
+        assert_eq!(signatures[0].signature, signatures[1].signature);
+        println!("DKLs23::RunMemory: Passed!");
+    }
+
+    #[tokio::test]
+    pub async fn test_dkls_run_tcp() {
+        let mut listeners = BTreeMap::new();
+        let mut addresses = BTreeMap::new();
+        for i in 1..=3 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            addresses.insert(i, listener.local_addr().unwrap());
+            listeners.insert(i, listener);
+        }
+
+        let handles: Vec<_> = listeners
+            .into_iter()
+            .map(|(i, listener)| {
+                let mut peers = addresses.clone();
+                peers.remove(&i);
+                tokio::spawn(async move {
+                    let mut transport =
+                        TcpTransport::connect(i, listener, peers)
+                            .await
+                            .unwrap();
+                    run_dkg(session(i), &mut transport).await.unwrap()
+                })
+            })
+            .collect();
+        let mut pks = Vec::new();
+        for handle in handles {
+            pks.push(handle.await.unwrap().pk);
+        }
+        assert!(pks.iter().all(|pk| *pk == pks[0]));
+        println!("DKLs23::RunTcp: Passed!");
+    }
+
+    #[tokio::test]
+    pub async fn test_dkls_tcp_stray_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let peers = BTreeMap::from([(2, address), (3, address)]);
+        let connecting =
+            tokio::spawn(TcpTransport::connect(1, listener, peers));
+
+        // A party we do not wait for, then the same party twice. The
+        // first connection of a party is the one that is kept.
+        let mut streams = Vec::new();
+        for peer in [9, 2, 2, 3] {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream.write_all(&[peer]).await.unwrap();
+            streams.push(stream);
+        }
+        let mut transport = connecting
+            .await
+            .unwrap()
+            .unwrap()
+            .with_timeout(Duration::from_millis(500));
+
+        for (stream, message) in [(1, b"first"), (2, b"again")] {
+            let frame = [&(message.len() as u32).to_be_bytes()[..], message];
+            streams[stream].write_all(&frame.concat()).await.unwrap();
+        }
+        assert_eq!(transport.receive().await, Ok((2, b"first".to_vec())));
+
+        // Nothing else comes before the deadline.
+        assert_eq!(transport.receive().await, Err(TransportError::Timeout));
+        println!("DKLs23::TcpStrayConnections: Passed!");
+    }
+}
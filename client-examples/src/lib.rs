@@ -1,3 +1,4 @@
 #![allow(dead_code)]
 mod dkls23;
+mod musig2;
 mod utils;
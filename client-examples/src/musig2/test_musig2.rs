@@ -0,0 +1,514 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::ffi::call;
+    use dkls23::utilities::rng;
+    use ffi_tss::k256::elliptic_curve::sec1::FromEncodedPoint;
+    use ffi_tss::k256::elliptic_curve::{Field, PrimeField};
+    use ffi_tss::k256::schnorr::{Signature, VerifyingKey};
+    use ffi_tss::k256::{
+        AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar,
+    };
+    use ffi_tss::musig2::protocols::key_agg::{key_agg, key_sort};
+    use ffi_tss::musig2::protocols::nonce::{
+        musig2_nonce_gen, nonce_agg, nonce_gen, nonce_gen_internal, AggNonce,
+        PubNonce, SecNonce,
+    };
+    use ffi_tss::musig2::protocols::signing::{
+        musig2_partial_sign, partial_sig_agg, partial_sign, partial_verify,
+        verify_schnorr_signature,
+    };
+    use ffi_tss::musig2::utilities::hashes::{cbytes, xbytes};
+    use serde_json::json;
+
+    // The test vectors are those of BIP-327, in its vectors directory.
+
+    // The secret key, the aggregated key, the message and the extra input.
+    type NonceGenCase<'a> = (
+        Option<&'a Scalar>,
+        Option<&'a [u8; 32]>,
+        Option<&'a [u8]>,
+        Option<&'a [u8]>,
+    );
+
+    // The keys and nonces of the signers, the message, our position
+    // among the signers and our partial signature.
+    type SignCase<'a> = (&'a [usize], &'a [usize], usize, usize, &'a str);
+
+    fn bytes(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    fn point(hex_str: &str) -> Option<AffinePoint> {
+        let encoded = EncodedPoint::from_bytes(bytes(hex_str)).ok()?;
+        Option::from(AffinePoint::from_encoded_point(&encoded))
+    }
+
+    fn points(hex_strs: &[&str]) -> Vec<AffinePoint> {
+        hex_strs.iter().map(|pk| point(pk).unwrap()).collect()
+    }
+
+    fn scalar(hex_str: &str) -> Scalar {
+        let bytes: [u8; 32] = bytes(hex_str).try_into().unwrap();
+        Scalar::from_repr(FieldBytes::from(bytes)).unwrap()
+    }
+
+    fn pub_nonce(hex_str: &str) -> PubNonce {
+        PubNonce {
+            r1: point(&hex_str[..66]).unwrap(),
+            r2: point(&hex_str[66..]).unwrap(),
+        }
+    }
+
+    // The aggregated nonce may hold the point at infinity, as 33 zeros.
+    fn agg_nonce(hex_str: &str) -> AggNonce {
+        let half = |hex_str: &str| {
+            if bytes(hex_str) == [0u8; 33] {
+                AffinePoint::IDENTITY
+            } else {
+                point(hex_str).unwrap()
+            }
+        };
+        AggNonce {
+            r1: half(&hex_str[..66]),
+            r2: half(&hex_str[66..]),
+        }
+    }
+
+    fn sec_nonce(hex_str: &str) -> SecNonce {
+        SecNonce {
+            k1: scalar(&hex_str[..64]),
+            k2: scalar(&hex_str[64..128]),
+            pk: point(&hex_str[128..]).unwrap(),
+        }
+    }
+
+    fn encode_sec_nonce(sec_nonce: &SecNonce) -> String {
+        hex::encode_upper(
+            [
+                sec_nonce.k1.to_bytes().to_vec(),
+                sec_nonce.k2.to_bytes().to_vec(),
+                cbytes(&sec_nonce.pk),
+            ]
+            .concat(),
+        )
+    }
+
+    fn encode_pub_nonce(pub_nonce: &PubNonce) -> String {
+        hex::encode_upper(
+            [cbytes(&pub_nonce.r1), cbytes(&pub_nonce.r2)].concat(),
+        )
+    }
+
+    #[test]
+    pub fn test_musig2_key_sort() {
+        let pks = points(&[
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EFF",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+        ]);
+        let sorted = points(&[
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EFF",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ]);
+        assert_eq!(key_sort(&pks), sorted);
+        println!("MuSig2::KeySort: Passed!");
+    }
+
+    const KEY_AGG_PKS: [&str; 7] = [
+        "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+        "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+        "020000000000000000000000000000000000000000000000000000000000000005",
+        "02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+        "04F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+        "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+    ];
+
+    #[test]
+    pub fn test_musig2_key_agg() {
+        let cases: [(&[usize], &str); 4] = [
+            (
+                &[0, 1, 2],
+                "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C",
+            ),
+            (
+                &[2, 1, 0],
+                "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B",
+            ),
+            (
+                &[0, 0, 0],
+                "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935",
+            ),
+            (
+                &[0, 0, 1, 1],
+                "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E",
+            ),
+        ];
+        for (indices, expected) in cases {
+            let pks: Vec<AffinePoint> = indices
+                .iter()
+                .map(|&i| point(KEY_AGG_PKS[i]).unwrap())
+                .collect();
+            let agg_pk = key_agg(&pks).unwrap();
+            assert_eq!(hex::encode_upper(xbytes(&agg_pk)), expected);
+        }
+
+        // Keys that are not on the curve, or not compressed, are refused.
+        for invalid in &KEY_AGG_PKS[3..6] {
+            assert!(point(invalid).is_none());
+        }
+        assert!(key_agg(&[]).is_err());
+        println!("MuSig2::KeyAgg: Passed!");
+    }
+
+    #[test]
+    pub fn test_musig2_nonce_gen() {
+        let rand = [0u8; 32];
+        let sk = scalar(&"02".repeat(32));
+        let pk = point(
+            "024D4B6CD1361032CA9BD2AEB9D900AA4D45D9EAD80AC9423374C451A7254D0766",
+        )
+        .unwrap();
+        let agg_pk: [u8; 32] = bytes(&"07".repeat(32)).try_into().unwrap();
+        let extra_in = bytes(&"08".repeat(32));
+        let msg_38 = bytes(&"26".repeat(38));
+        assert_eq!((ProjectivePoint::GENERATOR * sk).to_affine(), pk);
+
+        // The inputs of the vectors. Every optional input, and an empty
+        // message as opposed to no message, must change the nonce.
+        let cases: [NonceGenCase; 6] = [
+            (Some(&sk), Some(&agg_pk), Some(&[1u8; 32]), Some(&extra_in)),
+            (Some(&sk), Some(&agg_pk), Some(&[]), Some(&extra_in)),
+            (Some(&sk), Some(&agg_pk), Some(&msg_38), Some(&extra_in)),
+            (None, Some(&agg_pk), Some(&msg_38), Some(&extra_in)),
+            (Some(&sk), None, Some(&msg_38), Some(&extra_in)),
+            (Some(&sk), Some(&agg_pk), None, None),
+        ];
+        let mut sec_nonces = Vec::new();
+        for (sk, agg_pk, msg, extra_in) in cases {
+            let (sec_nonce, pub_nonce) =
+                nonce_gen_internal(&rand, sk, &pk, agg_pk, msg, extra_in);
+            let (again, _) =
+                nonce_gen_internal(&rand, sk, &pk, agg_pk, msg, extra_in);
+            assert_eq!(encode_sec_nonce(&sec_nonce), encode_sec_nonce(&again));
+            assert_ne!(sec_nonce.k1, sec_nonce.k2);
+            assert_eq!(sec_nonce.pk, pk);
+
+            let public =
+                |k: &Scalar| (ProjectivePoint::GENERATOR * k).to_affine();
+            assert_eq!(public(&sec_nonce.k1), pub_nonce.r1);
+            assert_eq!(public(&sec_nonce.k2), pub_nonce.r2);
+            sec_nonces.push(encode_sec_nonce(&sec_nonce));
+        }
+        sec_nonces.sort();
+        sec_nonces.dedup();
+        assert_eq!(sec_nonces.len(), cases.len());
+
+        // The random input changes the nonce too.
+        let (sec_nonce, _) =
+            nonce_gen_internal(&[1u8; 32], None, &pk, None, None, None);
+        let (other, _) = nonce_gen_internal(&rand, None, &pk, None, None, None);
+        assert_ne!(encode_sec_nonce(&sec_nonce), encode_sec_nonce(&other));
+        println!("MuSig2::NonceGen: Passed!");
+    }
+
+    const PUB_NONCES: [&str; 4] = [
+        "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E66603BA47FBC1834437B3212E89A84D8425E7BF12E0245D98262268EBDCB385D50641",
+        "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B833",
+        "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E6660279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60379BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+    ];
+
+    #[test]
+    pub fn test_musig2_nonce_agg() {
+        let cases = [
+            (
+                [0, 1],
+                "035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B024725377345BDE0E9C33AF3C43C0A29A9249F2F2956FA8CFEB55C8573D0262DC8",
+            ),
+            // The second points add up to the point at infinity.
+            (
+                [2, 3],
+                "035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B000000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        ];
+        for (indices, expected) in cases {
+            let pub_nonces: Vec<PubNonce> =
+                indices.iter().map(|&i| pub_nonce(PUB_NONCES[i])).collect();
+            let agg_nonce = nonce_agg(&pub_nonces).unwrap();
+            assert_eq!(hex::encode_upper(agg_nonce.to_bytes()), expected);
+        }
+
+        // A nonce whose first point is not compressed is refused.
+        assert!(point(&format!("04{}", &PUB_NONCES[1][2..66])).is_none());
+        assert!(nonce_agg(&[]).is_err());
+        println!("MuSig2::NonceAgg: Passed!");
+    }
+
+    #[test]
+    pub fn test_musig2_sign_verify() {
+        let sk = scalar(
+            "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671",
+        );
+        let pks = points(&[
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+        ]);
+        let sec_nonce = sec_nonce(
+            "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F703935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+        );
+        let pub_nonces: Vec<PubNonce> = [
+            "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+            "0237C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0387BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+        ]
+        .iter()
+        .map(|hex_str| pub_nonce(hex_str))
+        .collect();
+        let msgs = [
+            bytes("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF"),
+            Vec::new(),
+            bytes(&"26".repeat(38)),
+        ];
+        assert_eq!((ProjectivePoint::GENERATOR * sk).to_affine(), pks[0]);
+        assert_eq!(
+            encode_pub_nonce(&PubNonce {
+                r1: (ProjectivePoint::GENERATOR * sec_nonce.k1).to_affine(),
+                r2: (ProjectivePoint::GENERATOR * sec_nonce.k2).to_affine(),
+            }),
+            encode_pub_nonce(&pub_nonces[0]),
+        );
+
+        let cases: [SignCase; 6] = [
+            (
+                &[0, 1, 2],
+                &[0, 1, 2],
+                0,
+                0,
+                "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+            ),
+            (
+                &[1, 0, 2],
+                &[1, 0, 2],
+                0,
+                1,
+                "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52",
+            ),
+            (
+                &[1, 2, 0],
+                &[1, 2, 0],
+                0,
+                2,
+                "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900",
+            ),
+            // Both halves of the aggregated nonce are the point at infinity.
+            (
+                &[0, 1],
+                &[0, 3],
+                0,
+                0,
+                "AE386064B26105404798F75DE2EB9AF5EDA5387B064B83D049CB7C5E08879531",
+            ),
+            (
+                &[0, 1, 2],
+                &[0, 1, 2],
+                1,
+                0,
+                "D7D63FFD644CCDA4E62BC2BC0B1D02DD32A1DC3030E155195810231D1037D82D",
+            ),
+            (
+                &[0, 1, 2],
+                &[0, 1, 2],
+                2,
+                0,
+                "E184351828DA5094A97C79CABDAAA0BFB87608C32E8829A4DF5340A6F243B78C",
+            ),
+        ];
+        for (key_indices, nonce_indices, msg_index, signer, expected) in cases {
+            let signers: Vec<AffinePoint> =
+                key_indices.iter().map(|&i| pks[i]).collect();
+            let nonces: Vec<PubNonce> = nonce_indices
+                .iter()
+                .map(|&i| pub_nonces[i].clone())
+                .collect();
+            let agg_nonce = nonce_agg(&nonces).unwrap();
+            let msg = &msgs[msg_index];
+
+            let partial_sig =
+                partial_sign(&sec_nonce, &sk, &agg_nonce, &signers, msg)
+                    .unwrap();
+            assert_eq!(hex::encode_upper(partial_sig.to_bytes()), expected);
+            assert!(partial_verify(
+                &partial_sig,
+                &nonces[signer],
+                &pks[0],
+                &agg_nonce,
+                &signers,
+                msg,
+            ));
+
+            // The negated signature, and the signature of another signer,
+            // do not verify.
+            assert!(!partial_verify(
+                &-partial_sig,
+                &nonces[signer],
+                &pks[0],
+                &agg_nonce,
+                &signers,
+                msg,
+            ));
+            let other = (signer + 1) % signers.len();
+            assert!(!partial_verify(
+                &partial_sig,
+                &nonces[other],
+                &signers[other],
+                &agg_nonce,
+                &signers,
+                msg,
+            ));
+        }
+        println!("MuSig2::SignVerify: Passed!");
+    }
+
+    #[test]
+    pub fn test_musig2_sig_agg() {
+        let pks = points(&[
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02D2DC6F5DF7C56ACF38C7FA0AE7A759AE30E19B37359DFDE015872324C7EF6E05",
+            "03C7FB101D97FF930ACD0C6760852EF64E69083DE0B06AC6335724754BB4B0522C",
+        ]);
+        let msg = bytes(
+            "599C67EA410D005B9DA90817CF03ED3B1C868E4DA4EDF00A5880B0082C237869",
+        );
+        // The signers, the aggregated nonce, the partial signatures and
+        // the signature.
+        let cases: [(&[usize], &str, [&str; 2], &str); 2] = [
+            (
+                &[0, 1],
+                "0341432722C5CD0268D829C702CF0D1CBCE57033EED201FD335191385227C3210C03D377F2D258B64AADC0E16F26462323D701D286046A2EA93365656AFD9875982B",
+                [
+                    "B15D2CD3C3D22B04DAE438CE653F6B4ECF042F42CFDED7C41B64AAF9B4AF53FB",
+                    "6193D6AC61B354E9105BBDC8937A3454A6D705B6D57322A5A472A02CE99FCB64",
+                ],
+                "041DA22223CE65C92C9A0D6C2CAC828AAF1EEE56304FEC371DDF91EBB2B9EF0912F1038025857FEDEB3FF696F8B99FA4BB2C5812F6095A2E0004EC99CE18DE1E",
+            ),
+            (
+                &[0, 2],
+                "0224AFD36C902084058B51B5D36676BBA4DC97C775873768E58822F87FE437D792028CB15929099EEE2F5DAE404CD39357591BA32E9AF4E162B8D3E7CB5EFE31CB20",
+                [
+                    "9A87D3B79EC67228CB97878B76049B15DBD05B8158D17B5B9114D3C226887505",
+                    "66F82EA90923689B855D36C6B7E032FB9970301481B99E01CDB4D6AC7C347A15",
+                ],
+                "1069B67EC3D2F3C7C08291ACCB17A9C9B8F2819A52EB5DF8726E17E7D6B52E9F01800260A7E9DAC450F4BE522DE4CE12BA91AEAF2B4279219EF74BE1D286ADD9",
+            ),
+        ];
+        for (key_indices, agg_nonce_hex, partial_sigs, expected) in cases {
+            let signers: Vec<AffinePoint> =
+                key_indices.iter().map(|&i| pks[i]).collect();
+            let partial_sigs: Vec<Scalar> =
+                partial_sigs.iter().map(|sig| scalar(sig)).collect();
+            let signature = partial_sig_agg(
+                &partial_sigs,
+                &agg_nonce(agg_nonce_hex),
+                &signers,
+                &msg,
+            )
+            .unwrap();
+            assert_eq!(hex::encode_upper(signature), expected);
+
+            let x_only_pk = xbytes(&key_agg(&signers).unwrap());
+            assert!(verify_schnorr_signature(&msg, &x_only_pk, &signature));
+        }
+        println!("MuSig2::SigAgg: Passed!");
+    }
+
+    #[test]
+    pub fn test_musig2_sign() {
+        let msg = "Message to sign!".as_bytes();
+
+        let secret_keys: Vec<Scalar> =
+            (0..3).map(|_| Scalar::random(rng::get_rng())).collect();
+        let pks: Vec<AffinePoint> = secret_keys
+            .iter()
+            .map(|sk| (ProjectivePoint::GENERATOR * sk).to_affine())
+            .collect();
+        let pks = key_sort(&pks);
+        let agg_pk = key_agg(&pks).unwrap();
+
+        let mut sec_nonces = Vec::new();
+        let mut pub_nonces = Vec::new();
+        for sk in &secret_keys {
+            let pk = (ProjectivePoint::GENERATOR * sk).to_affine();
+            let (sec_nonce, pub_nonce) =
+                nonce_gen(Some(sk), &pk, Some(&agg_pk), Some(msg));
+            sec_nonces.push(sec_nonce);
+            pub_nonces.push(pub_nonce);
+        }
+        let agg_nonce = nonce_agg(&pub_nonces).unwrap();
+
+        let mut partial_sigs = Vec::new();
+        for i in 0..secret_keys.len() {
+            let partial_sig = partial_sign(
+                &sec_nonces[i],
+                &secret_keys[i],
+                &agg_nonce,
+                &pks,
+                msg,
+            )
+            .unwrap();
+            assert!(partial_verify(
+                &partial_sig,
+                &pub_nonces[i],
+                &sec_nonces[i].pk,
+                &agg_nonce,
+                &pks,
+                msg,
+            ));
+            partial_sigs.push(partial_sig);
+        }
+
+        let signature =
+            partial_sig_agg(&partial_sigs, &agg_nonce, &pks, msg).unwrap();
+        let x_only_pk = xbytes(&agg_pk);
+        assert!(verify_schnorr_signature(msg, &x_only_pk, &signature));
+
+        // Cross-check with the BIP-340 implementation from k256.
+        let verifying_key = VerifyingKey::from_bytes(&x_only_pk).unwrap();
+        let signature = Signature::try_from(&signature[..]).unwrap();
+        assert!(verifying_key.verify_raw(msg, &signature).is_ok());
+        println!("MuSig2::Sign: Passed!");
+    }
+
+    #[test]
+    pub fn test_musig2_nonce_used_once() {
+        let sk = Scalar::random(rng::get_rng());
+        let pk = (ProjectivePoint::GENERATOR * sk).to_affine();
+
+        // The secret nonce stays in the library, behind its id.
+        let nonce_out = call(musig2_nonce_gen, json!({ "pk": pk }));
+        assert!(nonce_out.get("sec_nonce").is_none());
+        let sign_in = json!({
+            "secret_key": sk,
+            "nonce_id": nonce_out["nonce_id"],
+            "agg_nonce": nonce_out["pub_nonce"],
+            "pks": [pk],
+            "msg": [1, 2, 3],
+        });
+        let sign_out = call(musig2_partial_sign, sign_in.clone());
+        assert!(sign_out.get("partial_sig").is_some());
+
+        // A second signature with the same nonce is refused.
+        let sign_out = call(musig2_partial_sign, sign_in);
+        assert!(sign_out.get("error").is_some());
+        println!("MuSig2::Nonce used once: Passed!");
+    }
+}
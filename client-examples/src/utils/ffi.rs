@@ -0,0 +1,22 @@
+use serde_json::Value;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+// Calls an FFI function directly, the same way the C client does.
+pub fn call_ffi(
+    ffi_func: extern "C" fn(*const c_char) -> *const c_char,
+    json_in: &str,
+) -> String {
+    let data = CString::new(json_in).unwrap();
+    let json_out = ffi_func(data.as_ptr());
+    let json_out = unsafe { CString::from_raw(json_out as *mut c_char) };
+    json_out.to_str().unwrap().to_string()
+}
+
+// Calls an FFI function with JSON values in and out.
+pub fn call(
+    ffi_func: extern "C" fn(*const c_char) -> *const c_char,
+    json_in: Value,
+) -> Value {
+    serde_json::from_str(&call_ffi(ffi_func, &json_in.to_string())).unwrap()
+}
@@ -1,2 +1,3 @@
+pub mod ffi;
 pub mod files;
 pub mod hash;
@@ -0,0 +1,144 @@
+use crate::relay::{check_id, Envelope, RelayError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+// A small client for the relay, one request per connection, so that
+// parties written in Rust need no HTTP library.
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ClientError {
+    Io { description: String },
+    // The answer of the relay cannot be parsed.
+    Malformed { description: String },
+    Relay(RelayError),
+    // Not every message arrived in time.
+    Timeout { phase: u8, received: usize },
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(error: std::io::Error) -> ClientError {
+        ClientError::Io {
+            description: error.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: RelayError,
+}
+
+pub struct RelayClient {
+    // host:port
+    addr: String,
+    session: String,
+}
+
+impl RelayClient {
+    pub fn new(addr: &str, session: &str) -> Result<RelayClient, ClientError> {
+        check_id(session).map_err(ClientError::Relay)?;
+        Ok(RelayClient {
+            addr: addr.to_string(),
+            session: session.to_string(),
+        })
+    }
+
+    fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, ClientError> {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(&self.addr)?;
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            self.addr,
+            body.len(),
+            body
+        )?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        let malformed = |description: &str| ClientError::Malformed {
+            description: description.to_string(),
+        };
+        let response = String::from_utf8(response)
+            .map_err(|_| malformed("The response is not UTF-8!"))?;
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| malformed("The response has no body!"))?;
+        let status: u16 = head
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| malformed("The response has no status!"))?;
+
+        if status != 200 {
+            let error: ErrorBody = serde_json::from_str(body)
+                .map_err(|error| malformed(&error.to_string()))?;
+            return Err(ClientError::Relay(error.error));
+        }
+        serde_json::from_str(body)
+            .map_err(|error| malformed(&error.to_string()))
+    }
+
+    pub fn post(&self, envelopes: &[Envelope]) -> Result<usize, ClientError> {
+        let path = format!("/sessions/{}/messages", self.session);
+        let body = serde_json::to_value(envelopes).unwrap();
+        let accepted: Value = self.request("POST", &path, Some(&body))?;
+        Ok(accepted["accepted"].as_u64().unwrap_or(0) as usize)
+    }
+
+    pub fn fetch(
+        &self,
+        phase: u8,
+        receiver: u8,
+    ) -> Result<Vec<Envelope>, ClientError> {
+        let path = format!(
+            "/sessions/{}/messages/{}/{}",
+            self.session, phase, receiver
+        );
+        self.request("GET", &path, None)
+    }
+
+    // Polls until `count` messages of the phase have arrived.
+    pub fn wait(
+        &self,
+        phase: u8,
+        receiver: u8,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<Vec<Envelope>, ClientError> {
+        let start = Instant::now();
+        loop {
+            let messages = self.fetch(phase, receiver)?;
+            if messages.len() >= count {
+                return Ok(messages);
+            }
+            if start.elapsed() > timeout {
+                return Err(ClientError::Timeout {
+                    phase,
+                    received: messages.len(),
+                });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn remove(&self) -> Result<bool, ClientError> {
+        let path = format!("/sessions/{}", self.session);
+        let removed: Value = self.request("DELETE", &path, None)?;
+        Ok(removed["removed"].as_bool().unwrap_or(false))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod client;
+pub mod relay;
+pub mod server;
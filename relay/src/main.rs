@@ -0,0 +1,17 @@
+use libtss_relay::server::Server;
+
+// Usage: libtss-relay [ADDR], where ADDR defaults to 127.0.0.1:7400.
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("127.0.0.1:7400"));
+    let server = match Server::bind(&addr) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("Cannot listen on {}: {}", addr, error);
+            std::process::exit(1);
+        }
+    };
+    println!("libtss-relay listening on {}", server.local_addr());
+    server.run();
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// The relay keeps the messages of every session until the session is
+// removed. A message with a receiver is only served to that party, and
+// a broadcast, without one, to every party but its sender. Nothing is
+// taken out when it is served, so a client that restarts can fetch its
+// messages again.
+
+pub const MAX_SESSIONS: usize = 1024;
+pub const MAX_MESSAGES: usize = 4096;
+pub const MAX_ID_LENGTH: usize = 128;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RelayError {
+    // A request the relay does not serve.
+    NotFound,
+    BadRequest {
+        description: String,
+    },
+    // Another message was already posted for the same phase, sender and
+    // receiver. Posting the same message again is fine.
+    Conflict {
+        phase: u8,
+        sender: u8,
+        receiver: Option<u8>,
+    },
+    // Too many sessions, or too many messages in a session.
+    Full,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Envelope {
+    pub phase: u8,
+    pub sender: u8,
+    // None for a broadcast.
+    #[serde(default)]
+    pub receiver: Option<u8>,
+    // The message itself, which the relay does not look into.
+    pub payload: Value,
+}
+
+impl Envelope {
+    fn is_for(&self, phase: u8, receiver: u8) -> bool {
+        self.phase == phase
+            && match self.receiver {
+                Some(to) => to == receiver,
+                None => self.sender != receiver,
+            }
+    }
+}
+
+// Session ids are given in hex, as in the URLs.
+pub fn check_id(id: &str) -> Result<(), RelayError> {
+    if id.is_empty()
+        || id.len() > MAX_ID_LENGTH
+        || !id.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(RelayError::BadRequest {
+            description: String::from("Session ids must be in hex!"),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct Relay {
+    sessions: Mutex<HashMap<String, Vec<Envelope>>>,
+}
+
+impl Relay {
+    pub fn new() -> Relay {
+        Relay::default()
+    }
+
+    // Returns how many of the messages were new.
+    pub fn post(
+        &self,
+        id: &str,
+        envelopes: Vec<Envelope>,
+    ) -> Result<usize, RelayError> {
+        check_id(id)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        if !sessions.contains_key(id) && sessions.len() >= MAX_SESSIONS {
+            return Err(RelayError::Full);
+        }
+        let session = sessions.entry(id.to_string()).or_default();
+
+        // Nothing is kept unless every message can be.
+        let mut new = Vec::new();
+        for envelope in envelopes {
+            let posted = session.iter().chain(new.iter()).find(|posted| {
+                posted.phase == envelope.phase
+                    && posted.sender == envelope.sender
+                    && posted.receiver == envelope.receiver
+            });
+            match posted {
+                Some(posted) if *posted == envelope => continue,
+                Some(_) => {
+                    return Err(RelayError::Conflict {
+                        phase: envelope.phase,
+                        sender: envelope.sender,
+                        receiver: envelope.receiver,
+                    })
+                }
+                None => new.push(envelope),
+            }
+        }
+        if session.len() + new.len() > MAX_MESSAGES {
+            return Err(RelayError::Full);
+        }
+        let accepted = new.len();
+        session.extend(new);
+        Ok(accepted)
+    }
+
+    // The messages of a phase for a party, in the order they were
+    // posted. A session nobody posted to yet has no messages.
+    pub fn fetch(
+        &self,
+        id: &str,
+        phase: u8,
+        receiver: u8,
+    ) -> Result<Vec<Envelope>, RelayError> {
+        check_id(id)?;
+        let sessions = self.sessions.lock().unwrap();
+        let messages = sessions
+            .get(id)
+            .map(|session| {
+                session
+                    .iter()
+                    .filter(|envelope| envelope.is_for(phase, receiver))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(messages)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<bool, RelayError> {
+        check_id(id)?;
+        Ok(self.sessions.lock().unwrap().remove(id).is_some())
+    }
+}
@@ -0,0 +1,131 @@
+use crate::relay::{Envelope, Relay, RelayError};
+use serde::Serialize;
+use serde_json::json;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tiny_http::{Header, Method, Request, Response};
+
+// The relay over HTTP, with JSON bodies:
+//
+//   POST   /sessions/{id}/messages                    [Envelope]
+//   GET    /sessions/{id}/messages/{phase}/{receiver}
+//   DELETE /sessions/{id}
+//
+// Errors are answered as {"error": RelayError}. The relay does not
+// authenticate anyone and is meant to listen on localhost, or behind a
+// proxy that does.
+
+pub const MAX_BODY: u64 = 1 << 20;
+
+pub struct Server {
+    server: tiny_http::Server,
+    relay: Arc<Relay>,
+}
+
+impl Server {
+    pub fn bind(addr: &str) -> Result<Server, String> {
+        let server =
+            tiny_http::Server::http(addr).map_err(|error| error.to_string())?;
+        Ok(Server {
+            server,
+            relay: Arc::new(Relay::new()),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.server.server_addr().to_ip().unwrap()
+    }
+
+    pub fn relay(&self) -> Arc<Relay> {
+        self.relay.clone()
+    }
+
+    // Serves requests until the process ends.
+    pub fn run(&self) {
+        for request in self.server.incoming_requests() {
+            let _ = respond(&self.relay, request);
+        }
+    }
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<impl Read> {
+    let header =
+        Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .unwrap();
+    Response::from_string(serde_json::to_string(body).unwrap())
+        .with_status_code(status)
+        .with_header(header)
+}
+
+fn status(error: &RelayError) -> u16 {
+    match error {
+        RelayError::NotFound => 404,
+        RelayError::BadRequest { .. } => 400,
+        RelayError::Conflict { .. } => 409,
+        RelayError::Full => 503,
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, RelayError> {
+    value.parse().map_err(|_| RelayError::BadRequest {
+        description: format!("Invalid number: {}", value),
+    })
+}
+
+fn read_envelopes(request: &mut Request) -> Result<Vec<Envelope>, RelayError> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|error| RelayError::BadRequest {
+            description: error.to_string(),
+        })?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(RelayError::BadRequest {
+            description: String::from("The body is too large!"),
+        });
+    }
+    serde_json::from_slice(&body).map_err(|error| RelayError::BadRequest {
+        description: error.to_string(),
+    })
+}
+
+fn handle(
+    relay: &Relay,
+    request: &mut Request,
+) -> Result<serde_json::Value, RelayError> {
+    let url = request.url().to_string();
+    let path: Vec<&str> = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_matches('/')
+        .split('/')
+        .collect();
+
+    match (request.method(), path.as_slice()) {
+        (Method::Post, ["sessions", id, "messages"]) => {
+            let envelopes = read_envelopes(request)?;
+            let accepted = relay.post(id, envelopes)?;
+            Ok(json!({ "accepted": accepted }))
+        }
+        (Method::Get, ["sessions", id, "messages", phase, receiver]) => {
+            let messages = relay.fetch(id, parse(phase)?, parse(receiver)?)?;
+            Ok(serde_json::to_value(messages).unwrap())
+        }
+        (Method::Delete, ["sessions", id]) => {
+            Ok(json!({ "removed": relay.remove(id)? }))
+        }
+        _ => Err(RelayError::NotFound),
+    }
+}
+
+fn respond(relay: &Relay, mut request: Request) -> std::io::Result<()> {
+    match handle(relay, &mut request) {
+        Ok(body) => request.respond(json_response(200, &body)),
+        Err(error) => request
+            .respond(json_response(status(&error), &json!({ "error": error }))),
+    }
+}
@@ -0,0 +1,191 @@
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::SignData;
+use dkls23::protocols::{Parameters, Party};
+use ffi_tss::dkls23::session::dkg::{DkgSession, DKG_ROUNDS};
+use ffi_tss::dkls23::session::sign::{SignSession, Signature, SIGN_ROUNDS};
+use ffi_tss::dkls23::session::{Message, Payload};
+use libtss_relay::client::RelayClient;
+use libtss_relay::relay::{Envelope, RelayError};
+use libtss_relay::server::Server;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// A relay on a free port, serving from its own thread.
+fn start_relay() -> String {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().to_string();
+    std::thread::spawn(move || server.run());
+    addr
+}
+
+fn post<P: Payload + Serialize>(client: &RelayClient, messages: &[Message<P>]) {
+    let envelopes: Vec<Envelope> = messages
+        .iter()
+        .map(|message| Envelope {
+            phase: message.payload.round(),
+            sender: message.sender,
+            receiver: Some(message.receiver),
+            payload: serde_json::to_value(message).unwrap(),
+        })
+        .collect();
+    client.post(&envelopes).unwrap();
+}
+
+fn wait<P: DeserializeOwned>(
+    client: &RelayClient,
+    round: u8,
+    receiver: u8,
+    count: usize,
+) -> Vec<Message<P>> {
+    client
+        .wait(round, receiver, count, TIMEOUT)
+        .unwrap()
+        .into_iter()
+        .map(|envelope| serde_json::from_value(envelope.payload).unwrap())
+        .collect()
+}
+
+fn run_dkg(addr: &str, session: SessionData) -> Party {
+    let client = RelayClient::new(addr, &hex(&session.session_id)).unwrap();
+    let index = session.party_index;
+    let mut session = DkgSession::new(session);
+    let count = session.counterparties().len();
+
+    let mut outgoing = session.start().unwrap();
+    for round in 1..=DKG_ROUNDS {
+        post(&client, &outgoing);
+        for message in wait(&client, round, index, count) {
+            session.receive(message).unwrap();
+        }
+        outgoing = session.advance().unwrap();
+    }
+    session.finish().unwrap()
+}
+
+fn run_sign(addr: &str, party: Party, sign_data: SignData) -> Signature {
+    let client = RelayClient::new(addr, &hex(&sign_data.sign_id)).unwrap();
+    let index = party.party_index;
+    let mut session = SignSession::new(party, sign_data, false);
+    let count = session.counterparties().len();
+
+    let mut outgoing = session.start().unwrap();
+    for round in 1..=SIGN_ROUNDS {
+        post(&client, &outgoing);
+        for message in wait(&client, round, index, count) {
+            session.receive(message).unwrap();
+        }
+        outgoing = session.advance().unwrap();
+    }
+    session.finish().unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn test_relay_routing() {
+    let addr = start_relay();
+    let client = RelayClient::new(&addr, "0a0b").unwrap();
+    let transmit = Envelope {
+        phase: 1,
+        sender: 1,
+        receiver: Some(2),
+        payload: json!({ "fragment": 1 }),
+    };
+    let broadcast = Envelope {
+        phase: 1,
+        sender: 3,
+        receiver: None,
+        payload: json!({ "commitment": 3 }),
+    };
+    assert_eq!(client.post(&[transmit.clone(), broadcast.clone()]), Ok(2));
+
+    assert_eq!(
+        client.fetch(1, 2).unwrap(),
+        vec![transmit.clone(), broadcast.clone()]
+    );
+    assert_eq!(client.fetch(1, 1).unwrap(), vec![broadcast.clone()]);
+    assert_eq!(client.fetch(1, 3).unwrap(), Vec::new());
+    assert_eq!(client.fetch(2, 2).unwrap(), Vec::new());
+
+    // A retry is accepted, another message in its place is not.
+    assert_eq!(client.post(std::slice::from_ref(&transmit)), Ok(0));
+    let mut changed = transmit;
+    changed.payload = json!({ "fragment": 2 });
+    let error = client.post(&[changed]).unwrap_err();
+    assert_eq!(
+        error,
+        libtss_relay::client::ClientError::Relay(RelayError::Conflict {
+            phase: 1,
+            sender: 1,
+            receiver: Some(2),
+        })
+    );
+
+    // Sessions do not see each other's messages.
+    let other = RelayClient::new(&addr, "0c0d").unwrap();
+    assert_eq!(other.fetch(1, 2).unwrap(), Vec::new());
+    assert!(RelayClient::new(&addr, "../0a0b").is_err());
+
+    assert_eq!(client.remove(), Ok(true));
+    assert_eq!(client.fetch(1, 2).unwrap(), Vec::new());
+}
+
+#[test]
+fn test_relay_dkg_and_sign() {
+    let addr = start_relay();
+    let parameters = Parameters {
+        threshold: 2,
+        share_count: 3,
+    };
+    let session_id: Vec<u8> = (0..32).collect();
+
+    let handles: Vec<_> = (1..=parameters.share_count)
+        .map(|party_index| {
+            let addr = addr.clone();
+            let session = SessionData {
+                parameters: parameters.clone(),
+                party_index,
+                session_id: session_id.clone(),
+            };
+            std::thread::spawn(move || run_dkg(&addr, session))
+        })
+        .collect();
+    let parties: BTreeMap<u8, Party> = handles
+        .into_iter()
+        .map(|handle| {
+            let party = handle.join().unwrap();
+            (party.party_index, party)
+        })
+        .collect();
+    assert_eq!(parties[&1].pk, parties[&2].pk);
+    assert_eq!(parties[&1].pk, parties[&3].pk);
+
+    // Parties 1 and 3 sign.
+    let sign_id: Vec<u8> = (32..64).collect();
+    let handles: Vec<_> = [(1, 3), (3, 1)]
+        .into_iter()
+        .map(|(index, counterparty)| {
+            let addr = addr.clone();
+            let party = parties[&index].clone();
+            let sign_data = SignData {
+                sign_id: sign_id.clone(),
+                counterparties: vec![counterparty],
+                message_hash: [7; 32],
+            };
+            std::thread::spawn(move || run_sign(&addr, party, sign_data))
+        })
+        .collect();
+    let signatures: Vec<Signature> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(signatures[0].signature, signatures[1].signature);
+    assert_eq!(signatures[0].rec_id, signatures[1].rec_id);
+}
@@ -0,0 +1,127 @@
+use clap::{Parser, ValueEnum};
+use dkls23::protocols::Parameters;
+use libtss_simulator::network::NetworkConfig;
+use libtss_simulator::report::Summary;
+use libtss_simulator::simulation::{simulate_dkg, simulate_sign};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Protocol {
+    Dkg,
+    Sign,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "libtss-simulator",
+    about = "Runs DKG and signing over a faulty virtual network"
+)]
+struct Args {
+    #[arg(long, value_enum, default_value = "dkg")]
+    protocol: Protocol,
+    #[arg(long, default_value_t = 3)]
+    parties: u8,
+    #[arg(long, default_value_t = 2)]
+    threshold: u8,
+    #[arg(long, default_value_t = 20)]
+    runs: u64,
+    /// The seed of the first run. Run i uses seed + i.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Latency bounds in virtual milliseconds.
+    #[arg(long, default_value_t = 5)]
+    latency_min: u64,
+    #[arg(long, default_value_t = 50)]
+    latency_max: u64,
+    /// Probabilities per message, from 0 to 1.
+    #[arg(long, default_value_t = 0.0)]
+    loss: f64,
+    #[arg(long, default_value_t = 0.0)]
+    duplicate: f64,
+    #[arg(long, default_value_t = 0.0)]
+    reorder: f64,
+    #[arg(long, default_value_t = 0.0)]
+    corrupt: f64,
+    /// Parties whose messages get tampered with, with --tamper.
+    #[arg(long, value_delimiter = ',')]
+    byzantine: Vec<u8>,
+    #[arg(long, default_value_t = 0.0)]
+    tamper: f64,
+    /// Prints the summary as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+// Exits with 1 when a fault slipped through, so that it can run in CI.
+fn main() {
+    let args = Args::parse();
+    let probabilities = [
+        args.loss,
+        args.duplicate,
+        args.reorder,
+        args.corrupt,
+        args.tamper,
+    ];
+    if probabilities.iter().any(|p| !(0.0..=1.0).contains(p))
+        || args.threshold < 2
+        || args.threshold > args.parties
+    {
+        eprintln!(
+            "Probabilities must be in [0, 1] and 2 <= threshold <= parties."
+        );
+        std::process::exit(2);
+    }
+
+    let parameters = Parameters {
+        threshold: args.threshold,
+        share_count: args.parties,
+    };
+    let config = |seed: u64| NetworkConfig {
+        latency: (args.latency_min, args.latency_max),
+        loss: args.loss,
+        duplicate: args.duplicate,
+        reorder: args.reorder,
+        corrupt: args.corrupt,
+        byzantine: args.byzantine.clone(),
+        tamper: args.tamper,
+        seed,
+        ..NetworkConfig::default()
+    };
+
+    // The keys to sign with come from a DKG over a perfect network.
+    let signers = match args.protocol {
+        Protocol::Dkg => Vec::new(),
+        Protocol::Sign => {
+            let dkg = simulate_dkg(&parameters, &NetworkConfig::default());
+            if !dkg.report.is_correct() {
+                eprintln!("The DKG without faults failed: {:?}", dkg.report);
+                std::process::exit(2);
+            }
+            dkg.outputs
+                .into_values()
+                .take(args.threshold as usize)
+                .collect()
+        }
+    };
+
+    let mut summary = Summary::default();
+    for run in 0..args.runs {
+        let config = config(args.seed + run);
+        let report = match args.protocol {
+            Protocol::Dkg => simulate_dkg(&parameters, &config).report,
+            Protocol::Sign => {
+                let message_hash = [run as u8; 32];
+                simulate_sign(&signers, message_hash, &config).report
+            }
+        };
+        summary.add(&report);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    } else {
+        print!("{}", summary);
+    }
+    if !summary.slipped.is_empty() {
+        std::process::exit(1);
+    }
+}
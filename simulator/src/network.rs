@@ -0,0 +1,362 @@
+use ffi_tss::dkls23::transport::{Transport, TransportError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::future::{poll_fn, Future};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+// A virtual network between the parties of one run. Time is counted in
+// virtual milliseconds and only moves when every party is waiting, so a
+// run takes no real time and the same seed gives the same faults. Each
+// message sent may be lost, delayed past later ones, delivered twice or
+// corrupted on the way, and the messages of byzantine parties may have
+// one of their values changed.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct NetworkConfig {
+    // Every message takes between these many milliseconds to arrive.
+    pub latency: (u64, u64),
+    // The probabilities of each fault, per message.
+    pub loss: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+    pub corrupt: f64,
+    // A reordered message is held back by up to this many milliseconds
+    // on top of its latency.
+    pub reorder_delay: u64,
+    // Parties that tamper with the messages they send, and how often.
+    pub byzantine: Vec<u8>,
+    pub tamper: f64,
+    pub seed: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> NetworkConfig {
+        NetworkConfig {
+            latency: (5, 50),
+            loss: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            corrupt: 0.0,
+            reorder_delay: 200,
+            byzantine: Vec::new(),
+            tamper: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum FaultKind {
+    Drop,
+    Duplicate,
+    Reorder,
+    // A bit flipped on the wire.
+    Corrupt,
+    // A value changed by a byzantine sender, with its JSON path.
+    Tamper { field: String },
+}
+
+impl FaultKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FaultKind::Drop => "drop",
+            FaultKind::Duplicate => "duplicate",
+            FaultKind::Reorder => "reorder",
+            FaultKind::Corrupt => "corrupt",
+            FaultKind::Tamper { .. } => "tamper",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub sender: u8,
+    pub receiver: u8,
+    // The round of the message, when it could still be read.
+    pub round: Option<u8>,
+    // When the message was sent, in virtual milliseconds.
+    pub at: u64,
+}
+
+// Reads the round of a message, for the fault log.
+pub type RoundOf = fn(&[u8]) -> Option<u8>;
+
+struct State {
+    config: NetworkConfig,
+    rng: StdRng,
+    round_of: RoundOf,
+    now: u64,
+    // Messages on the way, by delivery time and then by order sent.
+    in_flight: BTreeMap<(u64, u64), (u8, u8, Vec<u8>)>,
+    sent: u64,
+    // When the last message in order on each link is due. Links are
+    // first in, first out unless a message is reordered.
+    due: BTreeMap<(u8, u8), u64>,
+    inboxes: BTreeMap<u8, VecDeque<(u8, Vec<u8>)>>,
+    // Parties whose transport is still alive.
+    open: BTreeSet<u8>,
+    faults: Vec<Fault>,
+}
+
+impl State {
+    fn log(&mut self, kind: FaultKind, sender: u8, receiver: u8, bytes: &[u8]) {
+        let round = (self.round_of)(bytes);
+        self.faults.push(Fault {
+            kind,
+            sender,
+            receiver,
+            round,
+            at: self.now,
+        });
+    }
+
+    fn schedule(&mut self, at: u64, message: (u8, u8, Vec<u8>)) {
+        self.sent += 1;
+        self.in_flight.insert((at, self.sent), message);
+    }
+
+    // When a message sent now arrives.
+    fn arrival(&mut self) -> u64 {
+        let (min, max) = self.config.latency;
+        self.now + self.rng.gen_range(min..=max.max(min))
+    }
+
+    fn send(&mut self, sender: u8, receiver: u8, mut bytes: Vec<u8>) {
+        if self.config.byzantine.contains(&sender)
+            && self.rng.gen_bool(self.config.tamper)
+        {
+            let field = tamper(&mut self.rng, &mut bytes);
+            self.log(FaultKind::Tamper { field }, sender, receiver, &bytes);
+        }
+        if self.rng.gen_bool(self.config.loss) {
+            self.log(FaultKind::Drop, sender, receiver, &bytes);
+            return;
+        }
+        if self.rng.gen_bool(self.config.duplicate) {
+            self.log(FaultKind::Duplicate, sender, receiver, &bytes);
+            let at = self.arrival();
+            self.schedule(at, (sender, receiver, bytes.clone()));
+        }
+
+        let mut at = self.arrival();
+        if self.rng.gen_bool(self.config.reorder) {
+            self.log(FaultKind::Reorder, sender, receiver, &bytes);
+            at += self.rng.gen_range(1..=self.config.reorder_delay.max(1));
+        } else {
+            let due = self.due.entry((sender, receiver)).or_default();
+            at = at.max(*due);
+            *due = at;
+        }
+        if self.rng.gen_bool(self.config.corrupt) && !bytes.is_empty() {
+            self.log(FaultKind::Corrupt, sender, receiver, &bytes);
+            let byte = self.rng.gen_range(0..bytes.len());
+            bytes[byte] ^= 1 << self.rng.gen_range(0..8);
+        }
+        self.schedule(at, (sender, receiver, bytes));
+    }
+}
+
+// Changes one value of the message and gives its path. Bytes that are
+// not JSON get a bit flipped instead.
+fn tamper(rng: &mut StdRng, bytes: &mut Vec<u8>) -> String {
+    let mut message: Value = match serde_json::from_slice(bytes) {
+        Ok(message) => message,
+        Err(_) => {
+            let byte = rng.gen_range(0..bytes.len().max(1));
+            if let Some(byte) = bytes.get_mut(byte) {
+                *byte ^= 1;
+            }
+            return String::from("(bytes)");
+        }
+    };
+
+    let mut leaves = Vec::new();
+    collect_leaves(&message["payload"], String::from("payload"), &mut leaves);
+    if leaves.is_empty() {
+        return String::from("(none)");
+    }
+    let path = leaves.swap_remove(rng.gen_range(0..leaves.len()));
+    let value =
+        path.split('.')
+            .skip(1)
+            .fold(&mut message["payload"], |value, key| match value {
+                Value::Array(values) => {
+                    &mut values[key.parse::<usize>().unwrap()]
+                }
+                value => &mut value[key],
+            });
+    *value = match value.take() {
+        Value::Bool(flag) => Value::Bool(!flag),
+        Value::Number(number) => {
+            Value::from(number.as_u64().map_or(1, |number| number ^ 1))
+        }
+        Value::String(text) => Value::String(change_char(rng, &text)),
+        value => value,
+    };
+    *bytes = serde_json::to_vec(&message).unwrap();
+    path
+}
+
+fn collect_leaves(value: &Value, path: String, leaves: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                collect_leaves(value, format!("{}.{}", path, key), leaves);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                collect_leaves(value, format!("{}.{}", path, i), leaves);
+            }
+        }
+        Value::Null => {}
+        _ => leaves.push(path),
+    }
+}
+
+// Replaces one character by another hex digit, so that hex stays hex.
+fn change_char(rng: &mut StdRng, text: &str) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return String::from("0");
+    }
+    let i = rng.gen_range(0..chars.len());
+    chars[i] = if chars[i] == '0' { '1' } else { '0' };
+    chars.into_iter().collect()
+}
+
+#[derive(Clone)]
+pub struct Network {
+    state: Arc<Mutex<State>>,
+}
+
+impl Network {
+    pub fn new(
+        config: NetworkConfig,
+        parties: &[u8],
+        round_of: RoundOf,
+    ) -> Network {
+        let state = State {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            round_of,
+            now: 0,
+            in_flight: BTreeMap::new(),
+            sent: 0,
+            due: BTreeMap::new(),
+            inboxes: parties
+                .iter()
+                .map(|&index| (index, VecDeque::new()))
+                .collect(),
+            open: parties.iter().copied().collect(),
+            faults: Vec::new(),
+        };
+        Network {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn transport(&self, index: u8) -> SimTransport {
+        SimTransport {
+            index,
+            state: self.state.clone(),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.state.lock().unwrap().now
+    }
+
+    pub fn faults(&self) -> Vec<Fault> {
+        self.state.lock().unwrap().faults.clone()
+    }
+
+    // Moves the messages that are due into the inboxes, and says whether
+    // any inbox has something to read.
+    pub fn deliver(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = state.now;
+        while let Some(entry) = state.in_flight.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            let (sender, receiver, bytes) = entry.remove();
+            if state.open.contains(&receiver) {
+                let inbox = state.inboxes.get_mut(&receiver).unwrap();
+                inbox.push_back((sender, bytes));
+            }
+        }
+        state.inboxes.values().any(|inbox| !inbox.is_empty())
+    }
+
+    // Moves the clock to the next delivery. There is none when every
+    // message has arrived or was lost.
+    pub fn advance(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.in_flight.keys().next() {
+            Some(&(at, _)) => {
+                state.now = at;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct SimTransport {
+    index: u8,
+    state: Arc<Mutex<State>>,
+}
+
+impl Transport for SimTransport {
+    fn send(
+        &mut self,
+        receiver: u8,
+        message: Vec<u8>,
+    ) -> impl Future<Output = Result<(), TransportError>> + Send {
+        let mut state = self.state.lock().unwrap();
+        let result = if state.inboxes.contains_key(&receiver)
+            && receiver != self.index
+        {
+            state.send(self.index, receiver, message);
+            Ok(())
+        } else {
+            Err(TransportError::UnknownParty { index: receiver })
+        };
+        async { result }
+    }
+
+    fn receive(
+        &mut self,
+    ) -> impl Future<Output = Result<(u8, Vec<u8>), TransportError>> + Send
+    {
+        let index = self.index;
+        let state = self.state.clone();
+        poll_fn(move |_| {
+            let mut state = state.lock().unwrap();
+            let inbox = state.inboxes.get_mut(&index).unwrap();
+            if let Some(message) = inbox.pop_front() {
+                return Poll::Ready(Ok(message));
+            }
+            let waiting = state.in_flight.values().any(|m| m.1 == index);
+            if !waiting && state.open.iter().all(|&open| open == index) {
+                return Poll::Ready(Err(TransportError::Closed));
+            }
+            // The simulation polls every party again after each step.
+            Poll::Pending
+        })
+    }
+}
+
+impl Drop for SimTransport {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.open.remove(&self.index);
+        state.inboxes.get_mut(&self.index).unwrap().clear();
+    }
+}
@@ -0,0 +1,189 @@
+use crate::network::Fault;
+use ffi_tss::dkls23::transport::RunError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// What became of each party and of each fault in a run. A fault is
+// judged by the outcome of its receiver, since the receiver is the one
+// that has to notice it: a party that fails was told something is
+// wrong, a party that waits forever needs the orchestration to time it
+// out, and a party that finishes either got a right result anyway or was
+// fooled.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Outcome {
+    Finished { valid: bool },
+    Failed { error: RunError },
+    // Still waiting when no message was left on the way.
+    Stalled,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Verdict {
+    // The receiver failed with an error.
+    Caught,
+    // The receiver stalled, and only a deadline would catch it.
+    TimedOut,
+    // The receiver finished with a valid result.
+    Tolerated,
+    // The receiver finished with an invalid result.
+    Slipped,
+}
+
+impl Verdict {
+    pub fn of(outcome: &Outcome) -> Verdict {
+        match outcome {
+            Outcome::Failed { .. } => Verdict::Caught,
+            Outcome::Stalled => Verdict::TimedOut,
+            Outcome::Finished { valid: true } => Verdict::Tolerated,
+            Outcome::Finished { valid: false } => Verdict::Slipped,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FaultReport {
+    pub fault: Fault,
+    pub verdict: Verdict,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Report {
+    pub protocol: String,
+    pub seed: u64,
+    pub outcomes: BTreeMap<u8, Outcome>,
+    // Virtual milliseconds until the last party stopped.
+    pub elapsed: u64,
+    pub faults: Vec<FaultReport>,
+}
+
+impl Report {
+    pub fn new(
+        protocol: &str,
+        seed: u64,
+        outcomes: BTreeMap<u8, Outcome>,
+        elapsed: u64,
+        faults: Vec<Fault>,
+    ) -> Report {
+        let faults = faults
+            .into_iter()
+            .map(|fault| FaultReport {
+                verdict: Verdict::of(&outcomes[&fault.receiver]),
+                fault,
+            })
+            .collect();
+        Report {
+            protocol: protocol.to_string(),
+            seed,
+            outcomes,
+            elapsed,
+            faults,
+        }
+    }
+
+    // Every party finished with a valid result.
+    pub fn is_correct(&self) -> bool {
+        self.outcomes
+            .values()
+            .all(|outcome| *outcome == Outcome::Finished { valid: true })
+    }
+
+    pub fn slipped(&self) -> impl Iterator<Item = &FaultReport> {
+        self.faults
+            .iter()
+            .filter(|fault| fault.verdict == Verdict::Slipped)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Counts {
+    pub injected: usize,
+    pub caught: usize,
+    pub timed_out: usize,
+    pub tolerated: usize,
+    pub slipped: usize,
+}
+
+// The reports of many runs, added up by kind of fault.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Summary {
+    pub runs: usize,
+    pub correct: usize,
+    pub failed: usize,
+    pub stalled: usize,
+    pub faults: BTreeMap<String, Counts>,
+    // The faults that went unnoticed, to replay with their seed.
+    pub slipped: Vec<(u64, Fault)>,
+}
+
+impl Summary {
+    pub fn add(&mut self, report: &Report) {
+        self.runs += 1;
+        let outcomes = report.outcomes.values();
+        if report.is_correct() {
+            self.correct += 1;
+        } else if outcomes
+            .clone()
+            .any(|o| matches!(o, Outcome::Failed { .. }))
+        {
+            self.failed += 1;
+        } else if outcomes.clone().any(|o| *o == Outcome::Stalled) {
+            self.stalled += 1;
+        }
+
+        for fault in &report.faults {
+            let counts = self
+                .faults
+                .entry(fault.fault.kind.name().to_string())
+                .or_default();
+            counts.injected += 1;
+            match fault.verdict {
+                Verdict::Caught => counts.caught += 1,
+                Verdict::TimedOut => counts.timed_out += 1,
+                Verdict::Tolerated => counts.tolerated += 1,
+                Verdict::Slipped => counts.slipped += 1,
+            }
+        }
+        self.slipped.extend(
+            report
+                .slipped()
+                .map(|fault| (report.seed, fault.fault.clone())),
+        );
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "runs: {}, correct: {}, failed: {}, stalled: {}",
+            self.runs, self.correct, self.failed, self.stalled
+        )?;
+        writeln!(
+            f,
+            "{:<10} {:>9} {:>7} {:>9} {:>9} {:>7}",
+            "fault", "injected", "caught", "timed out", "tolerated", "slipped"
+        )?;
+        for (kind, counts) in &self.faults {
+            writeln!(
+                f,
+                "{:<10} {:>9} {:>7} {:>9} {:>9} {:>7}",
+                kind,
+                counts.injected,
+                counts.caught,
+                counts.timed_out,
+                counts.tolerated,
+                counts.slipped
+            )?;
+        }
+        for (seed, fault) in &self.slipped {
+            writeln!(
+                f,
+                "slipped through: seed {}, {:?} from {} to {} in round {:?}",
+                seed, fault.kind, fault.sender, fault.receiver, fault.round
+            )?;
+        }
+        Ok(())
+    }
+}
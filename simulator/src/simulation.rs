@@ -0,0 +1,218 @@
+use crate::network::{Network, NetworkConfig};
+use crate::report::{Outcome, Report};
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::{verify_ecdsa_signature, SignData};
+use dkls23::protocols::{Parameters, Party};
+use ffi_tss::dkls23::session::dkg::DkgPayload;
+use ffi_tss::dkls23::session::sign::{SignPayload, Signature};
+use ffi_tss::dkls23::session::{Message, Payload};
+use ffi_tss::dkls23::transport::{run_dkg, run_sign, RunError};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+// Runs every party of a protocol on one thread, over a virtual network.
+// The parties are the real sessions driven by run_dkg and run_sign, so
+// the simulation checks what the orchestration would do with the same
+// faults.
+
+type PartyRun<T> = Pin<Box<dyn Future<Output = Result<T, RunError>>>>;
+
+pub struct Simulation<T> {
+    pub report: Report,
+    // The outputs of the parties that finished.
+    pub outputs: BTreeMap<u8, T>,
+}
+
+fn round_of<P: Payload + DeserializeOwned>(bytes: &[u8]) -> Option<u8> {
+    serde_json::from_slice::<Message<P>>(bytes)
+        .ok()
+        .map(|message| message.payload.round())
+}
+
+// Polls the parties until each has finished or is waiting for nothing.
+// A party that is done is dropped at once, so that the others see its
+// transport close. None stands for a party that stalled.
+fn execute<T>(
+    network: &Network,
+    mut runs: BTreeMap<u8, PartyRun<T>>,
+) -> BTreeMap<u8, Option<Result<T, RunError>>> {
+    let mut context = Context::from_waker(Waker::noop());
+    let mut results = BTreeMap::new();
+    loop {
+        network.deliver();
+        let done: Vec<u8> = runs
+            .iter_mut()
+            .filter_map(|(&index, run)| match run.as_mut().poll(&mut context) {
+                Poll::Ready(result) => {
+                    results.insert(index, Some(result));
+                    Some(index)
+                }
+                Poll::Pending => None,
+            })
+            .collect();
+        for index in done {
+            runs.remove(&index);
+        }
+        if runs.is_empty() || (!network.deliver() && !network.advance()) {
+            break;
+        }
+    }
+    for index in runs.into_keys() {
+        results.insert(index, None);
+    }
+    results
+}
+
+fn report<T>(
+    protocol: &str,
+    config: &NetworkConfig,
+    network: &Network,
+    results: BTreeMap<u8, Option<Result<T, RunError>>>,
+    valid: impl Fn(&T) -> bool,
+) -> Simulation<T> {
+    let mut outcomes = BTreeMap::new();
+    let mut outputs = BTreeMap::new();
+    for (index, result) in results {
+        let outcome = match result {
+            Some(Ok(output)) => {
+                let outcome = Outcome::Finished {
+                    valid: valid(&output),
+                };
+                outputs.insert(index, output);
+                outcome
+            }
+            Some(Err(error)) => Outcome::Failed { error },
+            None => Outcome::Stalled,
+        };
+        outcomes.insert(index, outcome);
+    }
+    let report = Report::new(
+        protocol,
+        config.seed,
+        outcomes,
+        network.now(),
+        network.faults(),
+    );
+    Simulation { report, outputs }
+}
+
+pub fn simulate_dkg(
+    parameters: &Parameters,
+    config: &NetworkConfig,
+) -> Simulation<Party> {
+    let indices: Vec<u8> = (1..=parameters.share_count).collect();
+    let network =
+        Network::new(config.clone(), &indices, round_of::<DkgPayload>);
+    let session_id = format!("simulation-{}", config.seed).into_bytes();
+
+    let runs = indices
+        .iter()
+        .map(|&index| {
+            let session = SessionData {
+                parameters: parameters.clone(),
+                party_index: index,
+                session_id: session_id.clone(),
+            };
+            let mut transport = network.transport(index);
+            let run: PartyRun<Party> =
+                Box::pin(async move { run_dkg(session, &mut transport).await });
+            (index, run)
+        })
+        .collect();
+    let results = execute(&network, runs);
+
+    // The key is valid when every party that finished got the same key
+    // and chain code, and the shares of those parties sign with it.
+    let finished: Vec<Party> = results
+        .values()
+        .filter_map(|result| match result {
+            Some(Ok(party)) => Some(party.clone()),
+            _ => None,
+        })
+        .collect();
+    let agree = finished.windows(2).all(|pair| {
+        pair[0].pk == pair[1].pk
+            && serde_json::to_value(&pair[0].derivation_data).ok()
+                == serde_json::to_value(&pair[1].derivation_data).ok()
+    });
+    let valid = agree && signs(parameters.threshold, &finished, config.seed);
+    report("dkg", config, &network, results, |_| valid)
+}
+
+// Whether the parties sign over a perfect network, in groups of
+// `threshold` that take in every one of them. Fewer parties than the
+// threshold cannot use the key at all, and neither can a threshold of 0.
+fn signs(threshold: u8, parties: &[Party], seed: u64) -> bool {
+    let threshold = threshold as usize;
+    if threshold == 0 || parties.len() < threshold {
+        return false;
+    }
+    let config = NetworkConfig {
+        seed,
+        ..NetworkConfig::default()
+    };
+    (0..parties.len()).step_by(threshold).all(|first| {
+        // The last group is filled up with the first parties.
+        let signers: Vec<Party> = (first..first + threshold)
+            .map(|i| parties[i % parties.len()].clone())
+            .collect();
+        simulate_sign(&signers, [seed as u8; 32], &config)
+            .report
+            .is_correct()
+    })
+}
+
+// The signers are the given parties, all of one DKG.
+pub fn simulate_sign(
+    parties: &[Party],
+    message_hash: [u8; 32],
+    config: &NetworkConfig,
+) -> Simulation<Signature> {
+    let indices: Vec<u8> =
+        parties.iter().map(|party| party.party_index).collect();
+    let network =
+        Network::new(config.clone(), &indices, round_of::<SignPayload>);
+    let sign_id = format!("simulation-{}", config.seed).into_bytes();
+
+    let runs = parties
+        .iter()
+        .map(|party| {
+            let sign_data = SignData {
+                sign_id: sign_id.clone(),
+                counterparties: indices
+                    .iter()
+                    .copied()
+                    .filter(|&index| index != party.party_index)
+                    .collect(),
+                message_hash,
+            };
+            let party = party.clone();
+            let index = party.party_index;
+            let mut transport = network.transport(index);
+            let run: PartyRun<Signature> = Box::pin(async move {
+                run_sign(party, sign_data, true, &mut transport).await
+            });
+            (index, run)
+        })
+        .collect();
+    let results = execute(&network, runs);
+
+    let pk = parties[0].pk;
+    report(
+        "sign",
+        config,
+        &network,
+        results,
+        |signature: &Signature| {
+            verify_ecdsa_signature(
+                &message_hash,
+                &pk,
+                &signature.x_coord,
+                &signature.signature,
+            )
+        },
+    )
+}
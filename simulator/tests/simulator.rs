@@ -0,0 +1,195 @@
+use dkls23::protocols::{Parameters, Party};
+use ffi_tss::dkls23::transport::{block_on, Transport};
+use libtss_simulator::network::{Fault, FaultKind, Network, NetworkConfig};
+use libtss_simulator::report::{Outcome, Summary, Verdict};
+use libtss_simulator::simulation::{simulate_dkg, simulate_sign};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+const PARAMETERS: Parameters = Parameters {
+    threshold: 2,
+    share_count: 3,
+};
+
+// Sends fifty messages from party 1 to party 2 and gives the faults and
+// what party 2 read, in order.
+fn exchange(config: NetworkConfig) -> (Vec<Fault>, Vec<Value>) {
+    let network = Network::new(config, &[1, 2], |_| None);
+    let mut sender = network.transport(1);
+    let mut receiver = network.transport(2);
+    for n in 0..50 {
+        let message =
+            json!({ "sender": 1, "receiver": 2, "payload": { "n": n } });
+        block_on(sender.send(2, message.to_string().into_bytes())).unwrap();
+    }
+
+    let mut context = Context::from_waker(Waker::noop());
+    let mut received = Vec::new();
+    loop {
+        network.deliver();
+        while let Poll::Ready(Ok((sender, bytes))) =
+            pin!(receiver.receive()).poll(&mut context)
+        {
+            assert_eq!(sender, 1);
+            received.push(serde_json::from_slice(&bytes).unwrap());
+        }
+        if !network.advance() {
+            break;
+        }
+    }
+    (network.faults(), received)
+}
+
+#[test]
+fn test_network_faults() {
+    let config = NetworkConfig {
+        loss: 0.2,
+        duplicate: 0.2,
+        reorder: 0.3,
+        seed: 7,
+        ..NetworkConfig::default()
+    };
+    let (faults, received) = exchange(config.clone());
+    assert_eq!(exchange(config.clone()), (faults.clone(), received.clone()));
+
+    let count = |name: &str| {
+        faults
+            .iter()
+            .filter(|fault| fault.kind.name() == name)
+            .count()
+    };
+    assert!(count("drop") > 0 && count("duplicate") > 0);
+    assert_eq!(received.len(), 50 - count("drop") + count("duplicate"));
+    let order: Vec<_> = received.iter().map(|m| &m["payload"]["n"]).collect();
+    assert!(order
+        .windows(2)
+        .any(|pair| pair[0].as_u64() > pair[1].as_u64()));
+
+    // Without faults, everything arrives in order.
+    let (faults, received) = exchange(NetworkConfig::default());
+    assert!(faults.is_empty());
+    assert!(received
+        .iter()
+        .enumerate()
+        .all(|(n, message)| message["payload"]["n"] == n));
+}
+
+#[test]
+fn test_network_tamper() {
+    let config = NetworkConfig {
+        byzantine: vec![1],
+        tamper: 1.0,
+        ..NetworkConfig::default()
+    };
+    let (faults, received) = exchange(config);
+    assert_eq!(faults.len(), 50);
+    assert!(faults.iter().all(|fault| fault.kind
+        == FaultKind::Tamper {
+            field: String::from("payload.n")
+        }));
+    // Every message still parses, with one value changed.
+    for (n, message) in received.iter().enumerate() {
+        assert_eq!(message["sender"], 1);
+        assert_eq!(message["payload"]["n"], n as u64 ^ 1);
+    }
+}
+
+fn parties() -> Vec<Party> {
+    let dkg = simulate_dkg(&PARAMETERS, &NetworkConfig::default());
+    assert!(dkg.report.is_correct());
+    dkg.outputs.into_values().collect()
+}
+
+#[test]
+fn test_perfect_network() {
+    let dkg = simulate_dkg(&PARAMETERS, &NetworkConfig::default());
+    assert!(dkg.report.is_correct());
+    assert!(dkg.report.faults.is_empty());
+    assert!(dkg.report.elapsed > 0);
+    assert_eq!(dkg.outputs.len(), 3);
+
+    let signers: Vec<Party> = dkg.outputs.into_values().take(2).collect();
+    let sign = simulate_sign(&signers, [1; 32], &NetworkConfig::default());
+    assert!(sign.report.is_correct());
+    assert_eq!(sign.outputs[&1].signature, sign.outputs[&2].signature);
+}
+
+#[test]
+fn test_reorder_is_tolerated() {
+    let signers: Vec<Party> = parties().into_iter().skip(1).collect();
+    for seed in 0..5 {
+        let config = NetworkConfig {
+            reorder: 0.5,
+            seed,
+            ..NetworkConfig::default()
+        };
+        for report in [
+            simulate_dkg(&PARAMETERS, &config).report,
+            simulate_sign(&signers, [2; 32], &config).report,
+        ] {
+            assert!(report.is_correct(), "{:?}", report);
+            assert!(report
+                .faults
+                .iter()
+                .all(|fault| fault.verdict == Verdict::Tolerated));
+        }
+    }
+}
+
+#[test]
+fn test_loss_stalls() {
+    let config = NetworkConfig {
+        loss: 1.0,
+        ..NetworkConfig::default()
+    };
+    let report = simulate_dkg(&PARAMETERS, &config).report;
+    assert!(report
+        .outcomes
+        .values()
+        .all(|outcome| *outcome == Outcome::Stalled));
+    assert!(!report.faults.is_empty());
+    assert!(report
+        .faults
+        .iter()
+        .all(|fault| fault.verdict == Verdict::TimedOut));
+}
+
+#[test]
+fn test_faults_are_reported() {
+    let signers: Vec<Party> = parties().into_iter().take(2).collect();
+    let mut summary = Summary::default();
+    for seed in 0..10 {
+        let config = NetworkConfig {
+            duplicate: 0.1,
+            corrupt: 0.1,
+            byzantine: vec![2],
+            tamper: 0.3,
+            seed,
+            ..NetworkConfig::default()
+        };
+        let dkg = simulate_dkg(&PARAMETERS, &config).report;
+        let sign = simulate_sign(&signers, [3; 32], &config).report;
+        for report in [dkg, sign] {
+            // A run is only correct when every party got a valid result.
+            if report.faults.iter().any(|f| f.verdict == Verdict::Caught) {
+                assert!(!report.is_correct());
+            }
+            summary.add(&report);
+        }
+    }
+    assert_eq!(summary.runs, 20);
+    let counts = &summary.faults["tamper"];
+    assert_eq!(
+        counts.injected,
+        counts.caught + counts.timed_out + counts.tolerated + counts.slipped
+    );
+    let slipped = summary
+        .slipped
+        .iter()
+        .filter(|(_, fault)| fault.kind.name() == "tamper")
+        .count();
+    assert_eq!(counts.slipped, slipped);
+    assert!(summary.to_string().contains("tamper"));
+}
@@ -1,2 +1,4 @@
 pub mod protocols;
+pub mod session;
+pub mod transport;
 pub mod utilities;
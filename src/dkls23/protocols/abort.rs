@@ -0,0 +1,67 @@
+use crate::dkls23::session::abort::{signing_key, verifying_key, AbortMessage};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Abort messages for callers that run the phases themselves. The session
+// id is the one of the DKG, or the sign id when signing.
+#[derive(Deserialize, Serialize)]
+pub struct AbortIn {
+    pub session_id: Vec<u8>,
+    pub sender: u8,
+    pub phase: u8,
+    pub reason: String,
+    pub key: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VerifyAbortIn {
+    pub abort: AbortMessage,
+    pub public_key: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VerifyAbortOut {
+    pub valid: bool,
+}
+
+impl CJson for AbortIn {}
+impl CJson for AbortMessage {}
+impl CJson for VerifyAbortIn {}
+impl CJson for VerifyAbortOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_abort_message(
+    abort_json_in: *const c_char,
+) -> *const c_char {
+    let abort_in: AbortIn = AbortIn::from_json(abort_json_in);
+    let key = match signing_key(&abort_in.key) {
+        Ok(key) => key,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+
+    AbortMessage::new(
+        &key,
+        &abort_in.session_id,
+        abort_in.sender,
+        abort_in.phase,
+        &abort_in.reason,
+    )
+    .to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_verify_abort(
+    verify_json_in: *const c_char,
+) -> *const c_char {
+    let verify_in: VerifyAbortIn = VerifyAbortIn::from_json(verify_json_in);
+    let key = match verifying_key(&verify_in.public_key) {
+        Ok(key) => key,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+
+    VerifyAbortOut {
+        valid: verify_in.abort.verify(&key),
+    }
+    .to_json()
+}
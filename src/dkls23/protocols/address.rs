@@ -0,0 +1,75 @@
+use crate::dkls23::utilities::address::Addresses;
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use dkls23::protocols::derivation::DerivData;
+use dkls23::protocols::Party;
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Address structs
+#[derive(Deserialize, Serialize)]
+pub struct AddressesIn {
+    pub data: DerivData,
+    #[serde(default)]
+    pub cosmos_prefix: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AddressesOut {
+    pub addresses: Addresses,
+}
+
+// Address structs for parties
+#[derive(Deserialize, Serialize)]
+pub struct PartyAddressesIn {
+    pub party: Party,
+    #[serde(default)]
+    pub cosmos_prefix: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartyAddressesOut {
+    pub addresses: Addresses,
+}
+
+impl CJson for AddressesIn {}
+impl CJson for AddressesOut {}
+impl CJson for PartyAddressesIn {}
+impl CJson for PartyAddressesOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_addresses(
+    addresses_json_in: *const c_char,
+) -> *const c_char {
+    let addresses_in: AddressesIn = AddressesIn::from_json(addresses_json_in);
+    match Addresses::new(
+        &addresses_in.data.pk,
+        addresses_in.cosmos_prefix.as_deref(),
+    ) {
+        Err(error) => ErrorOut {
+            error: DerivationError::from(error),
+        }
+        .to_json(),
+
+        Ok(addresses) => AddressesOut { addresses }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_party_addresses(
+    addresses_json_in: *const c_char,
+) -> *const c_char {
+    let addresses_in: PartyAddressesIn =
+        PartyAddressesIn::from_json(addresses_json_in);
+    match Addresses::new(
+        &addresses_in.party.pk,
+        addresses_in.cosmos_prefix.as_deref(),
+    ) {
+        Err(error) => ErrorOut {
+            error: DerivationError::from(error),
+        }
+        .to_json(),
+
+        Ok(addresses) => PartyAddressesOut { addresses }.to_json(),
+    }
+}
@@ -0,0 +1,177 @@
+use crate::dkls23::protocols::public_derivation::PublicKeySource;
+use crate::dkls23::utilities::address::AddressKind;
+use crate::dkls23::utilities::bip32::{
+    ExtendedPublicKey, MAX_CHILD_NUMBER, MAX_DEPTH,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::os::raw::c_char;
+
+// Upper bound on the number of keys derived in a single call.
+pub const MAX_BATCH_SIZE: u32 = 10_000;
+
+// Which children of the base key we derive. A range derives exactly
+// count children. A gap limit scan (as in BIP-44) stops after gap_limit
+// consecutive children whose address is not among the used ones.
+#[derive(Deserialize, Serialize)]
+pub enum BatchScan {
+    Range {
+        start: u32,
+        count: u32,
+    },
+    GapLimit {
+        start: u32,
+        gap_limit: u32,
+        used: Vec<String>,
+    },
+}
+
+// Batch structs
+#[derive(Deserialize, Serialize)]
+pub struct DeriveBatchIn {
+    #[serde(flatten)]
+    pub source: PublicKeySource,
+    pub base_path: String,
+    pub scan: BatchScan,
+    pub address_kind: AddressKind,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct BatchEntry {
+    pub index: u32,
+    pub pubkey: String,
+    pub address: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeriveBatchOut {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl CJson for DeriveBatchIn {}
+impl CJson for DeriveBatchOut {}
+
+fn too_many_keys() -> DerivationError {
+    DerivationError::Other {
+        description: format!("At most {} keys per batch!", MAX_BATCH_SIZE),
+    }
+}
+
+fn batch_entry(
+    base: &ExtendedPublicKey,
+    index: u32,
+    address_kind: &AddressKind,
+) -> Result<Option<BatchEntry>, DerivationError> {
+    // The depth and the index were checked by the caller, so the only
+    // possible failure is an invalid child, which BIP-32 says to skip.
+    let child = match base.derive_child(index) {
+        Ok(child) => child,
+        Err(_) => return Ok(None),
+    };
+
+    Ok(Some(BatchEntry {
+        index,
+        pubkey: hex::encode(child.pk.to_encoded_point(true).as_bytes()),
+        address: address_kind.address(&child.pk)?,
+    }))
+}
+
+// The base key is derived once and every child is derived from it,
+// so a scan costs a single public derivation per address.
+pub fn derive_batch(
+    base: &ExtendedPublicKey,
+    scan: &BatchScan,
+    address_kind: &AddressKind,
+) -> Result<Vec<BatchEntry>, DerivationError> {
+    let start = match scan {
+        BatchScan::Range { start, .. } => *start,
+        BatchScan::GapLimit { start, .. } => *start,
+    };
+    if start > MAX_CHILD_NUMBER {
+        return Err(DerivationError::HardenedNotSupported { index: start });
+    }
+    if base.depth == MAX_DEPTH {
+        return Err(DerivationError::DepthOverflow {
+            depth: base.depth,
+            length: 1,
+        });
+    }
+
+    let mut entries = Vec::new();
+    match scan {
+        BatchScan::Range { start, count } => {
+            if *count > MAX_BATCH_SIZE {
+                return Err(too_many_keys());
+            }
+            if *count > MAX_CHILD_NUMBER - start + 1 {
+                return Err(DerivationError::HardenedNotSupported {
+                    index: MAX_CHILD_NUMBER + 1,
+                });
+            }
+            for index in *start..(start + count) {
+                if let Some(entry) = batch_entry(base, index, address_kind)? {
+                    entries.push(entry);
+                }
+            }
+        }
+        BatchScan::GapLimit {
+            start,
+            gap_limit,
+            used,
+        } => {
+            if *gap_limit == 0 {
+                return Err(DerivationError::Other {
+                    description: String::from(
+                        "The gap limit must be positive!",
+                    ),
+                });
+            }
+            let used: BTreeSet<&str> =
+                used.iter().map(|a| a.as_str()).collect();
+
+            let mut gap = 0;
+            let mut index = *start;
+            while gap < *gap_limit {
+                if entries.len() as u32 == MAX_BATCH_SIZE {
+                    return Err(too_many_keys());
+                }
+                if index > MAX_CHILD_NUMBER {
+                    break;
+                }
+                if let Some(entry) = batch_entry(base, index, address_kind)? {
+                    if used.contains(entry.address.as_str()) {
+                        gap = 0;
+                    } else {
+                        gap += 1;
+                    }
+                    entries.push(entry);
+                }
+                index += 1;
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_derive_batch(
+    batch_json_in: *const c_char,
+) -> *const c_char {
+    let batch_in: DeriveBatchIn = DeriveBatchIn::from_json(batch_json_in);
+    let result = batch_in
+        .source
+        .to_extended_key()
+        .and_then(|key| key.derive_from_path(&batch_in.base_path))
+        .and_then(|base| {
+            derive_batch(&base, &batch_in.scan, &batch_in.address_kind)
+        });
+    match result {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(entries) => DeriveBatchOut { entries }.to_json(),
+    }
+}
@@ -1,9 +1,27 @@
-use crate::dkls23::utilities::cjson::CJson;
+use crate::dkls23::utilities::bip32::{MAX_CHILD_NUMBER, MAX_DEPTH};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::{validate_path, DerivationError};
 use dkls23::protocols::derivation::DerivData;
 use dkls23::protocols::Party;
 use serde::{Deserialize, Serialize};
 use std::os::raw::c_char;
 
+// Path structs
+#[derive(Deserialize, Serialize)]
+pub struct ParsePathIn {
+    pub path: String,
+    #[serde(default)]
+    pub depth: u8,
+    #[serde(default)]
+    pub allow_hardened: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ParsePathOut {
+    pub indices: Vec<u32>,
+    pub depth: u8,
+}
+
 // Derivation structs
 #[derive(Deserialize, Serialize)]
 pub struct DeriveFromPathIn {
@@ -51,6 +69,8 @@ pub struct PartyDeriveChildOut {
     pub party: Party,
 }
 
+impl CJson for ParsePathIn {}
+impl CJson for ParsePathOut {}
 impl CJson for DeriveFromPathIn {}
 impl CJson for DeriveFromPathOut {}
 impl CJson for DeriveChildIn {}
@@ -60,19 +80,65 @@ impl CJson for PartyDeriveFromPathOut {}
 impl CJson for PartyDeriveChildIn {}
 impl CJson for PartyDeriveChildOut {}
 
+// We check the path ourselves before handing it to DKLs23, so that
+// the caller gets a typed error for every invalid path.
+fn check_derivation(
+    data: &DerivData,
+    path: &str,
+) -> Result<(), DerivationError> {
+    validate_path(path, data.depth, false)?;
+    Ok(())
+}
+
+fn check_child(
+    data: &DerivData,
+    child_number: u32,
+) -> Result<(), DerivationError> {
+    if data.depth == MAX_DEPTH {
+        return Err(DerivationError::DepthOverflow {
+            depth: data.depth,
+            length: 1,
+        });
+    }
+    if child_number > MAX_CHILD_NUMBER {
+        return Err(DerivationError::HardenedNotSupported {
+            index: child_number,
+        });
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_parse_path(
+    parse_path_json_in: *const c_char,
+) -> *const c_char {
+    let parse_path_in: ParsePathIn = ParsePathIn::from_json(parse_path_json_in);
+    match validate_path(
+        &parse_path_in.path,
+        parse_path_in.depth,
+        parse_path_in.allow_hardened,
+    ) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(indices) => ParsePathOut {
+            depth: parse_path_in.depth + indices.len() as u8,
+            indices,
+        }
+        .to_json(),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn dkls_derive_from_path(
     derive_json_in: *const c_char,
 ) -> *const c_char {
     let derive_from_path_in: DeriveFromPathIn =
         DeriveFromPathIn::from_json(derive_json_in);
-    match derive_from_path_in
-        .data
-        .derive_from_path(derive_from_path_in.path.as_str())
+    let path = derive_from_path_in.path.as_str();
+    match check_derivation(&derive_from_path_in.data, path)
+        .and_then(|_| Ok(derive_from_path_in.data.derive_from_path(path)?))
     {
-        Err(error) => {
-            panic!("Derivation error: {:?}", error);
-        }
+        Err(error) => ErrorOut { error }.to_json(),
 
         Ok(data) => DeriveFromPathOut { data }.to_json(),
     }
@@ -84,13 +150,12 @@ pub extern "C" fn dkls_party_derive_from_path(
 ) -> *const c_char {
     let derive_from_path_in: PartyDeriveFromPathIn =
         PartyDeriveFromPathIn::from_json(derive_json_in);
-    match derive_from_path_in
-        .party
-        .derive_from_path(derive_from_path_in.path.as_str())
+    let path = derive_from_path_in.path.as_str();
+    let data = &derive_from_path_in.party.derivation_data;
+    match check_derivation(data, path)
+        .and_then(|_| Ok(derive_from_path_in.party.derive_from_path(path)?))
     {
-        Err(error) => {
-            panic!("Derivation error: {:?}", error);
-        }
+        Err(error) => ErrorOut { error }.to_json(),
 
         Ok(party) => PartyDeriveFromPathOut { party }.to_json(),
     }
@@ -102,13 +167,11 @@ pub extern "C" fn dkls_derive_child(
 ) -> *const c_char {
     let derive_child_in: DeriveChildIn =
         DeriveChildIn::from_json(derive_json_in);
-    match derive_child_in
-        .data
-        .derive_child(derive_child_in.child_number)
+    let child_number = derive_child_in.child_number;
+    match check_child(&derive_child_in.data, child_number)
+        .and_then(|_| Ok(derive_child_in.data.derive_child(child_number)?))
     {
-        Err(error) => {
-            panic!("Derivation error: {:?}", error);
-        }
+        Err(error) => ErrorOut { error }.to_json(),
 
         Ok(data) => DeriveChildOut { data }.to_json(),
     }
@@ -120,13 +183,12 @@ pub extern "C" fn dkls_party_derive_child(
 ) -> *const c_char {
     let derive_child_in: PartyDeriveChildIn =
         PartyDeriveChildIn::from_json(derive_json_in);
-    match derive_child_in
-        .party
-        .derive_child(derive_child_in.child_number)
+    let child_number = derive_child_in.child_number;
+    let data = &derive_child_in.party.derivation_data;
+    match check_child(data, child_number)
+        .and_then(|_| Ok(derive_child_in.party.derive_child(child_number)?))
     {
-        Err(error) => {
-            panic!("Derivation error: {:?}", error);
-        }
+        Err(error) => ErrorOut { error }.to_json(),
 
         Ok(party) => PartyDeriveChildOut { party }.to_json(),
     }
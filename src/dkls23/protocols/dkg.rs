@@ -1,9 +1,20 @@
-use crate::dkls23::utilities::cjson::CJson;
+use crate::dkls23::protocols::echo::check_dkg;
+use crate::dkls23::session::blame::disclosed;
+use crate::dkls23::session::dkg::{DkgSession, DkgState};
+use crate::dkls23::session::echo::Echo;
+use crate::dkls23::session::id::{
+    require, SessionIdError, SessionIdTranscript,
+};
+use crate::dkls23::session::seal::{
+    require_identity, Identity, IdentityKeys, SealError, SealedMessage,
+};
+use crate::dkls23::session::{error_to_json, SessionError};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut, SealedOut};
 use dkls23::protocols::dkg::{
-    phase1, phase2, phase3, phase4, BroadcastDerivationPhase2to4,
-    BroadcastDerivationPhase3to4, KeepInitMulPhase3to4,
-    KeepInitZeroSharePhase2to3, KeepInitZeroSharePhase3to4, ProofCommitment,
-    SessionData, TransmitInitMulPhase3to4, TransmitInitZeroSharePhase2to4,
+    BroadcastDerivationPhase2to4, BroadcastDerivationPhase3to4,
+    KeepInitMulPhase3to4, KeepInitZeroSharePhase2to3,
+    KeepInitZeroSharePhase3to4, ProofCommitment, SessionData,
+    TransmitInitMulPhase3to4, TransmitInitZeroSharePhase2to4,
     TransmitInitZeroSharePhase3to4, UniqueKeepDerivationPhase2to3,
 };
 use dkls23::protocols::Party;
@@ -16,6 +27,18 @@ use std::os::raw::c_char;
 #[derive(Deserialize, Serialize)]
 pub struct Phase1In {
     pub session: SessionData,
+    // Proof that the id was agreed on with dkls_session_id_*. Required
+    // with the strict-session-id feature.
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    // Our nonce from dkls_session_id_commit, which must be our reveal
+    // in the transcript.
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    // Our identity keys. With them, the point-to-point messages come
+    // out sealed and are only accepted sealed.
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -26,7 +49,19 @@ pub struct Phase1Out {
 #[derive(Deserialize, Serialize)]
 pub struct Phase2In {
     pub session: SessionData,
+    // With an identity, our own phase 1 output. The fragments of the
+    // others then come sealed.
     pub poly_fragments: Vec<Scalar>,
+    // The transcript of phase 1. Every phase checks it, since each can be
+    // called on its own.
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+    #[serde(default)]
+    pub sealed: Vec<SealedMessage>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -44,6 +79,12 @@ pub struct Phase3In {
     pub session: SessionData,
     pub zero_kept: BTreeMap<u8, KeepInitZeroSharePhase2to3>,
     pub bip_kept: UniqueKeepDerivationPhase2to3,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -61,12 +102,29 @@ pub struct Phase4In {
     pub poly_point: Scalar,
     pub proofs_commitments: Vec<ProofCommitment>,
     pub zero_kept: BTreeMap<u8, KeepInitZeroSharePhase3to4>,
+    #[serde(default)]
     pub zero_received_phase2: Vec<TransmitInitZeroSharePhase2to4>,
+    #[serde(default)]
     pub zero_received_phase3: Vec<TransmitInitZeroSharePhase3to4>,
     pub mul_kept: BTreeMap<u8, KeepInitMulPhase3to4>,
+    #[serde(default)]
     pub mul_received: Vec<TransmitInitMulPhase3to4>,
     pub bip_broadcast_2to4: BTreeMap<u8, BroadcastDerivationPhase2to4>,
     pub bip_broadcast_3to4: BTreeMap<u8, BroadcastDerivationPhase3to4>,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+    #[serde(default)]
+    pub sealed: Vec<SealedMessage>,
+    // Whether phase 4 first checks, with the echoes of every other party
+    // from dkls_dkg_echo, that everyone got the same broadcasts.
+    #[serde(default)]
+    pub echo: bool,
+    #[serde(default)]
+    pub echoes: Vec<Echo>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -74,38 +132,214 @@ pub struct Phase4Out {
     pub party: Party,
 }
 
+// Every party but us takes part in the DKG.
+fn others(session: &SessionData) -> Vec<u8> {
+    (1..=session.parameters.share_count)
+        .filter(|&index| index != session.party_index)
+        .collect()
+}
+
+fn identity(
+    session: &SessionData,
+    keys: Option<&IdentityKeys>,
+    sealed: &[SealedMessage],
+) -> Result<Option<Identity>, SealError> {
+    let identity = require_identity(session.party_index, keys)?;
+    if identity.is_none() && !sealed.is_empty() {
+        return Err(SealError::Required);
+    }
+    Ok(identity)
+}
+
+fn check_id(
+    session: &SessionData,
+    transcript: Option<&SessionIdTranscript>,
+    id_nonce: Option<&str>,
+) -> Result<(), SessionIdError> {
+    require(transcript, id_nonce, |transcript, nonce| {
+        transcript.check_dkg(session, nonce)
+    })
+}
+
+fn seal_error(error: SealError) -> *const c_char {
+    ErrorOut { error }.to_json()
+}
+
+// Fills the received messages of phase 4 from the sealed ones. Plain
+// messages are refused once there is an identity.
+fn open_phase4(phase4_in: &mut Phase4In) -> Result<(), SealError> {
+    let session = &phase4_in.session;
+    let identity = match identity(
+        session,
+        phase4_in.identity.as_ref(),
+        &phase4_in.sealed,
+    )? {
+        Some(identity) => identity,
+        None => return Ok(()),
+    };
+    if !phase4_in.zero_received_phase2.is_empty()
+        || !phase4_in.zero_received_phase3.is_empty()
+        || !phase4_in.mul_received.is_empty()
+    {
+        return Err(SealError::Required);
+    }
+
+    let others = others(session);
+    let session_id = &session.session_id;
+    let sealed = &phase4_in.sealed;
+    phase4_in.zero_received_phase2 = identity.open_transmits(
+        session_id,
+        2,
+        "zero",
+        &others,
+        sealed,
+        |transmit: &TransmitInitZeroSharePhase2to4| &transmit.parties,
+    )?;
+    phase4_in.zero_received_phase3 = identity.open_transmits(
+        session_id,
+        3,
+        "zero",
+        &others,
+        sealed,
+        |transmit: &TransmitInitZeroSharePhase3to4| &transmit.parties,
+    )?;
+    phase4_in.mul_received = identity.open_transmits(
+        session_id,
+        3,
+        "mul",
+        &others,
+        sealed,
+        |transmit: &TransmitInitMulPhase3to4| &transmit.parties,
+    )?;
+    Ok(())
+}
+
 // DKG Phases
 #[no_mangle]
 pub extern "C" fn dkls_dkg_phase1(data: *const c_char) -> *const c_char {
     let phase1_in: Phase1In = Phase1In::from_json(data);
-    let fragments = phase1(&phase1_in.session);
+    let transcript = phase1_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase1_in.session,
+        transcript,
+        phase1_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let identity =
+        match identity(&phase1_in.session, phase1_in.identity.as_ref(), &[]) {
+            Ok(identity) => identity,
+            Err(error) => return seal_error(error),
+        };
+    let others = others(&phase1_in.session);
+    let session_id = phase1_in.session.session_id.clone();
+    let mut session = DkgSession::new(phase1_in.session);
+
+    let mut phase1_out = match session.phase1() {
+        Ok(phase1_out) => phase1_out,
+        Err(error) => return error_to_json(error),
+    };
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return phase1_out.to_json(),
+    };
 
-    Phase1Out { fragments }.to_json()
+    // Only our own fragment is left in the clear.
+    let mut sealed = Vec::new();
+    for receiver in others {
+        let fragment = &mut phase1_out.fragments[(receiver - 1) as usize];
+        match identity.seal(&session_id, receiver, 1, "fragment", fragment) {
+            Ok(message) => sealed.push(message),
+            Err(error) => return seal_error(error),
+        }
+        *fragment = Scalar::ZERO;
+    }
+    SealedOut {
+        out: phase1_out,
+        sealed,
+    }
+    .to_json()
 }
 
 #[no_mangle]
 pub extern "C" fn dkls_dkg_phase2(
     phase2_json_in: *const c_char,
 ) -> *const c_char {
-    let phase2_in: Phase2In = Phase2In::from_json(phase2_json_in);
-    let (
-        poly_point,
-        proof_commitment,
-        zero_keep,
-        zero_transmit,
-        bip_keep,
-        bip_broadcast,
-    ) = phase2(&phase2_in.session, &phase2_in.poly_fragments);
-
-    Phase2Out {
-        poly_point,
-        proof_commitment,
-        zero_keep,
-        zero_transmit,
-        bip_keep,
-        bip_broadcast,
+    let mut phase2_in: Phase2In = Phase2In::from_json(phase2_json_in);
+    let transcript = phase2_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase2_in.session,
+        transcript,
+        phase2_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let identity = match identity(
+        &phase2_in.session,
+        phase2_in.identity.as_ref(),
+        &phase2_in.sealed,
+    ) {
+        Ok(identity) => identity,
+        Err(error) => return seal_error(error),
+    };
+    let others = others(&phase2_in.session);
+    let session_id = phase2_in.session.session_id.clone();
+    if let Some(identity) = &identity {
+        let fragments: BTreeMap<u8, Scalar> = match identity.open_all(
+            &session_id,
+            1,
+            "fragment",
+            &others,
+            &phase2_in.sealed,
+        ) {
+            Ok(fragments) => fragments,
+            Err(error) => return seal_error(error),
+        };
+        for (sender, fragment) in fragments {
+            if let Some(slot) =
+                phase2_in.poly_fragments.get_mut((sender - 1) as usize)
+            {
+                *slot = fragment;
+            }
+        }
+    }
+    let index = phase2_in.session.party_index as usize;
+    let fragment = match phase2_in.poly_fragments.get(index.wrapping_sub(1)) {
+        Some(fragment) => *fragment,
+        None => {
+            return error_to_json(SessionError::Inconsistent {
+                description: String::from("Invalid polynomial fragments!"),
+            })
+        }
+    };
+    let mut session = DkgSession::with_state(
+        phase2_in.session,
+        DkgState::Phase1Done { fragment },
+    );
+
+    let mut phase2_out = match session.phase2(&phase2_in.poly_fragments) {
+        Ok(phase2_out) => phase2_out,
+        Err(error) => return error_to_json(error),
+    };
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return phase2_out.to_json(),
+    };
+
+    match identity.seal_transmits(
+        &session_id,
+        2,
+        "zero",
+        &std::mem::take(&mut phase2_out.zero_transmit),
+        |transmit| &transmit.parties,
+    ) {
+        Ok(sealed) => SealedOut {
+            out: phase2_out,
+            sealed,
+        }
+        .to_json(),
+        Err(error) => seal_error(error),
     }
-    .to_json()
 }
 
 #[no_mangle]
@@ -113,44 +347,109 @@ pub extern "C" fn dkls_dkg_phase3(
     phase3_json_in: *const c_char,
 ) -> *const c_char {
     let phase3_in: Phase3In = Phase3In::from_json(phase3_json_in);
-    let (zero_keep, zero_transmit, mul_keep, mul_transmit, bip_broadcast) =
-        phase3(
-            &phase3_in.session,
-            &phase3_in.zero_kept,
-            &phase3_in.bip_kept,
-        );
-
-    Phase3Out {
-        zero_keep,
-        zero_transmit,
-        mul_keep,
-        mul_transmit,
-        bip_broadcast,
+    let transcript = phase3_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase3_in.session,
+        transcript,
+        phase3_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let identity =
+        match identity(&phase3_in.session, phase3_in.identity.as_ref(), &[]) {
+            Ok(identity) => identity,
+            Err(error) => return seal_error(error),
+        };
+    let session_id = phase3_in.session.session_id.clone();
+    let mut session = DkgSession::with_state(
+        phase3_in.session,
+        DkgState::Phase2Done {
+            zero_kept: phase3_in.zero_kept,
+            bip_kept: phase3_in.bip_kept,
+        },
+    );
+
+    let mut phase3_out = match session.phase3() {
+        Ok(phase3_out) => phase3_out,
+        Err(error) => return error_to_json(error),
+    };
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return phase3_out.to_json(),
+    };
+
+    let zero = identity.seal_transmits(
+        &session_id,
+        3,
+        "zero",
+        &std::mem::take(&mut phase3_out.zero_transmit),
+        |transmit| &transmit.parties,
+    );
+    let mul = identity.seal_transmits(
+        &session_id,
+        3,
+        "mul",
+        &std::mem::take(&mut phase3_out.mul_transmit),
+        |transmit| &transmit.parties,
+    );
+    match zero.and_then(|zero| Ok([zero, mul?].concat())) {
+        Ok(sealed) => SealedOut {
+            out: phase3_out,
+            sealed,
+        }
+        .to_json(),
+        Err(error) => seal_error(error),
     }
-    .to_json()
 }
 
 #[no_mangle]
 pub extern "C" fn dkls_dkg_phase4(
     phase4_json_in: *const c_char,
 ) -> *const c_char {
-    let phase4_in: Phase4In = Phase4In::from_json(phase4_json_in);
-
-    match phase4(
+    let mut phase4_in: Phase4In = Phase4In::from_json(phase4_json_in);
+    let transcript = phase4_in.transcript.as_ref();
+    if let Err(error) = check_id(
         &phase4_in.session,
+        transcript,
+        phase4_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    if let Err(error) = open_phase4(&mut phase4_in) {
+        return seal_error(error);
+    }
+    if let Err(error) = check_dkg(&phase4_in) {
+        return ErrorOut { error }.to_json();
+    }
+    // Opening the sealed messages above already checked the identity.
+    let identity = identity(
+        &phase4_in.session,
+        phase4_in.identity.as_ref(),
+        &phase4_in.sealed,
+    )
+    .ok()
+    .flatten();
+    let mut session = DkgSession::with_state(
+        phase4_in.session,
+        DkgState::Phase3Done {
+            zero_kept: phase4_in.zero_kept,
+            mul_kept: phase4_in.mul_kept,
+        },
+    );
+
+    match session.phase4(
         &phase4_in.poly_point,
         &phase4_in.proofs_commitments,
-        &phase4_in.zero_kept,
         &phase4_in.zero_received_phase2,
         &phase4_in.zero_received_phase3,
-        &phase4_in.mul_kept,
         &phase4_in.mul_received,
         &phase4_in.bip_broadcast_2to4,
         &phase4_in.bip_broadcast_3to4,
     ) {
         Ok(party) => Phase4Out { party }.to_json(),
-        Err(abort) => {
-            panic!("Party {} aborted: {:?}", abort.index, abort.description);
+        Err(error) => {
+            let sealed = &phase4_in.sealed;
+            error_to_json(disclosed(error, identity.as_ref(), sealed))
         }
     }
 }
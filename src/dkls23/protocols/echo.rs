@@ -0,0 +1,139 @@
+use crate::dkls23::protocols::signing::at_path;
+use crate::dkls23::protocols::{dkg, signing};
+use crate::dkls23::session::echo::{
+    check, dkg_broadcasts, echo, sign_broadcasts, Echo, EchoError,
+};
+use crate::dkls23::session::seal::{Identity, IdentityKeys};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use dkls23::protocols::signing::{Broadcast3to4, SignData};
+use std::collections::BTreeMap;
+use std::os::raw::c_char;
+
+// The echoes are exchanged between phase 3 and phase 4, and name the
+// phase that they guard.
+const ECHO_PHASE: u8 = 4;
+
+impl CJson for Echo {}
+
+fn identity(
+    index: u8,
+    keys: Option<&IdentityKeys>,
+) -> Result<Option<Identity>, EchoError> {
+    Ok(keys.map(|keys| Identity::new(index, keys)).transpose()?)
+}
+
+pub(crate) fn dkg_echo(phase4_in: &dkg::Phase4In) -> Result<Echo, EchoError> {
+    let session = &phase4_in.session;
+    let identity = identity(session.party_index, phase4_in.identity.as_ref())?;
+    let broadcasts = dkg_broadcasts(
+        session.parameters.share_count,
+        &phase4_in.proofs_commitments,
+        &phase4_in.bip_broadcast_2to4,
+        &phase4_in.bip_broadcast_3to4,
+    )?;
+    Ok(echo(
+        &session.session_id,
+        session.party_index,
+        ECHO_PHASE,
+        &broadcasts,
+        identity.as_ref(),
+    ))
+}
+
+// Echoes are checked when phase 4 asks for them or is given any. Then
+// every other party must have sent one. Otherwise phase 4 runs as before.
+pub(crate) fn check_dkg(phase4_in: &dkg::Phase4In) -> Result<(), EchoError> {
+    if !phase4_in.echo && phase4_in.echoes.is_empty() {
+        return Ok(());
+    }
+    let session = &phase4_in.session;
+    let identity = identity(session.party_index, phase4_in.identity.as_ref())?;
+    let others: Vec<u8> = (1..=session.parameters.share_count)
+        .filter(|&index| index != session.party_index)
+        .collect();
+    check(
+        &dkg_echo(phase4_in)?,
+        &others,
+        &phase4_in.echoes,
+        identity.as_ref(),
+    )
+}
+
+// The sign data is the one after the derivation path is bound to it.
+pub(crate) fn sign_echo(
+    index: u8,
+    sign_data: &SignData,
+    broadcasts: &BTreeMap<u8, Broadcast3to4>,
+    keys: Option<&IdentityKeys>,
+) -> Result<Echo, EchoError> {
+    let identity = identity(index, keys)?;
+    let mut signers = sign_data.counterparties.clone();
+    signers.push(index);
+    let broadcasts = sign_broadcasts(&signers, broadcasts)?;
+    Ok(echo(
+        &sign_data.sign_id,
+        index,
+        ECHO_PHASE,
+        &broadcasts,
+        identity.as_ref(),
+    ))
+}
+
+pub(crate) fn check_sign(
+    index: u8,
+    sign_data: &SignData,
+    broadcasts: &BTreeMap<u8, Broadcast3to4>,
+    keys: Option<&IdentityKeys>,
+    echo: bool,
+    echoes: &[Echo],
+) -> Result<(), EchoError> {
+    if !echo && echoes.is_empty() {
+        return Ok(());
+    }
+    let identity = identity(index, keys)?;
+    check(
+        &sign_echo(index, sign_data, broadcasts, keys)?,
+        &sign_data.counterparties,
+        echoes,
+        identity.as_ref(),
+    )
+}
+
+// Both take the input of phase 4, and give the echo to send to every
+// other party before calling phase 4 with the echoes of the others.
+#[no_mangle]
+pub extern "C" fn dkls_dkg_echo(
+    phase4_json_in: *const c_char,
+) -> *const c_char {
+    let phase4_in: dkg::Phase4In = dkg::Phase4In::from_json(phase4_json_in);
+    match dkg_echo(&phase4_in) {
+        Ok(echo) => echo.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_sign_echo(
+    phase4_json_in: *const c_char,
+) -> *const c_char {
+    let phase4_in: signing::Phase4In =
+        signing::Phase4In::from_json(phase4_json_in);
+    let index = phase4_in.party.party_index;
+    let (_, sign_data) = match at_path(
+        phase4_in.party,
+        phase4_in.sign_data,
+        phase4_in.derivation_path.as_deref(),
+    ) {
+        Ok(result) => result,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    match sign_echo(
+        index,
+        &sign_data,
+        &phase4_in.broadcasts,
+        phase4_in.identity.as_ref(),
+    ) {
+        Ok(echo) => echo.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
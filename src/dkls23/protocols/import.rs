@@ -0,0 +1,109 @@
+use crate::dkls23::utilities::bip32::{
+    ExtendedPrivateKey, TPRV_VERSION, TPUB_VERSION, XPRV_VERSION, XPUB_VERSION,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use bip39::Mnemonic;
+use dkls23::protocols::derivation::ErrorDeriv;
+use dkls23::protocols::re_key::re_key;
+use dkls23::protocols::{Parameters, Party};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Import structs
+#[derive(Deserialize, Serialize)]
+pub enum ImportSource {
+    Mnemonic {
+        phrase: String,
+        #[serde(default)]
+        passphrase: String,
+    },
+    Xprv {
+        xprv: String,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ImportIn {
+    pub parameters: Parameters,
+    pub session_id: Vec<u8>,
+    pub source: ImportSource,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ImportOut {
+    pub parties: Vec<Party>,
+    pub xpub: String,
+}
+
+impl CJson for ImportIn {}
+impl CJson for ImportOut {}
+
+// Splits an existing BIP-32 wallet into threshold shares. The parties keep
+// the depth, child number and parent fingerprint of the imported key, so
+// the returned xpub is the same one the original wallet exports.
+pub fn import_wallet(
+    parameters: &Parameters,
+    session_id: &[u8],
+    source: &ImportSource,
+) -> Result<(Vec<Party>, String), ErrorDeriv> {
+    let (xprv, version) = match source {
+        ImportSource::Mnemonic { phrase, passphrase } => {
+            let mnemonic = Mnemonic::parse(phrase.as_str())
+                .map_err(|_| ErrorDeriv::new("Invalid mnemonic!"))?;
+            let seed = mnemonic.to_seed(passphrase.as_str());
+            (ExtendedPrivateKey::from_seed(&seed)?, XPUB_VERSION)
+        }
+        ImportSource::Xprv { xprv } => {
+            let (xprv, version) = ExtendedPrivateKey::decode(xprv)?;
+            let version = match version {
+                XPRV_VERSION => XPUB_VERSION,
+                TPRV_VERSION => TPUB_VERSION,
+                _ => {
+                    return Err(ErrorDeriv::new(
+                        "Unsupported extended key version!",
+                    ))
+                }
+            };
+            (xprv, version)
+        }
+    };
+
+    let mut parties = re_key(
+        parameters,
+        session_id,
+        &xprv.secret_key,
+        Some(xprv.chain_code),
+    );
+    for party in parties.iter_mut() {
+        party.derivation_data.depth = xprv.depth;
+        party.derivation_data.child_number = xprv.child_number;
+        party.derivation_data.parent_fingerprint = xprv.parent_fingerprint;
+    }
+
+    let xpub = xprv.public_key();
+    if parties.iter().any(|party| party.pk != xpub.pk) {
+        return Err(ErrorDeriv::new("Shares do not match the imported key!"));
+    }
+
+    Ok((parties, xpub.encode(version)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_import_wallet(
+    import_json_in: *const c_char,
+) -> *const c_char {
+    let import_in: ImportIn = ImportIn::from_json(import_json_in);
+    match import_wallet(
+        &import_in.parameters,
+        &import_in.session_id,
+        &import_in.source,
+    ) {
+        Err(error) => ErrorOut {
+            error: DerivationError::from(error),
+        }
+        .to_json(),
+
+        Ok((parties, xpub)) => ImportOut { parties, xpub }.to_json(),
+    }
+}
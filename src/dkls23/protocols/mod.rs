@@ -1,4 +1,18 @@
+pub mod abort;
+pub mod address;
+pub mod batch;
 pub mod derivation;
 pub mod dkg;
+pub mod echo;
+pub mod import;
+pub mod pairing;
+pub mod public_derivation;
 pub mod re_key;
+#[cfg(feature = "reconstruct")]
+pub mod reconstruct;
+pub mod resume;
+pub mod router;
+pub mod run;
+pub mod session_id;
 pub mod signing;
+pub mod xpub;
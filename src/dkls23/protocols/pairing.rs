@@ -0,0 +1,89 @@
+use crate::dkls23::session::pair::{
+    commit, finish, reveal, PairCommitment, PairKept, PairReveal, PinnedPeer,
+    Sas,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Pairing structs. The commitment and the reveal are sent to the other
+// device, over any channel.
+#[derive(Deserialize, Serialize)]
+pub struct PairCommitIn {
+    pub key: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PairCommitOut {
+    pub kept: PairKept,
+    pub commitment: PairCommitment,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PairRevealIn {
+    pub kept: PairKept,
+    pub commitment: PairCommitment,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PairRevealOut {
+    pub kept: PairKept,
+    pub reveal: PairReveal,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PairFinishIn {
+    pub kept: PairKept,
+    pub reveal: PairReveal,
+}
+
+// The peer goes into `pinned` of the identity keys, at the index of the
+// party, once the users have compared the codes.
+#[derive(Deserialize, Serialize)]
+pub struct PairFinishOut {
+    pub sas: Sas,
+    pub peer: PinnedPeer,
+}
+
+impl CJson for PairCommitIn {}
+impl CJson for PairCommitOut {}
+impl CJson for PairRevealIn {}
+impl CJson for PairRevealOut {}
+impl CJson for PairFinishIn {}
+impl CJson for PairFinishOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_pair_commit(
+    commit_json_in: *const c_char,
+) -> *const c_char {
+    let commit_in: PairCommitIn = PairCommitIn::from_json(commit_json_in);
+
+    match commit(&commit_in.key) {
+        Ok((kept, commitment)) => PairCommitOut { kept, commitment }.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_pair_reveal(
+    reveal_json_in: *const c_char,
+) -> *const c_char {
+    let reveal_in: PairRevealIn = PairRevealIn::from_json(reveal_json_in);
+
+    match reveal(reveal_in.kept, reveal_in.commitment) {
+        Ok((kept, reveal)) => PairRevealOut { kept, reveal }.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_pair_finish(
+    finish_json_in: *const c_char,
+) -> *const c_char {
+    let finish_in: PairFinishIn = PairFinishIn::from_json(finish_json_in);
+
+    match finish(finish_in.kept, finish_in.reveal) {
+        Ok((sas, peer)) => PairFinishOut { sas, peer }.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
@@ -0,0 +1,140 @@
+use crate::dkls23::utilities::address::Addresses;
+use crate::dkls23::utilities::bip32::ExtendedPublicKey;
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use dkls23::protocols::derivation::ChainCode;
+use dkls23::protocols::Party;
+use k256::AffinePoint;
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Public key from which we derive. Either an xpub, a public key
+// together with its chain code, which is taken as a master key,
+// or a party, of which only the public derivation data is used.
+#[derive(Deserialize, Serialize)]
+pub struct PublicKeySource {
+    #[serde(default)]
+    pub party: Option<Party>,
+    #[serde(default)]
+    pub xpub: Option<String>,
+    #[serde(default)]
+    pub pk: Option<AffinePoint>,
+    #[serde(default)]
+    pub chain_code: Option<ChainCode>,
+}
+
+impl PublicKeySource {
+    pub fn to_extended_key(
+        &self,
+    ) -> Result<ExtendedPublicKey, DerivationError> {
+        match (&self.party, &self.xpub, self.pk, self.chain_code) {
+            (Some(party), None, None, None) => {
+                Ok(ExtendedPublicKey::from(&party.derivation_data))
+            }
+            (None, Some(xpub), None, None) => {
+                let (key, _) = ExtendedPublicKey::decode(xpub)?;
+                Ok(key)
+            }
+            (None, None, Some(pk), Some(chain_code)) => Ok(ExtendedPublicKey {
+                depth: 0,
+                parent_fingerprint: [0; 4],
+                child_number: 0,
+                chain_code,
+                pk,
+            }),
+            _ => Err(DerivationError::Other {
+                description: String::from(
+                    "Give a party, an xpub or a public key with a chain code!",
+                ),
+            }),
+        }
+    }
+}
+
+// Public derivation structs
+#[derive(Deserialize, Serialize)]
+pub struct PublicDeriveFromPathIn {
+    #[serde(flatten)]
+    pub source: PublicKeySource,
+    pub path: String,
+    #[serde(default)]
+    pub cosmos_prefix: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PublicDeriveFromPathOut {
+    pub key: ExtendedPublicKey,
+    pub addresses: Addresses,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PublicDeriveChildIn {
+    #[serde(flatten)]
+    pub source: PublicKeySource,
+    pub child_number: u32,
+    #[serde(default)]
+    pub cosmos_prefix: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PublicDeriveChildOut {
+    pub key: ExtendedPublicKey,
+    pub addresses: Addresses,
+}
+
+impl CJson for PublicDeriveFromPathIn {}
+impl CJson for PublicDeriveFromPathOut {}
+impl CJson for PublicDeriveChildIn {}
+impl CJson for PublicDeriveChildOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_public_derive_from_path(
+    derive_json_in: *const c_char,
+) -> *const c_char {
+    let derive_from_path_in: PublicDeriveFromPathIn =
+        PublicDeriveFromPathIn::from_json(derive_json_in);
+    let result = derive_from_path_in
+        .source
+        .to_extended_key()
+        .and_then(|key| key.derive_from_path(&derive_from_path_in.path))
+        .and_then(|key| {
+            let addresses = Addresses::new(
+                &key.pk,
+                derive_from_path_in.cosmos_prefix.as_deref(),
+            )?;
+            Ok((key, addresses))
+        });
+    match result {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok((key, addresses)) => {
+            PublicDeriveFromPathOut { key, addresses }.to_json()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_public_derive_child(
+    derive_json_in: *const c_char,
+) -> *const c_char {
+    let derive_child_in: PublicDeriveChildIn =
+        PublicDeriveChildIn::from_json(derive_json_in);
+    let result = derive_child_in
+        .source
+        .to_extended_key()
+        .and_then(|key| key.derive_child(derive_child_in.child_number))
+        .and_then(|key| {
+            let addresses = Addresses::new(
+                &key.pk,
+                derive_child_in.cosmos_prefix.as_deref(),
+            )?;
+            Ok((key, addresses))
+        });
+    match result {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok((key, addresses)) => {
+            PublicDeriveChildOut { key, addresses }.to_json()
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::dkls23::utilities::bip32::{ExtendedPrivateKey, XPRV_VERSION};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use dkls23::protocols::Party;
+use k256::{ProjectivePoint, Scalar};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::os::raw::c_char;
+
+// Reconstruction is only compiled with the "reconstruct" feature.
+// It brings the whole secret key to a single place, so it should
+// only be used to wind down a wallet or during recovery drills.
+
+// Reconstruct structs
+#[derive(Deserialize, Serialize)]
+pub struct ReconstructIn {
+    pub parties: Vec<Party>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ReconstructOut {
+    pub secret_key: Scalar,
+    pub xprv: String,
+}
+
+impl CJson for ReconstructIn {}
+impl CJson for ReconstructOut {}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ErrorReconstruct {
+    pub description: String,
+}
+
+impl ErrorReconstruct {
+    pub fn new(description: &str) -> ErrorReconstruct {
+        ErrorReconstruct {
+            description: String::from(description),
+        }
+    }
+}
+
+// Lagrange coefficient of the party with index i for the
+// interpolation at zero over the given set of indices.
+fn lagrange_coefficient(i: u8, indices: &[u8]) -> Scalar {
+    let x_i = Scalar::from(i as u32);
+
+    let mut numerator = Scalar::ONE;
+    let mut denominator = Scalar::ONE;
+    for &j in indices {
+        if j == i {
+            continue;
+        }
+        let x_j = Scalar::from(j as u32);
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+
+    // The indices are distinct, so the denominator is invertible.
+    numerator * denominator.invert().unwrap()
+}
+
+pub fn reconstruct(
+    parties: &[Party],
+) -> Result<ExtendedPrivateKey, ErrorReconstruct> {
+    let first = parties
+        .first()
+        .ok_or(ErrorReconstruct::new("No parties were given!"))?;
+
+    if parties.len() < first.parameters.threshold as usize {
+        return Err(ErrorReconstruct::new(
+            "Not enough parties to reach the threshold!",
+        ));
+    }
+
+    let mut seen = BTreeSet::new();
+    for party in parties {
+        if party.pk != first.pk
+            || party.derivation_data.chain_code
+                != first.derivation_data.chain_code
+            || party.derivation_data.depth != first.derivation_data.depth
+            || party.derivation_data.child_number
+                != first.derivation_data.child_number
+        {
+            return Err(ErrorReconstruct::new(
+                "Parties belong to different keys!",
+            ));
+        }
+        if party.party_index == 0 || !seen.insert(party.party_index) {
+            return Err(ErrorReconstruct::new("Invalid or repeated index!"));
+        }
+    }
+
+    let indices: Vec<u8> = parties.iter().map(|p| p.party_index).collect();
+    let secret_key: Scalar = parties
+        .iter()
+        .map(|party| {
+            lagrange_coefficient(party.party_index, &indices) * party.poly_point
+        })
+        .sum();
+
+    if (ProjectivePoint::GENERATOR * secret_key).to_affine() != first.pk {
+        return Err(ErrorReconstruct::new(
+            "Reconstructed key does not match the public key!",
+        ));
+    }
+
+    Ok(ExtendedPrivateKey {
+        depth: first.derivation_data.depth,
+        parent_fingerprint: first.derivation_data.parent_fingerprint,
+        child_number: first.derivation_data.child_number,
+        chain_code: first.derivation_data.chain_code,
+        secret_key,
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_reconstruct(
+    reconstruct_json_in: *const c_char,
+) -> *const c_char {
+    let reconstruct_in: ReconstructIn =
+        ReconstructIn::from_json(reconstruct_json_in);
+    match reconstruct(&reconstruct_in.parties) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(xprv) => ReconstructOut {
+            secret_key: xprv.secret_key,
+            xprv: xprv.encode(XPRV_VERSION),
+        }
+        .to_json(),
+    }
+}
@@ -0,0 +1,551 @@
+use crate::dkls23::protocols::signing::at_path;
+use crate::dkls23::session::abort::{signing_key, verifying_key, AbortMessage};
+use crate::dkls23::session::dkg::{DkgSession, DKG_KIND};
+use crate::dkls23::session::id::{
+    require, SessionIdError, SessionIdTranscript,
+};
+use crate::dkls23::session::seal::{
+    require_identity, Identity, IdentityKeys, SealError, SealedMessage,
+};
+use crate::dkls23::session::sign::{SignSession, Signature, SIGN_KIND};
+use crate::dkls23::session::store::{Checkpoint, SessionStore, StoreError};
+use crate::dkls23::session::{
+    Closure, Message, Payload, SessionError, Timeouts,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::SignData;
+use dkls23::protocols::Party;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::os::raw::c_char;
+
+// Resumable sessions. Instead of handing the kept state back to the
+// caller after every phase, the session is checkpointed in a directory
+// and found again by its id, so it survives the app being killed.
+// Every call loads the checkpoint, applies one step and saves it before
+// answering. The messages to send are saved too, so after a restart
+// they can be sent again with dkls_resume_status.
+//
+// With timeouts, a session that misses a deadline is closed by the next
+// call, and its kept state is wiped from the checkpoint. The same
+// happens when it is aborted by us or by a counterparty.
+//
+// With identity keys, the messages are given out sealed for their
+// receivers and only sealed messages are received. The checkpoint keeps
+// them in the clear, since it is encrypted anyway.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ResumeError {
+    Store(StoreError),
+    Session(SessionError),
+    Derivation(DerivationError),
+    SessionId(SessionIdError),
+    Seal(SealError),
+    // A message that is not a message of this kind of session.
+    InvalidMessage { description: String },
+}
+
+impl From<StoreError> for ResumeError {
+    fn from(error: StoreError) -> ResumeError {
+        ResumeError::Store(error)
+    }
+}
+
+impl From<SessionError> for ResumeError {
+    fn from(error: SessionError) -> ResumeError {
+        ResumeError::Session(error)
+    }
+}
+
+impl From<DerivationError> for ResumeError {
+    fn from(error: DerivationError) -> ResumeError {
+        ResumeError::Derivation(error)
+    }
+}
+
+impl From<SessionIdError> for ResumeError {
+    fn from(error: SessionIdError) -> ResumeError {
+        ResumeError::SessionId(error)
+    }
+}
+
+impl From<SealError> for ResumeError {
+    fn from(error: SealError) -> ResumeError {
+        ResumeError::Seal(error)
+    }
+}
+
+// Where the checkpoints are kept and the 32-byte key, in hex, that
+// encrypts them. The key should come from the platform keystore.
+#[derive(Deserialize, Serialize)]
+pub struct StoreIn {
+    pub dir: String,
+    pub key: String,
+}
+
+impl StoreIn {
+    fn open(&self) -> Result<SessionStore, StoreError> {
+        let key = hex::decode(&self.key).map_err(|_| StoreError::InvalidKey)?;
+        SessionStore::new(&self.dir, &key)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeDkgIn {
+    pub store: StoreIn,
+    pub id: String,
+    pub session: SessionData,
+    #[serde(default)]
+    pub timeouts: Option<Timeouts>,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    // Our nonce from dkls_session_id_commit, which must be our reveal
+    // in the transcript.
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeSignIn {
+    pub store: StoreIn,
+    pub id: String,
+    pub party: Party,
+    pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    pub normalize: bool,
+    #[serde(default)]
+    pub timeouts: Option<Timeouts>,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeIn {
+    pub store: StoreIn,
+    pub id: String,
+    // Seals the messages to send.
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeReceiveIn {
+    pub store: StoreIn,
+    pub id: String,
+    pub messages: Vec<Value>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+}
+
+// The identity key, in hex, signs the abort message.
+#[derive(Deserialize, Serialize)]
+pub struct ResumeAbortIn {
+    pub store: StoreIn,
+    pub id: String,
+    pub key: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeAbortOut {
+    pub abort: AbortMessage,
+}
+
+// The public key is the identity key of the sender of the abort.
+#[derive(Deserialize, Serialize)]
+pub struct ResumeReceiveAbortIn {
+    pub store: StoreIn,
+    pub id: String,
+    pub abort: AbortMessage,
+    pub public_key: String,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+}
+
+// The phase last completed, whether the next one can run and the
+// messages of the last phase, which may not have been sent yet.
+#[derive(Deserialize, Serialize)]
+pub struct ResumeStatusOut {
+    pub phase: u8,
+    pub ready: bool,
+    pub outbox: Vec<Value>,
+    pub deadline: Option<u64>,
+    pub closure: Option<Closure>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeRemoveOut {
+    pub removed: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ResumeFinishOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+}
+
+impl CJson for StoreIn {}
+impl CJson for ResumeDkgIn {}
+impl CJson for ResumeSignIn {}
+impl CJson for ResumeIn {}
+impl CJson for ResumeReceiveIn {}
+impl CJson for ResumeAbortIn {}
+impl CJson for ResumeAbortOut {}
+impl CJson for ResumeReceiveAbortIn {}
+impl CJson for ResumeStatusOut {}
+impl CJson for ResumeRemoveOut {}
+impl CJson for ResumeFinishOut {}
+
+fn to_values<T: Serialize>(messages: &[T]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| serde_json::to_value(message).unwrap())
+        .collect()
+}
+
+fn from_value<T: for<'de> Deserialize<'de>>(
+    message: Value,
+) -> Result<T, ResumeError> {
+    serde_json::from_value(message).map_err(|error| {
+        ResumeError::InvalidMessage {
+            description: error.to_string(),
+        }
+    })
+}
+
+// Our identity in the session. The sealed-only feature requires one.
+fn identity(
+    checkpoint: &Checkpoint,
+    keys: Option<&IdentityKeys>,
+) -> Result<Option<Identity>, ResumeError> {
+    let party_index = match checkpoint {
+        Checkpoint::Dkg { session, .. } => session.data().party_index,
+        Checkpoint::Sign { session, .. } => session.party().party_index,
+    };
+    Ok(require_identity(party_index, keys)?)
+}
+
+fn seal_all<P: Payload + Serialize>(
+    identity: &Identity,
+    session_id: &[u8],
+    kind: &str,
+    messages: &[Message<P>],
+) -> Result<Vec<Value>, ResumeError> {
+    let sealed = messages
+        .iter()
+        .map(|message| identity.seal_message(session_id, kind, message))
+        .collect::<Result<Vec<SealedMessage>, SealError>>()?;
+    Ok(to_values(&sealed))
+}
+
+fn status(
+    checkpoint: &Checkpoint,
+    identity: Option<&Identity>,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let outbox = match (checkpoint, identity) {
+        (Checkpoint::Dkg { session, outbox }, Some(identity)) => {
+            let session_id = &session.data().session_id;
+            seal_all(identity, session_id, DKG_KIND, outbox)?
+        }
+        (Checkpoint::Sign { session, outbox }, Some(identity)) => {
+            let sign_id = &session.sign_data().sign_id;
+            seal_all(identity, sign_id, SIGN_KIND, outbox)?
+        }
+        (Checkpoint::Dkg { outbox, .. }, None) => to_values(outbox),
+        (Checkpoint::Sign { outbox, .. }, None) => to_values(outbox),
+    };
+    Ok(ResumeStatusOut {
+        phase: checkpoint.phase(),
+        ready: checkpoint.is_ready(),
+        outbox,
+        deadline: checkpoint.deadline(),
+        closure: checkpoint.closure().cloned(),
+    })
+}
+
+// A message to receive, opened first with an identity.
+fn open<P: Payload + DeserializeOwned>(
+    identity: Option<&Identity>,
+    session_id: &[u8],
+    kind: &str,
+    message: Value,
+) -> Result<Message<P>, ResumeError> {
+    match identity {
+        Some(identity) => {
+            let sealed: SealedMessage = from_value(message)?;
+            Ok(identity.open_message(session_id, kind, &sealed)?)
+        }
+        None => from_value(message),
+    }
+}
+
+// Runs one step on the checkpoint and saves it. A step that fails is
+// not saved, unless it closed the session, so that its kept state is
+// wiped from the disk too.
+fn step<T>(
+    store: &SessionStore,
+    id: &str,
+    checkpoint: &mut Checkpoint,
+    f: impl FnOnce(&mut Checkpoint) -> Result<T, ResumeError>,
+) -> Result<T, ResumeError> {
+    let result = f(checkpoint);
+    if result.is_ok() || checkpoint.closure().is_some() {
+        store.save(id, checkpoint)?;
+    }
+    result
+}
+
+fn create(
+    store: &SessionStore,
+    id: &str,
+    checkpoint: Checkpoint,
+) -> Result<ResumeStatusOut, ResumeError> {
+    if store.exists(id)? {
+        return Err(StoreError::Exists { id: id.to_string() }.into());
+    }
+    store.save(id, &checkpoint)?;
+    status(&checkpoint, None)
+}
+
+pub fn resume_dkg(
+    resume_in: ResumeDkgIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    require(
+        resume_in.transcript.as_ref(),
+        resume_in.id_nonce.as_deref(),
+        |transcript, nonce| transcript.check_dkg(&resume_in.session, nonce),
+    )?;
+    let mut session = DkgSession::new(resume_in.session);
+    if let Some(timeouts) = resume_in.timeouts {
+        session = session.with_timeouts(timeouts);
+    }
+    let checkpoint = Checkpoint::Dkg {
+        session,
+        outbox: Vec::new(),
+    };
+    create(&store, &resume_in.id, checkpoint)
+}
+
+pub fn resume_sign(
+    resume_in: ResumeSignIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    require(
+        resume_in.transcript.as_ref(),
+        resume_in.id_nonce.as_deref(),
+        |transcript, nonce| {
+            transcript.check_sign(&resume_in.party, &resume_in.sign_data, nonce)
+        },
+    )?;
+    let (party, sign_data) = at_path(
+        resume_in.party,
+        resume_in.sign_data,
+        resume_in.derivation_path.as_deref(),
+    )?;
+    let mut session = SignSession::new(party, sign_data, resume_in.normalize);
+    if let Some(timeouts) = resume_in.timeouts {
+        session = session.with_timeouts(timeouts);
+    }
+    let checkpoint = Checkpoint::Sign {
+        session,
+        outbox: Vec::new(),
+    };
+    create(&store, &resume_in.id, checkpoint)
+}
+
+// A message that was already received is skipped, so after a restart
+// the caller can pass everything it has again. A different message from
+// a party that already sent one for the round is an error. Nothing is
+// saved unless every message is accepted.
+pub fn resume_receive(
+    receive_in: ResumeReceiveIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = receive_in.store.open()?;
+    let mut checkpoint: Checkpoint = store.load(&receive_in.id)?;
+    let identity = identity(&checkpoint, receive_in.identity.as_ref())?;
+    let identity = identity.as_ref();
+
+    step(&store, &receive_in.id, &mut checkpoint, |checkpoint| {
+        for message in receive_in.messages {
+            let result = match checkpoint {
+                Checkpoint::Dkg { session, .. } => {
+                    let session_id = session.data().session_id.clone();
+                    session.receive(open(
+                        identity,
+                        &session_id,
+                        DKG_KIND,
+                        message,
+                    )?)
+                }
+                Checkpoint::Sign { session, .. } => {
+                    let sign_id = session.sign_data().sign_id.clone();
+                    session
+                        .receive(open(identity, &sign_id, SIGN_KIND, message)?)
+                }
+            };
+            match result {
+                Ok(()) | Err(SessionError::Duplicate { .. }) => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    })?;
+    status(&checkpoint, identity)
+}
+
+pub fn resume_advance(
+    resume_in: ResumeIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    let mut checkpoint: Checkpoint = store.load(&resume_in.id)?;
+    let identity = identity(&checkpoint, resume_in.identity.as_ref())?;
+
+    step(&store, &resume_in.id, &mut checkpoint, |checkpoint| {
+        match checkpoint {
+            Checkpoint::Dkg { session, outbox } => {
+                *outbox = session.advance()?
+            }
+            Checkpoint::Sign { session, outbox } => {
+                *outbox = session.advance()?
+            }
+        }
+        Ok(())
+    })?;
+    status(&checkpoint, identity.as_ref())
+}
+
+pub fn resume_status(
+    resume_in: ResumeIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    let mut checkpoint: Checkpoint = store.load(&resume_in.id)?;
+    let identity = identity(&checkpoint, resume_in.identity.as_ref())?;
+
+    // A session past its deadline is closed here, so hosts find out
+    // without calling the next phase.
+    if checkpoint.closure().is_none() && checkpoint.check_open().is_err() {
+        store.save(&resume_in.id, &checkpoint)?;
+    }
+    status(&checkpoint, identity.as_ref())
+}
+
+pub fn resume_abort(
+    abort_in: ResumeAbortIn,
+) -> Result<ResumeAbortOut, ResumeError> {
+    let store = abort_in.store.open()?;
+    let key = signing_key(&abort_in.key)?;
+    let mut checkpoint: Checkpoint = store.load(&abort_in.id)?;
+
+    let abort = checkpoint.abort(&key, &abort_in.reason);
+    store.save(&abort_in.id, &checkpoint)?;
+    Ok(ResumeAbortOut { abort })
+}
+
+pub fn resume_receive_abort(
+    abort_in: ResumeReceiveAbortIn,
+) -> Result<ResumeStatusOut, ResumeError> {
+    let store = abort_in.store.open()?;
+    let key = verifying_key(&abort_in.public_key)?;
+    let mut checkpoint: Checkpoint = store.load(&abort_in.id)?;
+    let identity = identity(&checkpoint, abort_in.identity.as_ref())?;
+
+    step(&store, &abort_in.id, &mut checkpoint, |checkpoint| {
+        Ok(checkpoint.receive_abort(&abort_in.abort, &key)?)
+    })?;
+    status(&checkpoint, identity.as_ref())
+}
+
+// Gives the result and deletes the checkpoint.
+pub fn resume_finish(
+    resume_in: ResumeIn,
+) -> Result<ResumeFinishOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    let checkpoint: Checkpoint = store.load(&resume_in.id)?;
+
+    let finish_out = match checkpoint {
+        Checkpoint::Dkg { session, .. } => ResumeFinishOut {
+            party: Some(session.finish()?),
+            signature: None,
+        },
+        Checkpoint::Sign { session, .. } => ResumeFinishOut {
+            party: None,
+            signature: Some(session.finish()?),
+        },
+    };
+
+    store.remove(&resume_in.id)?;
+    Ok(finish_out)
+}
+
+// Drops a session that will not be finished.
+pub fn resume_remove(
+    resume_in: ResumeIn,
+) -> Result<ResumeRemoveOut, ResumeError> {
+    let store = resume_in.store.open()?;
+    let removed = store.exists(&resume_in.id)?;
+    store.remove(&resume_in.id)?;
+    Ok(ResumeRemoveOut { removed })
+}
+
+fn to_json<T: CJson>(result: Result<T, ResumeError>) -> *const c_char {
+    match result {
+        Ok(out) => out.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_dkg(data: *const c_char) -> *const c_char {
+    to_json(resume_dkg(ResumeDkgIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_sign(data: *const c_char) -> *const c_char {
+    to_json(resume_sign(ResumeSignIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_receive(data: *const c_char) -> *const c_char {
+    to_json(resume_receive(ResumeReceiveIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_advance(data: *const c_char) -> *const c_char {
+    to_json(resume_advance(ResumeIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_status(data: *const c_char) -> *const c_char {
+    to_json(resume_status(ResumeIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_abort(data: *const c_char) -> *const c_char {
+    to_json(resume_abort(ResumeAbortIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_receive_abort(
+    data: *const c_char,
+) -> *const c_char {
+    to_json(resume_receive_abort(ResumeReceiveAbortIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_finish(data: *const c_char) -> *const c_char {
+    to_json(resume_finish(ResumeIn::from_json(data)))
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_resume_remove(data: *const c_char) -> *const c_char {
+    to_json(resume_remove(ResumeIn::from_json(data)))
+}
@@ -0,0 +1,605 @@
+use crate::dkls23::protocols::{dkg, signing};
+use crate::dkls23::session::seal::SealedMessage;
+use crate::dkls23::session::sign::SIGN_KIND;
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use dkls23::protocols::dkg::{
+    BroadcastDerivationPhase2to4, BroadcastDerivationPhase3to4,
+    ProofCommitment, TransmitInitMulPhase3to4, TransmitInitZeroSharePhase2to4,
+    TransmitInitZeroSharePhase3to4,
+};
+use dkls23::protocols::signing::{
+    Broadcast3to4, TransmitPhase1to2, TransmitPhase2to3,
+};
+use dkls23::protocols::PartiesMessage;
+use k256::Scalar;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::os::raw::c_char;
+
+// The router takes the output of every party for a phase, keyed by party
+// index, and gives back what each party has to receive in the next phase.
+// Phases run with identity keys output their point-to-point messages in
+// `sealed` instead, which is passed next to the outputs, keyed the same
+// way, and given back by receiver. The sealed-only feature refuses plain
+// point-to-point messages.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RoutingError {
+    // The output of a party that should take part is missing.
+    MissingParty { index: u8 },
+    // A message whose sender is not the party that output it, or whose
+    // receiver is not another party of the protocol. Broadcasts have
+    // no receiver and are reported with receiver 0.
+    Misaddressed { from: u8, sender: u8, receiver: u8 },
+    // Two messages for the same sender and receiver.
+    Duplicate { sender: u8, receiver: u8 },
+    // A party did not output a message for one of the others.
+    MissingMessage { sender: u8, receiver: u8 },
+    // The outputs do not agree with each other.
+    Inconsistent { description: String },
+    // Plain point-to-point messages, with the sealed-only feature.
+    Unsealed,
+}
+
+// Messages sent from one party to another.
+trait Transmit: Clone {
+    fn parties(&self) -> &PartiesMessage;
+}
+
+impl Transmit for TransmitInitZeroSharePhase2to4 {
+    fn parties(&self) -> &PartiesMessage {
+        &self.parties
+    }
+}
+
+impl Transmit for TransmitInitZeroSharePhase3to4 {
+    fn parties(&self) -> &PartiesMessage {
+        &self.parties
+    }
+}
+
+impl Transmit for TransmitInitMulPhase3to4 {
+    fn parties(&self) -> &PartiesMessage {
+        &self.parties
+    }
+}
+
+impl Transmit for TransmitPhase1to2 {
+    fn parties(&self) -> &PartiesMessage {
+        &self.parties
+    }
+}
+
+impl Transmit for TransmitPhase2to3 {
+    fn parties(&self) -> &PartiesMessage {
+        &self.parties
+    }
+}
+
+// Whether the phase was run with identity keys.
+fn is_sealed(
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<bool, RoutingError> {
+    let is_sealed = sealed.values().any(|messages| !messages.is_empty());
+    if !is_sealed && cfg!(feature = "sealed-only") {
+        return Err(RoutingError::Unsealed);
+    }
+    Ok(is_sealed)
+}
+
+// Groups the messages by receiver. Every party must send exactly one
+// message to each of the other parties. When the phase was sealed, the
+// messages are in the sealed ones and none may be left in the clear.
+fn route<'a, T: Transmit + 'a>(
+    mut transmits: impl Iterator<Item = (u8, &'a Vec<T>)>,
+    indices: &BTreeSet<u8>,
+    sealed: bool,
+) -> Result<BTreeMap<u8, Vec<T>>, RoutingError> {
+    if sealed {
+        if let Some((from, _)) =
+            transmits.find(|(_, messages)| !messages.is_empty())
+        {
+            return Err(RoutingError::Inconsistent {
+                description: format!(
+                    "Party {} sent plain messages in a sealed phase!",
+                    from
+                ),
+            });
+        }
+        return Ok(indices.iter().map(|&index| (index, Vec::new())).collect());
+    }
+
+    let mut received: BTreeMap<u8, BTreeMap<u8, T>> = indices
+        .iter()
+        .map(|&index| (index, BTreeMap::new()))
+        .collect();
+
+    for (from, messages) in transmits {
+        for message in messages {
+            let sender = message.parties().sender;
+            let receiver = message.parties().receiver;
+            if sender != from
+                || receiver == from
+                || !indices.contains(&receiver)
+            {
+                return Err(RoutingError::Misaddressed {
+                    from,
+                    sender,
+                    receiver,
+                });
+            }
+            let inbox = received.get_mut(&receiver).unwrap();
+            if inbox.insert(sender, message.clone()).is_some() {
+                return Err(RoutingError::Duplicate { sender, receiver });
+            }
+        }
+    }
+
+    for (&receiver, inbox) in &received {
+        for &sender in indices {
+            if sender != receiver && !inbox.contains_key(&sender) {
+                return Err(RoutingError::MissingMessage { sender, receiver });
+            }
+        }
+    }
+
+    Ok(received
+        .into_iter()
+        .map(|(receiver, inbox)| (receiver, inbox.into_values().collect()))
+        .collect())
+}
+
+// The same for sealed messages, of which every party sends one of each
+// kind of the phase to each of the others.
+fn route_sealed(
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+    indices: &BTreeSet<u8>,
+    phase: u8,
+    kinds: &[&str],
+) -> Result<BTreeMap<u8, Vec<SealedMessage>>, RoutingError> {
+    let mut received: BTreeMap<u8, Vec<SealedMessage>> =
+        indices.iter().map(|&index| (index, Vec::new())).collect();
+    let mut sent = BTreeSet::new();
+
+    for (&from, messages) in sealed {
+        for message in messages {
+            let sender = message.sender;
+            let receiver = message.receiver;
+            if sender != from
+                || receiver == from
+                || !indices.contains(&sender)
+                || !indices.contains(&receiver)
+                || message.phase != phase
+                || !kinds.contains(&message.kind.as_str())
+            {
+                return Err(RoutingError::Misaddressed {
+                    from,
+                    sender,
+                    receiver,
+                });
+            }
+            if !sent.insert((sender, receiver, message.kind.as_str())) {
+                return Err(RoutingError::Duplicate { sender, receiver });
+            }
+            received.get_mut(&receiver).unwrap().push(message.clone());
+        }
+    }
+
+    for &receiver in indices {
+        for &sender in indices {
+            if sender != receiver
+                && kinds
+                    .iter()
+                    .any(|kind| !sent.contains(&(sender, receiver, kind)))
+            {
+                return Err(RoutingError::MissingMessage { sender, receiver });
+            }
+        }
+    }
+
+    Ok(received)
+}
+
+// In the DKG every party takes part, so the indices must be 1, ..., n.
+fn dkg_indices<T>(
+    outputs: &BTreeMap<u8, T>,
+) -> Result<BTreeSet<u8>, RoutingError> {
+    let indices: BTreeSet<u8> = (1..=outputs.len().max(1) as u8).collect();
+    for &index in &indices {
+        if !outputs.contains_key(&index) {
+            return Err(RoutingError::MissingParty { index });
+        }
+    }
+    Ok(indices)
+}
+
+// Router structs
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase1In {
+    pub outputs: BTreeMap<u8, dkg::Phase1Out>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase1Out {
+    pub poly_fragments: BTreeMap<u8, Vec<Scalar>>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase2In {
+    pub outputs: BTreeMap<u8, dkg::Phase2Out>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase2Out {
+    pub proofs_commitments: Vec<ProofCommitment>,
+    pub zero_received_phase2: BTreeMap<u8, Vec<TransmitInitZeroSharePhase2to4>>,
+    pub bip_broadcast_2to4: BTreeMap<u8, BroadcastDerivationPhase2to4>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase3In {
+    pub outputs: BTreeMap<u8, dkg::Phase3Out>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteDkgPhase3Out {
+    pub zero_received_phase3: BTreeMap<u8, Vec<TransmitInitZeroSharePhase3to4>>,
+    pub mul_received: BTreeMap<u8, Vec<TransmitInitMulPhase3to4>>,
+    pub bip_broadcast_3to4: BTreeMap<u8, BroadcastDerivationPhase3to4>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase1In {
+    pub outputs: BTreeMap<u8, signing::Phase1Out>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase1Out {
+    pub received: BTreeMap<u8, Vec<TransmitPhase1to2>>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase2In {
+    pub outputs: BTreeMap<u8, signing::Phase2Out>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase2Out {
+    pub received: BTreeMap<u8, Vec<TransmitPhase2to3>>,
+    #[serde(default)]
+    pub sealed: BTreeMap<u8, Vec<SealedMessage>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase3In {
+    pub outputs: BTreeMap<u8, signing::Phase3Out>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RouteSignPhase3Out {
+    pub x_coord: String,
+    pub received: Vec<Broadcast3to4>,
+    // The same broadcasts by sender, as the echo needs them.
+    pub broadcasts: BTreeMap<u8, Broadcast3to4>,
+}
+
+impl CJson for RouteDkgPhase1In {}
+impl CJson for RouteDkgPhase1Out {}
+impl CJson for RouteDkgPhase2In {}
+impl CJson for RouteDkgPhase2Out {}
+impl CJson for RouteDkgPhase3In {}
+impl CJson for RouteDkgPhase3Out {}
+impl CJson for RouteSignPhase1In {}
+impl CJson for RouteSignPhase1Out {}
+impl CJson for RouteSignPhase2In {}
+impl CJson for RouteSignPhase2Out {}
+impl CJson for RouteSignPhase3In {}
+impl CJson for RouteSignPhase3Out {}
+
+// Party i sends its j-th fragment to party j, itself included.
+pub fn route_dkg_phase1(
+    outputs: &BTreeMap<u8, dkg::Phase1Out>,
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<RouteDkgPhase1Out, RoutingError> {
+    let indices = dkg_indices(outputs)?;
+    // Sealed fragments are zero in the outputs, but for our own.
+    let sealed = if is_sealed(sealed)? {
+        route_sealed(sealed, &indices, 1, &["fragment"])?
+    } else {
+        BTreeMap::new()
+    };
+
+    for (&from, output) in outputs {
+        if output.fragments.len() != indices.len() {
+            return Err(RoutingError::Inconsistent {
+                description: format!(
+                    "Party {} has {} fragments instead of {}!",
+                    from,
+                    output.fragments.len(),
+                    indices.len()
+                ),
+            });
+        }
+    }
+
+    let poly_fragments = indices
+        .iter()
+        .map(|&receiver| {
+            let fragments = outputs
+                .values()
+                .map(|output| output.fragments[(receiver - 1) as usize])
+                .collect();
+            (receiver, fragments)
+        })
+        .collect();
+
+    Ok(RouteDkgPhase1Out {
+        poly_fragments,
+        sealed,
+    })
+}
+
+pub fn route_dkg_phase2(
+    outputs: &BTreeMap<u8, dkg::Phase2Out>,
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<RouteDkgPhase2Out, RoutingError> {
+    let indices = dkg_indices(outputs)?;
+    let is_sealed = is_sealed(sealed)?;
+
+    let mut proofs_commitments = Vec::with_capacity(outputs.len());
+    let mut bip_broadcast_2to4 = BTreeMap::new();
+    for (&from, output) in outputs {
+        let index = output.proof_commitment.index;
+        if index != from {
+            return Err(RoutingError::Misaddressed {
+                from,
+                sender: index,
+                receiver: 0,
+            });
+        }
+        let sender = output.bip_broadcast.sender_index;
+        if sender != from {
+            return Err(RoutingError::Misaddressed {
+                from,
+                sender,
+                receiver: 0,
+            });
+        }
+        proofs_commitments.push(output.proof_commitment.clone());
+        bip_broadcast_2to4.insert(from, output.bip_broadcast.clone());
+    }
+
+    let zero_received_phase2 = route(
+        outputs
+            .iter()
+            .map(|(&from, output)| (from, &output.zero_transmit)),
+        &indices,
+        is_sealed,
+    )?;
+    let sealed = if is_sealed {
+        route_sealed(sealed, &indices, 2, &["zero"])?
+    } else {
+        BTreeMap::new()
+    };
+
+    Ok(RouteDkgPhase2Out {
+        proofs_commitments,
+        zero_received_phase2,
+        bip_broadcast_2to4,
+        sealed,
+    })
+}
+
+pub fn route_dkg_phase3(
+    outputs: &BTreeMap<u8, dkg::Phase3Out>,
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<RouteDkgPhase3Out, RoutingError> {
+    let indices = dkg_indices(outputs)?;
+    let is_sealed = is_sealed(sealed)?;
+
+    let mut bip_broadcast_3to4 = BTreeMap::new();
+    for (&from, output) in outputs {
+        let sender = output.bip_broadcast.sender_index;
+        if sender != from {
+            return Err(RoutingError::Misaddressed {
+                from,
+                sender,
+                receiver: 0,
+            });
+        }
+        bip_broadcast_3to4.insert(from, output.bip_broadcast.clone());
+    }
+
+    let zero_received_phase3 = route(
+        outputs
+            .iter()
+            .map(|(&from, output)| (from, &output.zero_transmit)),
+        &indices,
+        is_sealed,
+    )?;
+    let mul_received = route(
+        outputs
+            .iter()
+            .map(|(&from, output)| (from, &output.mul_transmit)),
+        &indices,
+        is_sealed,
+    )?;
+    let sealed = if is_sealed {
+        route_sealed(sealed, &indices, 3, &["zero", "mul"])?
+    } else {
+        BTreeMap::new()
+    };
+
+    Ok(RouteDkgPhase3Out {
+        zero_received_phase3,
+        mul_received,
+        bip_broadcast_3to4,
+        sealed,
+    })
+}
+
+// For signing, the parties are the keys of the map, i.e. the signers.
+pub fn route_sign_phase1(
+    outputs: &BTreeMap<u8, signing::Phase1Out>,
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<RouteSignPhase1Out, RoutingError> {
+    let indices: BTreeSet<u8> = outputs.keys().copied().collect();
+    let is_sealed = is_sealed(sealed)?;
+    let received = route(
+        outputs
+            .iter()
+            .map(|(&from, output)| (from, &output.transmit)),
+        &indices,
+        is_sealed,
+    )?;
+    let sealed = if is_sealed {
+        route_sealed(sealed, &indices, 1, &[SIGN_KIND])?
+    } else {
+        BTreeMap::new()
+    };
+
+    Ok(RouteSignPhase1Out { received, sealed })
+}
+
+pub fn route_sign_phase2(
+    outputs: &BTreeMap<u8, signing::Phase2Out>,
+    sealed: &BTreeMap<u8, Vec<SealedMessage>>,
+) -> Result<RouteSignPhase2Out, RoutingError> {
+    let indices: BTreeSet<u8> = outputs.keys().copied().collect();
+    let is_sealed = is_sealed(sealed)?;
+    let received = route(
+        outputs
+            .iter()
+            .map(|(&from, output)| (from, &output.transmit)),
+        &indices,
+        is_sealed,
+    )?;
+    let sealed = if is_sealed {
+        route_sealed(sealed, &indices, 2, &[SIGN_KIND])?
+    } else {
+        BTreeMap::new()
+    };
+
+    Ok(RouteSignPhase2Out { received, sealed })
+}
+
+pub fn route_sign_phase3(
+    outputs: &BTreeMap<u8, signing::Phase3Out>,
+) -> Result<RouteSignPhase3Out, RoutingError> {
+    let x_coord = match outputs.values().next() {
+        Some(output) => output.x_coord.clone(),
+        None => return Err(RoutingError::MissingParty { index: 1 }),
+    };
+    if outputs.values().any(|output| output.x_coord != x_coord) {
+        return Err(RoutingError::Inconsistent {
+            description: String::from(
+                "The parties computed different x-coordinates!",
+            ),
+        });
+    }
+
+    let broadcasts: BTreeMap<u8, Broadcast3to4> = outputs
+        .iter()
+        .map(|(&index, output)| (index, output.broadcast.clone()))
+        .collect();
+    let received = broadcasts.values().cloned().collect();
+
+    Ok(RouteSignPhase3Out {
+        x_coord,
+        received,
+        broadcasts,
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_dkg_phase1(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteDkgPhase1In = RouteDkgPhase1In::from_json(route_json_in);
+    match route_dkg_phase1(&route_in.outputs, &route_in.sealed) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_dkg_phase2(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteDkgPhase2In = RouteDkgPhase2In::from_json(route_json_in);
+    match route_dkg_phase2(&route_in.outputs, &route_in.sealed) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_dkg_phase3(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteDkgPhase3In = RouteDkgPhase3In::from_json(route_json_in);
+    match route_dkg_phase3(&route_in.outputs, &route_in.sealed) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_sign_phase1(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteSignPhase1In =
+        RouteSignPhase1In::from_json(route_json_in);
+    match route_sign_phase1(&route_in.outputs, &route_in.sealed) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_sign_phase2(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteSignPhase2In =
+        RouteSignPhase2In::from_json(route_json_in);
+    match route_sign_phase2(&route_in.outputs, &route_in.sealed) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_route_sign_phase3(
+    route_json_in: *const c_char,
+) -> *const c_char {
+    let route_in: RouteSignPhase3In =
+        RouteSignPhase3In::from_json(route_json_in);
+    match route_sign_phase3(&route_in.outputs) {
+        Err(error) => ErrorOut { error }.to_json(),
+
+        Ok(route_out) => route_out.to_json(),
+    }
+}
@@ -0,0 +1,177 @@
+use crate::dkls23::protocols::signing::at_path;
+use crate::dkls23::session::id::{
+    require, SessionIdError, SessionIdTranscript,
+};
+use crate::dkls23::session::seal::IdentityKeys;
+use crate::dkls23::session::sign::Signature;
+use crate::dkls23::transport::callback::{CallbackTransport, DklsCallbacks};
+use crate::dkls23::transport::{
+    block_on, run_dkg, run_dkg_sealed, run_sign, run_sign_sealed, RunError,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::SignData;
+use dkls23::protocols::Party;
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+use std::time::Duration;
+
+// A whole DKG or signature in one call, over the callbacks of the host
+// (see transport::callback). The call blocks until the run ends, fails,
+// times out or is cancelled.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum HostRunError {
+    Run(RunError),
+    Derivation(DerivationError),
+    SessionId(SessionIdError),
+    // The callbacks pointer is null.
+    NoCallbacks,
+}
+
+impl From<RunError> for HostRunError {
+    fn from(error: RunError) -> HostRunError {
+        HostRunError::Run(error)
+    }
+}
+
+impl From<DerivationError> for HostRunError {
+    fn from(error: DerivationError) -> HostRunError {
+        HostRunError::Derivation(error)
+    }
+}
+
+impl From<SessionIdError> for HostRunError {
+    fn from(error: SessionIdError) -> HostRunError {
+        HostRunError::SessionId(error)
+    }
+}
+
+// Run structs. The timeout, in milliseconds, is for the whole run. With
+// identity keys, every message is sealed for its receiver.
+#[derive(Deserialize, Serialize)]
+pub struct DkgRunIn {
+    pub session: SessionData,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    // Our nonce from dkls_session_id_commit, which must be our reveal
+    // in the transcript.
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DkgRunOut {
+    pub party: Party,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SignRunIn {
+    pub party: Party,
+    pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    pub normalize: bool,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+}
+
+impl CJson for DkgRunIn {}
+impl CJson for DkgRunOut {}
+impl CJson for SignRunIn {}
+impl CJson for Signature {}
+
+pub fn dkg_run(
+    run_in: DkgRunIn,
+    callbacks: &DklsCallbacks,
+) -> Result<DkgRunOut, HostRunError> {
+    require(
+        run_in.transcript.as_ref(),
+        run_in.id_nonce.as_deref(),
+        |transcript, nonce| transcript.check_dkg(&run_in.session, nonce),
+    )?;
+    let timeout = run_in.timeout_ms.map(Duration::from_millis);
+    let mut transport = CallbackTransport::new(callbacks, timeout);
+    let party = match &run_in.identity {
+        Some(identity) => {
+            block_on(run_dkg_sealed(run_in.session, identity, &mut transport))
+        }
+        None => block_on(run_dkg(run_in.session, &mut transport)),
+    }?;
+    Ok(DkgRunOut { party })
+}
+
+pub fn sign_run(
+    run_in: SignRunIn,
+    callbacks: &DklsCallbacks,
+) -> Result<Signature, HostRunError> {
+    require(
+        run_in.transcript.as_ref(),
+        run_in.id_nonce.as_deref(),
+        |transcript, nonce| {
+            transcript.check_sign(&run_in.party, &run_in.sign_data, nonce)
+        },
+    )?;
+    let (party, sign_data) = at_path(
+        run_in.party,
+        run_in.sign_data,
+        run_in.derivation_path.as_deref(),
+    )?;
+    let timeout = run_in.timeout_ms.map(Duration::from_millis);
+    let mut transport = CallbackTransport::new(callbacks, timeout);
+    let normalize = run_in.normalize;
+    Ok(match &run_in.identity {
+        Some(identity) => block_on(run_sign_sealed(
+            party,
+            sign_data,
+            normalize,
+            identity,
+            &mut transport,
+        )),
+        None => block_on(run_sign(party, sign_data, normalize, &mut transport)),
+    }?)
+}
+
+fn to_json<T: CJson>(result: Result<T, HostRunError>) -> *const c_char {
+    match result {
+        Ok(out) => out.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn dkls_dkg_run(
+    data: *const c_char,
+    callbacks: *const DklsCallbacks,
+) -> *const c_char {
+    let run_in: DkgRunIn = DkgRunIn::from_json(data);
+    match unsafe { callbacks.as_ref() } {
+        Some(callbacks) => to_json(dkg_run(run_in, callbacks)),
+        None => to_json::<DkgRunOut>(Err(HostRunError::NoCallbacks)),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn dkls_sign_run(
+    data: *const c_char,
+    callbacks: *const DklsCallbacks,
+) -> *const c_char {
+    let run_in: SignRunIn = SignRunIn::from_json(data);
+    match unsafe { callbacks.as_ref() } {
+        Some(callbacks) => to_json(sign_run(run_in, callbacks)),
+        None => to_json::<Signature>(Err(HostRunError::NoCallbacks)),
+    }
+}
@@ -0,0 +1,97 @@
+use crate::dkls23::session::id::{
+    commit, finish, reveal, IdCommitment, IdKept, IdReveal, SessionIdContext,
+    SessionIdTranscript,
+};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Session id structs. The commitments and the reveals are broadcast to
+// all the parties of the context.
+#[derive(Deserialize, Serialize)]
+pub struct IdCommitIn {
+    pub context: SessionIdContext,
+    pub party_index: u8,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct IdCommitOut {
+    pub kept: IdKept,
+    pub commitment: IdCommitment,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct IdRevealIn {
+    pub kept: IdKept,
+    pub commitments: Vec<IdCommitment>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct IdRevealOut {
+    pub kept: IdKept,
+    pub reveal: IdReveal,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct IdFinishIn {
+    pub kept: IdKept,
+    pub reveals: Vec<IdReveal>,
+}
+
+// The transcript is passed with the session id to the first phase,
+// and our nonce with it as id_nonce.
+#[derive(Deserialize, Serialize)]
+pub struct IdFinishOut {
+    pub session_id: Vec<u8>,
+    pub transcript: SessionIdTranscript,
+    pub nonce: String,
+}
+
+impl CJson for IdCommitIn {}
+impl CJson for IdCommitOut {}
+impl CJson for IdRevealIn {}
+impl CJson for IdRevealOut {}
+impl CJson for IdFinishIn {}
+impl CJson for IdFinishOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_session_id_commit(
+    commit_json_in: *const c_char,
+) -> *const c_char {
+    let commit_in: IdCommitIn = IdCommitIn::from_json(commit_json_in);
+
+    match commit(commit_in.context, commit_in.party_index) {
+        Ok((kept, commitment)) => IdCommitOut { kept, commitment }.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_session_id_reveal(
+    reveal_json_in: *const c_char,
+) -> *const c_char {
+    let reveal_in: IdRevealIn = IdRevealIn::from_json(reveal_json_in);
+
+    match reveal(reveal_in.kept, reveal_in.commitments) {
+        Ok((kept, reveal)) => IdRevealOut { kept, reveal }.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_session_id_finish(
+    finish_json_in: *const c_char,
+) -> *const c_char {
+    let finish_in: IdFinishIn = IdFinishIn::from_json(finish_json_in);
+    let nonce = finish_in.kept.nonce.clone();
+
+    match finish(finish_in.kept, finish_in.reveals) {
+        Ok((session_id, transcript)) => IdFinishOut {
+            session_id,
+            transcript,
+            nonce,
+        }
+        .to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
+    }
+}
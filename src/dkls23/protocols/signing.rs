@@ -1,12 +1,25 @@
-use crate::dkls23::utilities::cjson::CJson;
+use crate::dkls23::protocols::echo::check_sign;
+use crate::dkls23::session::blame::disclosed;
+use crate::dkls23::session::echo::{Echo, EchoError};
+use crate::dkls23::session::error_to_json;
+use crate::dkls23::session::id::{
+    require, SessionIdError, SessionIdTranscript,
+};
+use crate::dkls23::session::seal::{
+    require_identity, Identity, IdentityKeys, SealError, SealedMessage,
+};
+use crate::dkls23::session::sign::{SignSession, SignState, SIGN_KIND};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut, SealedOut};
+use crate::dkls23::utilities::path::{validate_path, DerivationError};
 use dkls23::protocols::signing::{
     verify_ecdsa_signature, Broadcast3to4, KeepPhase1to2, KeepPhase2to3,
     SignData, TransmitPhase1to2, TransmitPhase2to3, UniqueKeep1to2,
     UniqueKeep2to3,
 };
-use dkls23::protocols::Party;
-use dkls23::utilities::hashes::HashOutput;
+use dkls23::protocols::{PartiesMessage, Party};
+use dkls23::utilities::hashes::{hash, HashOutput};
 use k256::AffinePoint;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::os::raw::c_char;
@@ -16,6 +29,20 @@ use std::os::raw::c_char;
 pub struct Phase1In {
     pub party: Party,
     pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    // Proof that the id was agreed on with dkls_session_id_*. Required
+    // with the strict-session-id feature.
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    // Our nonce from dkls_session_id_commit, which must be our reveal
+    // in the transcript.
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    // Our identity keys. With them, the transmit messages come out
+    // sealed and are only accepted sealed.
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -29,9 +56,22 @@ pub struct Phase1Out {
 pub struct Phase2In {
     pub party: Party,
     pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
     pub unique_kept: UniqueKeep1to2,
+    // The transcript of phase 1. Every phase checks it, since each can be
+    // called on its own.
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
     pub kept: BTreeMap<u8, KeepPhase1to2>,
+    #[serde(default)]
     pub received: Vec<TransmitPhase1to2>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+    #[serde(default)]
+    pub sealed: Vec<SealedMessage>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -45,9 +85,20 @@ pub struct Phase2Out {
 pub struct Phase3In {
     pub party: Party,
     pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
     pub unique_kept: UniqueKeep2to3,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
     pub kept: BTreeMap<u8, KeepPhase2to3>,
+    #[serde(default)]
     pub received: Vec<TransmitPhase2to3>,
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
+    #[serde(default)]
+    pub sealed: Vec<SealedMessage>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -60,9 +111,29 @@ pub struct Phase3Out {
 pub struct Phase4In {
     pub party: Party,
     pub sign_data: SignData,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
     pub x_coord: String,
+    // The broadcasts of every signer, ours included, either in the order
+    // of the indices or by sender. The echo needs them by sender.
+    #[serde(default)]
     pub received: Vec<Broadcast3to4>,
+    #[serde(default)]
+    pub broadcasts: BTreeMap<u8, Broadcast3to4>,
     pub normalize: bool,
+    #[serde(default)]
+    pub transcript: Option<SessionIdTranscript>,
+    #[serde(default)]
+    pub id_nonce: Option<String>,
+    // Whether phase 4 first checks, with the echoes of every other party
+    // from dkls_sign_echo, that everyone got the same broadcasts.
+    #[serde(default)]
+    pub echo: bool,
+    #[serde(default)]
+    pub echoes: Vec<Echo>,
+    // Signs our echo, and checks the others are signed.
+    #[serde(default)]
+    pub identity: Option<IdentityKeys>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -95,20 +166,172 @@ impl CJson for Phase4Out {}
 impl CJson for VerifyIn {}
 impl CJson for VerifyOut {}
 
+// Signing at a derivation path. Every party derives its share on its own
+// and the path is bound to the sign id, so if the signers do not agree on
+// the path, their messages do not match and the protocol aborts.
+pub(crate) fn at_path(
+    party: Party,
+    sign_data: SignData,
+    path: Option<&str>,
+) -> Result<(Party, SignData), DerivationError> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok((party, sign_data)),
+    };
+
+    // We bind the indices rather than the text, since "m/0/5" and
+    // "m/0/05" are the same key.
+    let indices = validate_path(path, party.derivation_data.depth, false)?;
+    let canonical: Vec<u8> = indices
+        .iter()
+        .flat_map(|index| index.to_be_bytes())
+        .collect();
+    let party = party.derive_from_path(path)?;
+    let sign_data = SignData {
+        sign_id: hash(&canonical, &sign_data.sign_id).to_vec(),
+        ..sign_data
+    };
+
+    Ok((party, sign_data))
+}
+
+// The transcript is for the sign id before the path is bound to it.
+fn check_id(
+    party: &Party,
+    sign_data: &SignData,
+    transcript: Option<&SessionIdTranscript>,
+    id_nonce: Option<&str>,
+) -> Result<(), SessionIdError> {
+    require(transcript, id_nonce, |transcript, nonce| {
+        transcript.check_sign(party, sign_data, nonce)
+    })
+}
+
+fn identity(
+    party: &Party,
+    keys: Option<&IdentityKeys>,
+    sealed: &[SealedMessage],
+) -> Result<Option<Identity>, SealError> {
+    let identity = require_identity(party.party_index, keys)?;
+    if identity.is_none() && !sealed.is_empty() {
+        return Err(SealError::Required);
+    }
+    Ok(identity)
+}
+
+// With an identity, the messages we receive come from the sealed ones.
+fn open<T: DeserializeOwned>(
+    identity: Option<&Identity>,
+    sign_data: &SignData,
+    phase: u8,
+    received: Vec<T>,
+    sealed: &[SealedMessage],
+    parties: impl Fn(&T) -> &PartiesMessage,
+) -> Result<Vec<T>, SealError> {
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return Ok(received),
+    };
+    if !received.is_empty() {
+        return Err(SealError::Required);
+    }
+    identity.open_transmits(
+        &sign_data.sign_id,
+        phase,
+        SIGN_KIND,
+        &sign_data.counterparties,
+        sealed,
+        parties,
+    )
+}
+
+// The broadcasts of phase 4 come in a list or by sender, not both.
+fn received(
+    received: Vec<Broadcast3to4>,
+    broadcasts: BTreeMap<u8, Broadcast3to4>,
+) -> Result<Vec<Broadcast3to4>, EchoError> {
+    match (received.is_empty(), broadcasts.is_empty()) {
+        (_, true) => Ok(received),
+        (true, false) => Ok(broadcasts.into_values().collect()),
+        (false, false) => Err(EchoError::InvalidBroadcasts {
+            description: String::from(
+                "The broadcasts must come in a list or by sender, not both!",
+            ),
+        }),
+    }
+}
+
+// With an identity, the transmit messages go out sealed instead.
+fn seal<T: Serialize>(
+    identity: Option<&Identity>,
+    sign_data: &SignData,
+    phase: u8,
+    transmit: &mut Vec<T>,
+    parties: impl Fn(&T) -> &PartiesMessage,
+) -> Result<Option<Vec<SealedMessage>>, SealError> {
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return Ok(None),
+    };
+    identity
+        .seal_transmits(
+            &sign_data.sign_id,
+            phase,
+            SIGN_KIND,
+            &std::mem::take(transmit),
+            parties,
+        )
+        .map(Some)
+}
+
 #[no_mangle]
 pub extern "C" fn dkls_sign_phase1(
     phase1_json_in: *const c_char,
 ) -> *const c_char {
     let phase1_in: Phase1In = Phase1In::from_json(phase1_json_in);
-    let (unique_keep, keep, transmit) =
-        phase1_in.party.sign_phase1(&phase1_in.sign_data);
+    let transcript = phase1_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase1_in.party,
+        &phase1_in.sign_data,
+        transcript,
+        phase1_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let identity =
+        match identity(&phase1_in.party, phase1_in.identity.as_ref(), &[]) {
+            Ok(identity) => identity,
+            Err(error) => return ErrorOut { error }.to_json(),
+        };
+    let (party, sign_data) = match at_path(
+        phase1_in.party,
+        phase1_in.sign_data,
+        phase1_in.derivation_path.as_deref(),
+    ) {
+        Ok(result) => result,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let mut session = SignSession::new(party, sign_data.clone(), false);
 
-    Phase1Out {
-        unique_keep,
-        keep,
-        transmit,
+    let mut phase1_out = match session.phase1() {
+        Ok(phase1_out) => phase1_out,
+        Err(error) => return error_to_json(error),
+    };
+    match seal(
+        identity.as_ref(),
+        &sign_data,
+        1,
+        &mut phase1_out.transmit,
+        |transmit| &transmit.parties,
+    ) {
+        Ok(Some(sealed)) => SealedOut {
+            out: phase1_out,
+            sealed,
+        }
+        .to_json(),
+        Ok(None) => phase1_out.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
     }
-    .to_json()
 }
 
 #[no_mangle]
@@ -116,22 +339,73 @@ pub extern "C" fn dkls_sign_phase2(
     phase2_json_in: *const c_char,
 ) -> *const c_char {
     let phase2_in: Phase2In = Phase2In::from_json(phase2_json_in);
-    match phase2_in.party.sign_phase2(
+    let transcript = phase2_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase2_in.party,
         &phase2_in.sign_data,
-        &phase2_in.unique_kept,
-        &phase2_in.kept,
-        &phase2_in.received,
+        transcript,
+        phase2_in.id_nonce.as_deref(),
     ) {
-        Ok((unique_keep, keep, transmit)) => Phase2Out {
-            unique_keep,
-            keep,
-            transmit,
-        }
-        .to_json(),
+        return ErrorOut { error }.to_json();
+    }
+    let identity = match identity(
+        &phase2_in.party,
+        phase2_in.identity.as_ref(),
+        &phase2_in.sealed,
+    ) {
+        Ok(identity) => identity,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let (party, sign_data) = match at_path(
+        phase2_in.party,
+        phase2_in.sign_data,
+        phase2_in.derivation_path.as_deref(),
+    ) {
+        Ok(result) => result,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let received = match open(
+        identity.as_ref(),
+        &sign_data,
+        1,
+        phase2_in.received,
+        &phase2_in.sealed,
+        |transmit| &transmit.parties,
+    ) {
+        Ok(received) => received,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let mut session = SignSession::with_state(
+        party,
+        sign_data.clone(),
+        false,
+        SignState::Phase1Done {
+            unique_kept: phase2_in.unique_kept,
+            kept: phase2_in.kept,
+        },
+    );
 
-        Err(abort) => {
-            panic!("Party {} aborted: {:?}", abort.index, abort.description);
+    let mut phase2_out = match session.phase2(&received) {
+        Ok(phase2_out) => phase2_out,
+        Err(error) => {
+            let sealed = &phase2_in.sealed;
+            return error_to_json(disclosed(error, identity.as_ref(), sealed));
+        }
+    };
+    match seal(
+        identity.as_ref(),
+        &sign_data,
+        2,
+        &mut phase2_out.transmit,
+        |transmit| &transmit.parties,
+    ) {
+        Ok(Some(sealed)) => SealedOut {
+            out: phase2_out,
+            sealed,
         }
+        .to_json(),
+        Ok(None) => phase2_out.to_json(),
+        Err(error) => ErrorOut { error }.to_json(),
     }
 }
 
@@ -140,16 +414,57 @@ pub extern "C" fn dkls_sign_phase3(
     phase3_json_in: *const c_char,
 ) -> *const c_char {
     let phase3_in: Phase3In = Phase3In::from_json(phase3_json_in);
-    match phase3_in.party.sign_phase3(
+    let transcript = phase3_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase3_in.party,
         &phase3_in.sign_data,
-        &phase3_in.unique_kept,
-        &phase3_in.kept,
-        &phase3_in.received,
+        transcript,
+        phase3_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let identity = match identity(
+        &phase3_in.party,
+        phase3_in.identity.as_ref(),
+        &phase3_in.sealed,
+    ) {
+        Ok(identity) => identity,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let (party, sign_data) = match at_path(
+        phase3_in.party,
+        phase3_in.sign_data,
+        phase3_in.derivation_path.as_deref(),
+    ) {
+        Ok(result) => result,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let received = match open(
+        identity.as_ref(),
+        &sign_data,
+        2,
+        phase3_in.received,
+        &phase3_in.sealed,
+        |transmit| &transmit.parties,
     ) {
-        Ok((x_coord, broadcast)) => Phase3Out { x_coord, broadcast }.to_json(),
+        Ok(received) => received,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let mut session = SignSession::with_state(
+        party,
+        sign_data,
+        false,
+        SignState::Phase2Done {
+            unique_kept: phase3_in.unique_kept,
+            kept: phase3_in.kept,
+        },
+    );
 
-        Err(abort) => {
-            panic!("Party {} aborted: {:?}", abort.index, abort.description);
+    match session.phase3(&received) {
+        Ok(phase3_out) => phase3_out.to_json(),
+        Err(error) => {
+            let sealed = &phase3_in.sealed;
+            error_to_json(disclosed(error, identity.as_ref(), sealed))
         }
     }
 }
@@ -159,17 +474,49 @@ pub extern "C" fn dkls_sign_phase4(
     phase4_json_in: *const c_char,
 ) -> *const c_char {
     let phase4_in: Phase4In = Phase4In::from_json(phase4_json_in);
-    match phase4_in.party.sign_phase4(
+    let transcript = phase4_in.transcript.as_ref();
+    if let Err(error) = check_id(
+        &phase4_in.party,
         &phase4_in.sign_data,
-        &phase4_in.x_coord,
-        &phase4_in.received,
-        phase4_in.normalize,
+        transcript,
+        phase4_in.id_nonce.as_deref(),
+    ) {
+        return ErrorOut { error }.to_json();
+    }
+    let (party, sign_data) = match at_path(
+        phase4_in.party,
+        phase4_in.sign_data,
+        phase4_in.derivation_path.as_deref(),
+    ) {
+        Ok(result) => result,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    if let Err(error) = check_sign(
+        party.party_index,
+        &sign_data,
+        &phase4_in.broadcasts,
+        phase4_in.identity.as_ref(),
+        phase4_in.echo,
+        &phase4_in.echoes,
     ) {
-        Ok((signature, rec_id)) => Phase4Out { signature, rec_id }.to_json(),
+        return ErrorOut { error }.to_json();
+    }
+    let received = match received(phase4_in.received, phase4_in.broadcasts) {
+        Ok(received) => received,
+        Err(error) => return ErrorOut { error }.to_json(),
+    };
+    let mut session = SignSession::with_state(
+        party,
+        sign_data,
+        phase4_in.normalize,
+        SignState::Phase3Done {
+            x_coord: phase4_in.x_coord,
+        },
+    );
 
-        Err(abort) => {
-            panic!("Party {} aborted: {:?}", abort.index, abort.description);
-        }
+    match session.phase4(&received) {
+        Ok(phase4_out) => phase4_out.to_json(),
+        Err(error) => error_to_json(error),
     }
 }
 
@@ -0,0 +1,90 @@
+use crate::dkls23::utilities::bip32::{ExtendedKeyVersion, ExtendedPublicKey};
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::dkls23::utilities::path::DerivationError;
+use dkls23::protocols::derivation::DerivData;
+use dkls23::protocols::Party;
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Extended public key structs
+#[derive(Deserialize, Serialize)]
+pub struct XpubIn {
+    pub data: DerivData,
+    #[serde(default)]
+    pub version: ExtendedKeyVersion,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct XpubOut {
+    pub xpub: String,
+}
+
+// Extended public key structs for parties
+#[derive(Deserialize, Serialize)]
+pub struct PartyXpubIn {
+    pub party: Party,
+    #[serde(default)]
+    pub version: ExtendedKeyVersion,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartyXpubOut {
+    pub xpub: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ParseXpubIn {
+    pub xpub: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ParseXpubOut {
+    pub version: ExtendedKeyVersion,
+    pub key: ExtendedPublicKey,
+}
+
+impl CJson for XpubIn {}
+impl CJson for XpubOut {}
+impl CJson for PartyXpubIn {}
+impl CJson for PartyXpubOut {}
+impl CJson for ParseXpubIn {}
+impl CJson for ParseXpubOut {}
+
+#[no_mangle]
+pub extern "C" fn dkls_xpub(xpub_json_in: *const c_char) -> *const c_char {
+    let xpub_in: XpubIn = XpubIn::from_json(xpub_json_in);
+    let xpub = ExtendedPublicKey::from(&xpub_in.data)
+        .encode(xpub_in.version.to_bytes());
+
+    XpubOut { xpub }.to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_party_xpub(
+    xpub_json_in: *const c_char,
+) -> *const c_char {
+    let xpub_in: PartyXpubIn = PartyXpubIn::from_json(xpub_json_in);
+    let xpub = ExtendedPublicKey::from(&xpub_in.party.derivation_data)
+        .encode(xpub_in.version.to_bytes());
+
+    PartyXpubOut { xpub }.to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn dkls_parse_xpub(
+    parse_json_in: *const c_char,
+) -> *const c_char {
+    let parse_in: ParseXpubIn = ParseXpubIn::from_json(parse_json_in);
+    match ExtendedPublicKey::decode(&parse_in.xpub) {
+        Err(error) => ErrorOut {
+            error: DerivationError::from(error),
+        }
+        .to_json(),
+
+        Ok((key, version)) => ParseXpubOut {
+            version: ExtendedKeyVersion::from_bytes(version),
+            key,
+        }
+        .to_json(),
+    }
+}
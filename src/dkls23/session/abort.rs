@@ -0,0 +1,89 @@
+use crate::dkls23::session::SessionError;
+use k256::ecdsa::signature::{Signer, Verifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+// A party that gives up on a session tells the others with an abort
+// message. It is signed with the identity key of the party, which the
+// others already know, so an abort cannot be forged by the transport.
+
+const DOMAIN: &[u8] = b"libtss/abort/v1";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AbortMessage {
+    pub session_id: Vec<u8>,
+    pub sender: u8,
+    // The phase the sender had completed.
+    pub phase: u8,
+    pub reason: String,
+    // ECDSA over secp256k1, r || s in hex.
+    pub signature: String,
+}
+
+fn signed_bytes(
+    session_id: &[u8],
+    sender: u8,
+    phase: u8,
+    reason: &str,
+) -> Vec<u8> {
+    let mut bytes = DOMAIN.to_vec();
+    bytes.extend_from_slice(&(session_id.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(session_id);
+    bytes.push(sender);
+    bytes.push(phase);
+    bytes.extend_from_slice(reason.as_bytes());
+    bytes
+}
+
+impl AbortMessage {
+    pub fn new(
+        key: &SigningKey,
+        session_id: &[u8],
+        sender: u8,
+        phase: u8,
+        reason: &str,
+    ) -> AbortMessage {
+        let signature: Signature =
+            key.sign(&signed_bytes(session_id, sender, phase, reason));
+        AbortMessage {
+            session_id: session_id.to_vec(),
+            sender,
+            phase,
+            reason: reason.to_string(),
+            signature: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    pub fn verify(&self, key: &VerifyingKey) -> bool {
+        let signature = match hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        {
+            Some(signature) => signature,
+            None => return false,
+        };
+        let bytes = signed_bytes(
+            &self.session_id,
+            self.sender,
+            self.phase,
+            &self.reason,
+        );
+        key.verify(&bytes, &signature).is_ok()
+    }
+}
+
+// Identity keys are given as hex: the 32-byte secret key, and the SEC1
+// encoded public key, compressed or not.
+pub fn signing_key(key: &str) -> Result<SigningKey, SessionError> {
+    hex::decode(key)
+        .ok()
+        .and_then(|bytes| SigningKey::from_slice(&bytes).ok())
+        .ok_or(SessionError::InvalidKey)
+}
+
+pub fn verifying_key(key: &str) -> Result<VerifyingKey, SessionError> {
+    hex::decode(key)
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_sec1_bytes(&bytes).ok())
+        .ok_or(SessionError::InvalidKey)
+}
@@ -0,0 +1,204 @@
+use crate::dkls23::session::abort::verifying_key;
+use crate::dkls23::session::seal::{open_disclosed, Identity, SealedMessage};
+use crate::dkls23::session::SessionError;
+use dkls23::protocols::Abort;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// DKLs23 aborts with our own index and a description of what failed, in
+// words. The report does not guess from the words: it names the step of
+// DKLs23 that failed, the accused party when only one counterparty could
+// have caused it, and keeps what every counterparty sent for the phase.
+// When the messages came sealed, each one keeps the sealed message it
+// came in and the key that opens it, so anyone with the identity keys
+// can check who sent it and run the checks again.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum FailedCheck {
+    // Phase 4 of the DKG: the proofs, commitments, zero shares,
+    // multiplication and chain codes of the other parties.
+    KeyGeneration,
+    // Phases 2 and 3 of signing: the commitments, the multiplication
+    // and the consistency of the values of the other parties.
+    Signing,
+    // Phase 4 of signing: the final signature.
+    Signature,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Evidence {
+    pub sender: u8,
+    // The round the message was sent in and which message of the round
+    // it is, as sealed messages name them.
+    pub round: u8,
+    pub kind: String,
+    pub message: Value,
+    // The sealed message it came in, and its key in hex. Broadcasts and
+    // messages of unsealed phases have none.
+    #[serde(default)]
+    pub sealed: Option<SealedMessage>,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl Evidence {
+    pub fn new<T: Serialize>(
+        sender: u8,
+        round: u8,
+        kind: &str,
+        message: &T,
+    ) -> Evidence {
+        Evidence {
+            sender,
+            round,
+            kind: kind.to_string(),
+            message: serde_json::to_value(message).unwrap(),
+            sealed: None,
+            key: None,
+        }
+    }
+
+    // Whether the sealed message is from the sender, to the reporter, in
+    // the session, and opens to the message.
+    fn is_bound(
+        &self,
+        session_id: &[u8],
+        reporter: u8,
+        peers: &BTreeMap<u8, String>,
+    ) -> bool {
+        let (sealed, key) = match (&self.sealed, &self.key) {
+            (Some(sealed), Some(key)) => (sealed, key),
+            _ => return false,
+        };
+        let sender_key = match peers.get(&self.sender) {
+            Some(public_key) => match verifying_key(public_key) {
+                Ok(sender_key) => sender_key,
+                Err(_) => return false,
+            },
+            None => return false,
+        };
+        sealed.session_id == session_id
+            && sealed.sender == self.sender
+            && sealed.receiver == reporter
+            && sealed.phase == self.round
+            && sealed.kind == self.kind
+            && open_disclosed(sealed, &sender_key, key)
+                .ok()
+                .and_then(|plaintext| {
+                    serde_json::from_slice::<Value>(&plaintext).ok()
+                })
+                .is_some_and(|message| message == self.message)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlameReport {
+    // The DKG session id, or the sign id when signing.
+    pub session_id: Vec<u8>,
+    // The party whose check failed, that is us.
+    pub reporter: u8,
+    // Our only counterparty, or None when there are several and DKLs23
+    // does not say which one it was.
+    pub accused: Option<u8>,
+    // The phase that was running.
+    pub phase: u8,
+    pub check: FailedCheck,
+    // The description of DKLs23, as it is.
+    pub description: String,
+    // SHA-256, in hex, of the messages of the evidence.
+    pub message_hash: Option<String>,
+    pub evidence: Vec<Evidence>,
+}
+
+fn hash_evidence(evidence: &[Evidence]) -> String {
+    let messages: Vec<(u8, u8, &str, &Value)> = evidence
+        .iter()
+        .map(|item| {
+            (item.sender, item.round, item.kind.as_str(), &item.message)
+        })
+        .collect();
+    let bytes = serde_json::to_vec(&messages).unwrap();
+    hex::encode(Sha256::digest(bytes))
+}
+
+impl BlameReport {
+    pub fn new(
+        session_id: &[u8],
+        phase: u8,
+        check: FailedCheck,
+        abort: Abort,
+        counterparties: &[u8],
+        evidence: Vec<Evidence>,
+    ) -> BlameReport {
+        let accused = match counterparties {
+            [accused] => Some(*accused),
+            _ => None,
+        };
+        BlameReport {
+            session_id: session_id.to_vec(),
+            reporter: abort.index,
+            accused,
+            phase,
+            check,
+            description: abort.description,
+            message_hash: (!evidence.is_empty())
+                .then(|| hash_evidence(&evidence)),
+            evidence,
+        }
+    }
+
+    // Adds to each message of the evidence the sealed message it came
+    // in, with its key.
+    pub fn disclose(&mut self, identity: &Identity, sealed: &[SealedMessage]) {
+        for item in self.evidence.iter_mut() {
+            let found = sealed.iter().find(|message| {
+                message.sender == item.sender
+                    && message.phase == item.round
+                    && message.kind == item.kind
+                    && identity
+                        .open::<Value>(
+                            &self.session_id,
+                            item.round,
+                            &item.kind,
+                            message,
+                        )
+                        .is_ok_and(|opened| opened == item.message)
+            });
+            if let Some(message) = found {
+                item.key = identity.disclose(message).ok();
+                item.sealed = item.key.as_ref().map(|_| message.clone());
+            }
+        }
+    }
+
+    // Whether the evidence is the one the hash was taken over, and every
+    // message of it was sealed by its sender for the reporter. The peers
+    // are the public identity keys, in hex, by party index. Without them
+    // the reporter could have made up the messages, so a report without
+    // sealed evidence does not verify.
+    pub fn verify_evidence(&self, peers: &BTreeMap<u8, String>) -> bool {
+        !self.evidence.is_empty()
+            && self.message_hash.as_deref()
+                == Some(hash_evidence(&self.evidence).as_str())
+            && self.evidence.iter().all(|item| {
+                item.is_bound(&self.session_id, self.reporter, peers)
+            })
+    }
+}
+
+// Discloses the sealed messages behind the evidence of an abort.
+pub(crate) fn disclosed(
+    error: SessionError,
+    identity: Option<&Identity>,
+    sealed: &[SealedMessage],
+) -> SessionError {
+    match (error, identity) {
+        (SessionError::Aborted { mut blame }, Some(identity)) => {
+            blame.disclose(identity, sealed);
+            SessionError::Aborted { blame }
+        }
+        (error, _) => error,
+    }
+}
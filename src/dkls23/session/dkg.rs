@@ -0,0 +1,720 @@
+use crate::dkls23::protocols::dkg::{Phase1Out, Phase2Out, Phase3Out};
+use crate::dkls23::session::abort::AbortMessage;
+use crate::dkls23::session::blame::{BlameReport, Evidence, FailedCheck};
+use crate::dkls23::session::{
+    now_millis, Closure, Inbox, Lifetime, Message, Payload, SessionError,
+    Timeouts,
+};
+use dkls23::protocols::dkg::{
+    phase1, phase2, phase3, phase4, BroadcastDerivationPhase2to4,
+    BroadcastDerivationPhase3to4, KeepInitMulPhase3to4,
+    KeepInitZeroSharePhase2to3, KeepInitZeroSharePhase3to4, ProofCommitment,
+    SessionData, TransmitInitMulPhase3to4, TransmitInitZeroSharePhase2to4,
+    TransmitInitZeroSharePhase3to4, UniqueKeepDerivationPhase2to3,
+};
+use dkls23::protocols::Party;
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use k256::elliptic_curve::zeroize::Zeroize;
+use k256::Scalar;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Number of communication rounds of the DKG.
+pub const DKG_ROUNDS: u8 = 3;
+
+// What sealed DKG messages are called.
+pub const DKG_KIND: &str = "dkg";
+
+// What a party sends to each counterparty after phases 1, 2 and 3.
+#[derive(Clone, Deserialize, Serialize)]
+pub enum DkgPayload {
+    Round1 {
+        fragment: Scalar,
+    },
+    Round2 {
+        proof_commitment: ProofCommitment,
+        zero_transmit: TransmitInitZeroSharePhase2to4,
+        bip_broadcast: BroadcastDerivationPhase2to4,
+    },
+    Round3 {
+        zero_transmit: TransmitInitZeroSharePhase3to4,
+        mul_transmit: TransmitInitMulPhase3to4,
+        bip_broadcast: BroadcastDerivationPhase3to4,
+    },
+}
+
+pub type DkgMessage = Message<DkgPayload>;
+
+impl Payload for DkgPayload {
+    fn round(&self) -> u8 {
+        match self {
+            DkgPayload::Round1 { .. } => 1,
+            DkgPayload::Round2 { .. } => 2,
+            DkgPayload::Round3 { .. } => 3,
+        }
+    }
+
+    fn is_addressed(&self, sender: u8, receiver: u8) -> bool {
+        match self {
+            DkgPayload::Round1 { .. } => true,
+            DkgPayload::Round2 {
+                proof_commitment,
+                zero_transmit,
+                bip_broadcast,
+            } => {
+                proof_commitment.index == sender
+                    && zero_transmit.parties.sender == sender
+                    && zero_transmit.parties.receiver == receiver
+                    && bip_broadcast.sender_index == sender
+            }
+            DkgPayload::Round3 {
+                zero_transmit,
+                mul_transmit,
+                bip_broadcast,
+            } => {
+                zero_transmit.parties.sender == sender
+                    && zero_transmit.parties.receiver == receiver
+                    && mul_transmit.parties.sender == sender
+                    && mul_transmit.parties.receiver == receiver
+                    && bip_broadcast.sender_index == sender
+            }
+        }
+    }
+
+    fn zeroize(&mut self) {
+        if let DkgPayload::Round1 { fragment } = self {
+            fragment.zeroize();
+        }
+    }
+}
+
+// What a party keeps from one phase to the next.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Deserialize, Serialize)]
+pub enum DkgState {
+    Created,
+    Phase1Done {
+        fragment: Scalar,
+    },
+    Phase2Done {
+        zero_kept: BTreeMap<u8, KeepInitZeroSharePhase2to3>,
+        bip_kept: UniqueKeepDerivationPhase2to3,
+    },
+    Phase3Done {
+        zero_kept: BTreeMap<u8, KeepInitZeroSharePhase3to4>,
+        mul_kept: BTreeMap<u8, KeepInitMulPhase3to4>,
+    },
+    Finished {
+        party: Party,
+    },
+    // Expired or aborted after the given phase. Nothing is kept.
+    Closed {
+        phase: u8,
+        closure: Closure,
+    },
+}
+
+impl DkgState {
+    pub fn phase(&self) -> u8 {
+        match self {
+            DkgState::Created => 0,
+            DkgState::Phase1Done { .. } => 1,
+            DkgState::Phase2Done { .. } => 2,
+            DkgState::Phase3Done { .. } => 3,
+            DkgState::Finished { .. } => 4,
+            DkgState::Closed { phase, .. } => *phase,
+        }
+    }
+
+    // Overwrites the fragment, seeds and nonces kept between phases.
+    pub fn zeroize(&mut self) {
+        match self {
+            DkgState::Phase1Done { fragment } => fragment.zeroize(),
+            DkgState::Phase2Done {
+                zero_kept,
+                bip_kept,
+            } => {
+                for keep in zero_kept.values_mut() {
+                    keep.seed.zeroize();
+                    keep.salt.zeroize();
+                }
+                bip_kept.aux_chain_code.zeroize();
+                bip_kept.cc_salt.zeroize();
+            }
+            DkgState::Phase3Done {
+                zero_kept,
+                mul_kept,
+            } => {
+                for keep in zero_kept.values_mut() {
+                    keep.seed.zeroize();
+                }
+                for keep in mul_kept.values_mut() {
+                    keep.ot_sender.s.zeroize();
+                    keep.nonce.zeroize();
+                    keep.ot_receiver.seed.zeroize();
+                    keep.correlation.zeroize();
+                    keep.vec_r.zeroize();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Our own results that phase 4 needs again, next to the messages of the
+// other parties.
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct Own {
+    poly_point: Option<Scalar>,
+    proof_commitment: Option<ProofCommitment>,
+    bip_broadcast_2to4: Option<BroadcastDerivationPhase2to4>,
+    bip_broadcast_3to4: Option<BroadcastDerivationPhase3to4>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct DkgSession {
+    data: SessionData,
+    state: DkgState,
+    own: Own,
+    inbox: Inbox<DkgPayload>,
+    lifetime: Lifetime,
+}
+
+impl DkgSession {
+    pub fn new(data: SessionData) -> DkgSession {
+        DkgSession::with_state(data, DkgState::Created)
+    }
+
+    // Used by the FFI, where the caller keeps the state between phases.
+    pub(crate) fn with_state(data: SessionData, state: DkgState) -> DkgSession {
+        DkgSession {
+            data,
+            state,
+            own: Own::default(),
+            inbox: Inbox::default(),
+            lifetime: Lifetime::default(),
+        }
+    }
+
+    // Every phase from now on must complete within its timeout.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> DkgSession {
+        self.lifetime = Lifetime::new(timeouts);
+        self.lifetime.start(self.phase());
+        self
+    }
+
+    pub fn data(&self) -> &SessionData {
+        &self.data
+    }
+
+    pub fn state(&self) -> &DkgState {
+        &self.state
+    }
+
+    pub fn phase(&self) -> u8 {
+        self.state.phase()
+    }
+
+    // Everyone takes part in the DKG.
+    pub fn counterparties(&self) -> BTreeSet<u8> {
+        (1..=self.data.parameters.share_count)
+            .filter(|&index| index != self.data.party_index)
+            .collect()
+    }
+
+    // When the phase we are waiting for must complete.
+    pub fn deadline(&self) -> Option<u64> {
+        self.lifetime.deadline()
+    }
+
+    pub fn closure(&self) -> Option<&Closure> {
+        match &self.state {
+            DkgState::Closed { closure, .. } => Some(closure),
+            _ => None,
+        }
+    }
+
+    // Closes the session if its deadline has passed. Called before
+    // every step, but hosts can also call it from a timer.
+    pub fn check_open(&mut self) -> Result<(), SessionError> {
+        if let Some(deadline) = self.lifetime.expired(now_millis()) {
+            let phase = self.phase();
+            self.close(Closure::Expired { phase, deadline });
+        }
+        match self.closure() {
+            Some(closure) => Err(SessionError::Closed {
+                closure: closure.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Overwrites the secrets kept so far and drops everything else.
+    fn close(&mut self, closure: Closure) {
+        if self.closure().is_some() {
+            return;
+        }
+        let phase = self.phase();
+        self.state.zeroize();
+        if let Some(poly_point) = &mut self.own.poly_point {
+            poly_point.zeroize();
+        }
+        self.state = DkgState::Closed { phase, closure };
+        self.own = Own::default();
+        self.inbox.wipe();
+        self.lifetime.stop();
+    }
+
+    // Gives up on the session. The message should be sent to every
+    // counterparty.
+    pub fn abort(&mut self, key: &SigningKey, reason: &str) -> AbortMessage {
+        let own = self.data.party_index;
+        let message = AbortMessage::new(
+            key,
+            &self.data.session_id,
+            own,
+            self.phase(),
+            reason,
+        );
+        self.close(Closure::Aborted {
+            index: own,
+            reason: reason.to_string(),
+        });
+        message
+    }
+
+    // Closes the session when a counterparty aborted it. The key is the
+    // identity key of the sender.
+    pub fn receive_abort(
+        &mut self,
+        message: &AbortMessage,
+        key: &VerifyingKey,
+    ) -> Result<(), SessionError> {
+        if message.session_id != self.data.session_id
+            || !self.counterparties().contains(&message.sender)
+            || !message.verify(key)
+        {
+            return Err(SessionError::InvalidAbort {
+                sender: message.sender,
+            });
+        }
+        self.close(Closure::Aborted {
+            index: message.sender,
+            reason: message.reason.clone(),
+        });
+        Ok(())
+    }
+
+    fn wrong_phase(&self, required: u8) -> SessionError {
+        SessionError::WrongPhase {
+            current: self.phase(),
+            required,
+        }
+    }
+
+    pub fn receive(&mut self, message: DkgMessage) -> Result<(), SessionError> {
+        self.check_open()?;
+        let counterparties = self.counterparties();
+        self.inbox.insert(
+            self.data.party_index,
+            &counterparties,
+            self.phase(),
+            DKG_ROUNDS,
+            message,
+        )
+    }
+
+    // Whether advance() can run the next phase.
+    pub fn is_ready(&self) -> bool {
+        if self.closure().is_some() {
+            return false;
+        }
+        let phase = self.phase();
+        phase == 0
+            || (phase <= DKG_ROUNDS
+                && self.inbox.missing(phase, &self.counterparties()).is_empty())
+    }
+
+    // Runs phase 1.
+    pub fn start(&mut self) -> Result<Vec<DkgMessage>, SessionError> {
+        let own = self.data.party_index;
+        let Phase1Out { fragments } = self.phase1()?;
+
+        let messages = self
+            .counterparties()
+            .into_iter()
+            .map(|receiver| DkgMessage {
+                sender: own,
+                receiver,
+                payload: DkgPayload::Round1 {
+                    fragment: fragments[(receiver - 1) as usize],
+                },
+            })
+            .collect();
+        Ok(messages)
+    }
+
+    // Runs the next phase with the messages of the last round.
+    pub fn advance(&mut self) -> Result<Vec<DkgMessage>, SessionError> {
+        self.check_open()?;
+        match self.phase() {
+            0 => self.start(),
+            1 => self.advance_phase2(),
+            2 => self.advance_phase3(),
+            3 => {
+                self.advance_phase4()?;
+                Ok(Vec::new())
+            }
+            _ => Err(self.wrong_phase(DKG_ROUNDS)),
+        }
+    }
+
+    pub fn finish(self) -> Result<Party, SessionError> {
+        match self.state {
+            DkgState::Finished { party } => Ok(party),
+            DkgState::Closed { closure, .. } => {
+                Err(SessionError::Closed { closure })
+            }
+            state => Err(SessionError::WrongPhase {
+                current: state.phase(),
+                required: 4,
+            }),
+        }
+    }
+
+    fn advance_phase2(&mut self) -> Result<Vec<DkgMessage>, SessionError> {
+        let own_fragment = match &self.state {
+            DkgState::Phase1Done { fragment } => *fragment,
+            _ => return Err(self.wrong_phase(1)),
+        };
+        let received = self.inbox.complete(1, &self.counterparties())?;
+
+        let own = self.data.party_index;
+        let poly_fragments: Vec<Scalar> =
+            (1..=self.data.parameters.share_count)
+                .map(|index| match received.get(&index) {
+                    Some(DkgPayload::Round1 { fragment }) => *fragment,
+                    _ => own_fragment,
+                })
+                .collect();
+
+        let Phase2Out {
+            proof_commitment,
+            zero_transmit,
+            bip_broadcast,
+            ..
+        } = self.phase2(&poly_fragments)?;
+
+        let messages = zero_transmit
+            .into_iter()
+            .map(|zero_transmit| DkgMessage {
+                sender: own,
+                receiver: zero_transmit.parties.receiver,
+                payload: DkgPayload::Round2 {
+                    proof_commitment: proof_commitment.clone(),
+                    zero_transmit,
+                    bip_broadcast: bip_broadcast.clone(),
+                },
+            })
+            .collect();
+        Ok(messages)
+    }
+
+    fn advance_phase3(&mut self) -> Result<Vec<DkgMessage>, SessionError> {
+        // Phase 3 does not use the messages of round 2, but every party
+        // must have committed before we go on.
+        self.inbox.complete(2, &self.counterparties())?;
+
+        let Phase3Out {
+            zero_transmit,
+            mul_transmit,
+            bip_broadcast,
+            ..
+        } = self.phase3()?;
+
+        let own = self.data.party_index;
+        let mut messages = Vec::with_capacity(zero_transmit.len());
+        for zero_transmit in zero_transmit {
+            let receiver = zero_transmit.parties.receiver;
+            let mul_transmit = mul_transmit
+                .iter()
+                .find(|message| message.parties.receiver == receiver)
+                .ok_or(SessionError::Inconsistent {
+                    description: String::from("Missing multiplication data!"),
+                })?
+                .clone();
+            messages.push(DkgMessage {
+                sender: own,
+                receiver,
+                payload: DkgPayload::Round3 {
+                    zero_transmit,
+                    mul_transmit,
+                    bip_broadcast: bip_broadcast.clone(),
+                },
+            });
+        }
+        Ok(messages)
+    }
+
+    fn advance_phase4(&mut self) -> Result<(), SessionError> {
+        let counterparties = self.counterparties();
+        let round2 = self.inbox.complete(2, &counterparties)?;
+        let round3 = self.inbox.complete(3, &counterparties)?;
+
+        let own = self.data.party_index;
+        let missing = || SessionError::Inconsistent {
+            description: String::from("Missing own data of phase 2 or 3!"),
+        };
+        let poly_point = self.own.poly_point.ok_or_else(missing)?;
+
+        let mut proofs_commitments = BTreeMap::new();
+        let mut zero_received_phase2 = Vec::new();
+        let mut bip_broadcast_2to4 = BTreeMap::new();
+        proofs_commitments.insert(
+            own,
+            self.own.proof_commitment.clone().ok_or_else(missing)?,
+        );
+        bip_broadcast_2to4.insert(
+            own,
+            self.own.bip_broadcast_2to4.clone().ok_or_else(missing)?,
+        );
+        for (sender, payload) in round2 {
+            if let DkgPayload::Round2 {
+                proof_commitment,
+                zero_transmit,
+                bip_broadcast,
+            } = payload
+            {
+                proofs_commitments.insert(sender, proof_commitment);
+                zero_received_phase2.push(zero_transmit);
+                bip_broadcast_2to4.insert(sender, bip_broadcast);
+            }
+        }
+
+        let mut zero_received_phase3 = Vec::new();
+        let mut mul_received = Vec::new();
+        let mut bip_broadcast_3to4 = BTreeMap::new();
+        bip_broadcast_3to4.insert(
+            own,
+            self.own.bip_broadcast_3to4.clone().ok_or_else(missing)?,
+        );
+        for (sender, payload) in round3 {
+            if let DkgPayload::Round3 {
+                zero_transmit,
+                mul_transmit,
+                bip_broadcast,
+            } = payload
+            {
+                zero_received_phase3.push(zero_transmit);
+                mul_received.push(mul_transmit);
+                bip_broadcast_3to4.insert(sender, bip_broadcast);
+            }
+        }
+
+        let proofs_commitments: Vec<ProofCommitment> =
+            proofs_commitments.into_values().collect();
+        self.phase4(
+            &poly_point,
+            &proofs_commitments,
+            &zero_received_phase2,
+            &zero_received_phase3,
+            &mul_received,
+            &bip_broadcast_2to4,
+            &bip_broadcast_3to4,
+        )?;
+        Ok(())
+    }
+
+    // The phases themselves. They take what the other parties sent as
+    // DKLs23 expects it and return our output as the FFI does.
+    pub(crate) fn phase1(&mut self) -> Result<Phase1Out, SessionError> {
+        self.check_open()?;
+        if self.phase() != 0 {
+            return Err(self.wrong_phase(0));
+        }
+
+        let fragments = phase1(&self.data);
+
+        self.state = DkgState::Phase1Done {
+            fragment: fragments[(self.data.party_index - 1) as usize],
+        };
+        self.lifetime.start(1);
+        Ok(Phase1Out { fragments })
+    }
+
+    pub(crate) fn phase2(
+        &mut self,
+        poly_fragments: &[Scalar],
+    ) -> Result<Phase2Out, SessionError> {
+        self.check_open()?;
+        let own_fragment = match &self.state {
+            DkgState::Phase1Done { fragment } => *fragment,
+            _ => return Err(self.wrong_phase(1)),
+        };
+        let own = self.data.party_index;
+        if poly_fragments.len() != self.data.parameters.share_count as usize
+            || poly_fragments[(own - 1) as usize] != own_fragment
+        {
+            return Err(SessionError::Inconsistent {
+                description: String::from("Invalid polynomial fragments!"),
+            });
+        }
+
+        let (
+            poly_point,
+            proof_commitment,
+            zero_keep,
+            zero_transmit,
+            bip_keep,
+            bip_broadcast,
+        ) = phase2(&self.data, poly_fragments);
+
+        self.own.poly_point = Some(poly_point);
+        self.own.proof_commitment = Some(proof_commitment.clone());
+        self.own.bip_broadcast_2to4 = Some(bip_broadcast.clone());
+        self.state = DkgState::Phase2Done {
+            zero_kept: zero_keep.clone(),
+            bip_kept: bip_keep.clone(),
+        };
+        self.lifetime.start(2);
+        Ok(Phase2Out {
+            poly_point,
+            proof_commitment,
+            zero_keep,
+            zero_transmit,
+            bip_keep,
+            bip_broadcast,
+        })
+    }
+
+    pub(crate) fn phase3(&mut self) -> Result<Phase3Out, SessionError> {
+        self.check_open()?;
+        let (zero_kept, bip_kept) = match &self.state {
+            DkgState::Phase2Done {
+                zero_kept,
+                bip_kept,
+            } => (zero_kept, bip_kept),
+            _ => return Err(self.wrong_phase(2)),
+        };
+
+        let (zero_keep, zero_transmit, mul_keep, mul_transmit, bip_broadcast) =
+            phase3(&self.data, zero_kept, bip_kept);
+
+        self.own.bip_broadcast_3to4 = Some(bip_broadcast.clone());
+        self.state = DkgState::Phase3Done {
+            zero_kept: zero_keep.clone(),
+            mul_kept: mul_keep.clone(),
+        };
+        self.lifetime.start(3);
+        Ok(Phase3Out {
+            zero_keep,
+            zero_transmit,
+            mul_keep,
+            mul_transmit,
+            bip_broadcast,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn phase4(
+        &mut self,
+        poly_point: &Scalar,
+        proofs_commitments: &[ProofCommitment],
+        zero_received_phase2: &[TransmitInitZeroSharePhase2to4],
+        zero_received_phase3: &[TransmitInitZeroSharePhase3to4],
+        mul_received: &[TransmitInitMulPhase3to4],
+        bip_broadcast_2to4: &BTreeMap<u8, BroadcastDerivationPhase2to4>,
+        bip_broadcast_3to4: &BTreeMap<u8, BroadcastDerivationPhase3to4>,
+    ) -> Result<Party, SessionError> {
+        self.check_open()?;
+        let (zero_kept, mul_kept) = match &self.state {
+            DkgState::Phase3Done {
+                zero_kept,
+                mul_kept,
+            } => (zero_kept, mul_kept),
+            _ => return Err(self.wrong_phase(3)),
+        };
+
+        let party = phase4(
+            &self.data,
+            poly_point,
+            proofs_commitments,
+            zero_kept,
+            zero_received_phase2,
+            zero_received_phase3,
+            mul_kept,
+            mul_received,
+            bip_broadcast_2to4,
+            bip_broadcast_3to4,
+        )
+        .map_err(|abort| {
+            let own = self.data.party_index;
+            let mut evidence: Vec<Evidence> = Vec::new();
+            for proof in proofs_commitments {
+                if proof.index != own {
+                    evidence.push(Evidence::new(
+                        proof.index,
+                        2,
+                        "proof_commitment",
+                        proof,
+                    ));
+                }
+            }
+            for (&sender, broadcast) in bip_broadcast_2to4 {
+                if sender != own {
+                    evidence.push(Evidence::new(
+                        sender,
+                        2,
+                        "bip_broadcast",
+                        broadcast,
+                    ));
+                }
+            }
+            for (&sender, broadcast) in bip_broadcast_3to4 {
+                if sender != own {
+                    evidence.push(Evidence::new(
+                        sender,
+                        3,
+                        "bip_broadcast",
+                        broadcast,
+                    ));
+                }
+            }
+            let transmits = zero_received_phase2
+                .iter()
+                .map(|message| (2, "zero", json!(message), &message.parties))
+                .chain(zero_received_phase3.iter().map(|message| {
+                    (3, "zero", json!(message), &message.parties)
+                }))
+                .chain(mul_received.iter().map(|message| {
+                    (3, "mul", json!(message), &message.parties)
+                }));
+            for (round, kind, message, parties) in transmits {
+                evidence.push(Evidence::new(
+                    parties.sender,
+                    round,
+                    kind,
+                    &message,
+                ));
+            }
+            let counterparties: Vec<u8> =
+                self.counterparties().into_iter().collect();
+            let blame = BlameReport::new(
+                &self.data.session_id,
+                4,
+                FailedCheck::KeyGeneration,
+                abort,
+                &counterparties,
+                evidence,
+            );
+            SessionError::Aborted { blame }
+        })?;
+
+        self.state = DkgState::Finished {
+            party: party.clone(),
+        };
+        self.lifetime.stop();
+        Ok(party)
+    }
+}
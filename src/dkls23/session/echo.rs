@@ -0,0 +1,275 @@
+use crate::dkls23::session::seal::{Identity, SealError};
+use dkls23::protocols::dkg::{
+    BroadcastDerivationPhase2to4, BroadcastDerivationPhase3to4, ProofCommitment,
+};
+use dkls23::protocols::signing::Broadcast3to4;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Echo broadcast, for the broadcasts that phase 4 assumes everyone got
+// the same. Before phase 4, every party sends the others a digest of the
+// broadcast it received from each party. If two parties got different
+// broadcasts from the same sender, their digests differ and the sender,
+// or whoever relayed its broadcast, is named as equivocating.
+//
+// With identity keys the echoes are signed, so that a relay cannot
+// forge them either.
+
+const DOMAIN: &[u8] = b"libtss/echo/v1";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EchoError {
+    // An echo from a party that is not in the session, or a second one.
+    UnknownParty {
+        sender: u8,
+    },
+    Duplicate {
+        sender: u8,
+    },
+    Missing {
+        parties: Vec<u8>,
+    },
+    // An echo for another session or phase.
+    WrongSession {
+        sender: u8,
+    },
+    WrongPhase {
+        sender: u8,
+        phase: u8,
+    },
+    // An echo without digests for exactly the parties of the session.
+    Malformed {
+        sender: u8,
+        description: String,
+    },
+    // Our own broadcasts do not match the parties of the session.
+    InvalidBroadcasts {
+        description: String,
+    },
+    // A missing or invalid signature, or an identity key that cannot be
+    // used.
+    Signature(SealError),
+    // Parties got different broadcasts from `party`. The digests are
+    // those each party echoed, ours included.
+    Equivocation {
+        party: u8,
+        phase: u8,
+        digests: BTreeMap<u8, String>,
+    },
+}
+
+impl From<SealError> for EchoError {
+    fn from(error: SealError) -> Self {
+        EchoError::Signature(error)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Echo {
+    pub session_id: Vec<u8>,
+    pub sender: u8,
+    pub phase: u8,
+    // SHA-256 in hex of the broadcast from each party, by party index.
+    pub digests: BTreeMap<u8, String>,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl Echo {
+    fn bytes(&self) -> Vec<u8> {
+        let mut bytes = DOMAIN.to_vec();
+        bytes.extend_from_slice(&(self.session_id.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.session_id);
+        bytes.push(self.sender);
+        bytes.push(self.phase);
+        for (party, digest) in &self.digests {
+            bytes.push(*party);
+            bytes.extend_from_slice(digest.as_bytes());
+        }
+        bytes
+    }
+}
+
+fn digest(session_id: &[u8], phase: u8, party: u8, broadcast: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update((session_id.len() as u32).to_be_bytes());
+    hasher.update(session_id);
+    hasher.update([phase, party]);
+    hasher.update(broadcast);
+    hex::encode(hasher.finalize())
+}
+
+// Our echo of the broadcasts we received, given as bytes by sender.
+pub fn echo(
+    session_id: &[u8],
+    sender: u8,
+    phase: u8,
+    broadcasts: &BTreeMap<u8, Vec<u8>>,
+    identity: Option<&Identity>,
+) -> Echo {
+    let digests = broadcasts
+        .iter()
+        .map(|(&party, broadcast)| {
+            (party, digest(session_id, phase, party, broadcast))
+        })
+        .collect();
+    let mut echo = Echo {
+        session_id: session_id.to_vec(),
+        sender,
+        phase,
+        digests,
+        signature: None,
+    };
+    echo.signature = identity.map(|identity| identity.sign(&echo.bytes()));
+    echo
+}
+
+// Checks the echoes of `others` against ours. With an identity, every
+// echo must be signed by its sender.
+pub fn check(
+    own: &Echo,
+    others: &[u8],
+    echoes: &[Echo],
+    identity: Option<&Identity>,
+) -> Result<(), EchoError> {
+    let mut by_sender = BTreeMap::new();
+    for echo in echoes {
+        let sender = echo.sender;
+        if !others.contains(&sender) {
+            return Err(EchoError::UnknownParty { sender });
+        }
+        if echo.session_id != own.session_id {
+            return Err(EchoError::WrongSession { sender });
+        }
+        if echo.phase != own.phase {
+            return Err(EchoError::WrongPhase {
+                sender,
+                phase: echo.phase,
+            });
+        }
+        if let Some(identity) = identity {
+            let signature = echo
+                .signature
+                .as_deref()
+                .ok_or(SealError::InvalidSignature { sender })?;
+            identity.verify(sender, &echo.bytes(), signature)?;
+        }
+        if !echo.digests.keys().eq(own.digests.keys()) {
+            return Err(EchoError::Malformed {
+                sender,
+                description: String::from("The echo is for other parties!"),
+            });
+        }
+        if by_sender.insert(sender, echo).is_some() {
+            return Err(EchoError::Duplicate { sender });
+        }
+    }
+    let missing: Vec<u8> = others
+        .iter()
+        .filter(|sender| !by_sender.contains_key(sender))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(EchoError::Missing { parties: missing });
+    }
+
+    for (&party, own_digest) in &own.digests {
+        if by_sender
+            .values()
+            .any(|echo| &echo.digests[&party] != own_digest)
+        {
+            let digests = by_sender
+                .values()
+                .copied()
+                .chain([own])
+                .map(|echo| (echo.sender, echo.digests[&party].clone()))
+                .collect();
+            return Err(EchoError::Equivocation {
+                party,
+                phase: own.phase,
+                digests,
+            });
+        }
+    }
+    Ok(())
+}
+
+// The broadcasts that DKG phase 4 takes, by sender: the proof commitment
+// and both derivation broadcasts of each party.
+pub fn dkg_broadcasts(
+    share_count: u8,
+    proofs_commitments: &[ProofCommitment],
+    bip_broadcast_2to4: &BTreeMap<u8, BroadcastDerivationPhase2to4>,
+    bip_broadcast_3to4: &BTreeMap<u8, BroadcastDerivationPhase3to4>,
+) -> Result<BTreeMap<u8, Vec<u8>>, EchoError> {
+    // The proofs are taken by the index they carry, not by position.
+    let mut proofs = BTreeMap::new();
+    for proof in proofs_commitments {
+        if proof.index == 0 || proof.index > share_count {
+            return Err(EchoError::InvalidBroadcasts {
+                description: format!(
+                    "A proof is for party {}, which is not in the session!",
+                    proof.index
+                ),
+            });
+        }
+        if proofs.insert(proof.index, proof).is_some() {
+            return Err(EchoError::InvalidBroadcasts {
+                description: format!(
+                    "There are two proofs for party {}!",
+                    proof.index
+                ),
+            });
+        }
+    }
+    (1..=share_count)
+        .map(|party| {
+            let (proof, broadcast_2, broadcast_3) = match (
+                proofs.get(&party),
+                bip_broadcast_2to4.get(&party),
+                bip_broadcast_3to4.get(&party),
+            ) {
+                (Some(proof), Some(broadcast_2), Some(broadcast_3)) => {
+                    (proof, broadcast_2, broadcast_3)
+                }
+                _ => {
+                    return Err(EchoError::InvalidBroadcasts {
+                        description: format!(
+                            "The broadcasts of party {} are missing!",
+                            party
+                        ),
+                    })
+                }
+            };
+            let bytes =
+                serde_json::to_vec(&(proof, broadcast_2, broadcast_3)).unwrap();
+            Ok((party, bytes))
+        })
+        .collect()
+}
+
+// The broadcasts that signing phase 4 takes, by sender, ours included.
+// DKLs23 does not say who sent a broadcast, so they must come keyed by
+// sender rather than in a list.
+pub fn sign_broadcasts(
+    parties: &[u8],
+    received: &BTreeMap<u8, Broadcast3to4>,
+) -> Result<BTreeMap<u8, Vec<u8>>, EchoError> {
+    let mut parties = parties.to_vec();
+    parties.sort_unstable();
+    if !received.keys().eq(parties.iter()) {
+        return Err(EchoError::InvalidBroadcasts {
+            description: String::from(
+                "There must be a broadcast per party, by sender!",
+            ),
+        });
+    }
+    Ok(received
+        .iter()
+        .map(|(&party, broadcast)| {
+            (party, serde_json::to_vec(broadcast).unwrap())
+        })
+        .collect())
+}
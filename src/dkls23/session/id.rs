@@ -0,0 +1,342 @@
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::SignData;
+use dkls23::protocols::{Parameters, Party};
+use dkls23::utilities::rng;
+use k256::elliptic_curve::rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Session ids agreed on by commit-then-reveal. Every party commits to a
+// random nonce, and only reveals it once it has the commitments of all
+// the others, so no party can choose its nonce after seeing theirs. The
+// id is the hash of all the nonces together with the context: what the
+// session is for, the parameters and the parties taking part.
+//
+// The transcript of the run is the proof that an id was made this way.
+// Anyone can recompute the id from it, so the DKG and signing exports
+// can check it, and with the strict-session-id feature they require it.
+// A transcript alone only shows that it is consistent: an old one still
+// is, and one party can make a whole transcript by itself. So the check
+// also takes the nonce we generated in this run, which must be our
+// reveal in the transcript.
+
+const DOMAIN: &[u8] = b"libtss/session-id/v1";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SessionIdError {
+    // A party that is not in the context, or the same party twice.
+    UnknownParty { sender: u8 },
+    Duplicate { sender: u8 },
+    // Commitments or reveals that are still missing.
+    Missing { parties: Vec<u8> },
+    // The nonce that a party revealed is not the one it committed to.
+    WrongReveal { sender: u8 },
+    // The context has no such party or is not valid for the session.
+    InvalidContext { description: String },
+    // The id of the session is not the one of the transcript.
+    Mismatch,
+    // The transcript does not hold the nonce we revealed in this run, or
+    // that nonce was not given. It may be replayed from an older run.
+    NotOurs,
+    // The session was started without a transcript.
+    Required,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Purpose {
+    Dkg,
+    Sign,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SessionIdContext {
+    pub purpose: Purpose,
+    pub threshold: u8,
+    pub share_count: u8,
+    // Every party taking part, including us.
+    pub parties: Vec<u8>,
+}
+
+impl SessionIdContext {
+    // The parties are sorted so that all of them hash the same bytes.
+    fn bytes(&self) -> Vec<u8> {
+        let mut parties = self.parties.clone();
+        parties.sort_unstable();
+        let mut bytes = DOMAIN.to_vec();
+        bytes.push(match self.purpose {
+            Purpose::Dkg => 0,
+            Purpose::Sign => 1,
+        });
+        bytes.push(self.threshold);
+        bytes.push(self.share_count);
+        bytes.push(parties.len() as u8);
+        bytes.extend_from_slice(&parties);
+        bytes
+    }
+
+    fn check(&self, sender: u8) -> Result<(), SessionIdError> {
+        if !self.parties.contains(&sender) {
+            return Err(SessionIdError::UnknownParty { sender });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IdCommitment {
+    pub sender: u8,
+    // SHA-256 in hex.
+    pub commitment: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IdReveal {
+    pub sender: u8,
+    // 32 bytes in hex.
+    pub nonce: String,
+}
+
+// What a party keeps between the steps. The nonce must not be sent
+// before the commitments of all the others have arrived.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IdKept {
+    pub context: SessionIdContext,
+    pub party_index: u8,
+    pub nonce: String,
+    #[serde(default)]
+    pub commitments: Vec<IdCommitment>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SessionIdTranscript {
+    pub context: SessionIdContext,
+    pub commitments: Vec<IdCommitment>,
+    pub reveals: Vec<IdReveal>,
+}
+
+fn commitment(context: &[u8], sender: u8, nonce: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(context);
+    hasher.update(b"commit");
+    hasher.update([sender]);
+    hasher.update(nonce);
+    hex::encode(hasher.finalize())
+}
+
+// One entry per party of the context, in index order.
+fn by_sender<T: Clone>(
+    context: &SessionIdContext,
+    entries: &[T],
+    sender: impl Fn(&T) -> u8,
+) -> Result<BTreeMap<u8, T>, SessionIdError> {
+    let mut by_sender = BTreeMap::new();
+    for entry in entries {
+        let sender = sender(entry);
+        context.check(sender)?;
+        if by_sender.insert(sender, entry.clone()).is_some() {
+            return Err(SessionIdError::Duplicate { sender });
+        }
+    }
+    let missing: Vec<u8> = context
+        .parties
+        .iter()
+        .filter(|party| !by_sender.contains_key(party))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(SessionIdError::Missing { parties: missing });
+    }
+    Ok(by_sender)
+}
+
+pub fn commit(
+    context: SessionIdContext,
+    party_index: u8,
+) -> Result<(IdKept, IdCommitment), SessionIdError> {
+    if context.parties.len() < 2 {
+        return Err(SessionIdError::InvalidContext {
+            description: String::from("There must be at least two parties!"),
+        });
+    }
+    context.check(party_index)?;
+
+    let mut nonce = [0u8; 32];
+    rng::get_rng().fill_bytes(&mut nonce);
+    let id_commitment = IdCommitment {
+        sender: party_index,
+        commitment: commitment(&context.bytes(), party_index, &nonce),
+    };
+    let kept = IdKept {
+        context,
+        party_index,
+        nonce: hex::encode(nonce),
+        commitments: Vec::new(),
+    };
+    Ok((kept, id_commitment))
+}
+
+// The commitments are those of all the parties, ours included. Ours
+// must be the one we made, or someone could have swapped it for theirs.
+pub fn reveal(
+    mut kept: IdKept,
+    commitments: Vec<IdCommitment>,
+) -> Result<(IdKept, IdReveal), SessionIdError> {
+    let committed = by_sender(&kept.context, &commitments, |c| c.sender)?;
+    let sender = kept.party_index;
+    let nonce = match hex::decode(&kept.nonce) {
+        Ok(nonce) if nonce.len() == 32 => nonce,
+        _ => return Err(SessionIdError::WrongReveal { sender }),
+    };
+    if commitment(&kept.context.bytes(), sender, &nonce)
+        != committed[&sender].commitment
+    {
+        return Err(SessionIdError::WrongReveal { sender });
+    }
+    let id_reveal = IdReveal {
+        sender: kept.party_index,
+        nonce: kept.nonce.clone(),
+    };
+    kept.commitments = commitments;
+    Ok((kept, id_reveal))
+}
+
+pub fn finish(
+    kept: IdKept,
+    reveals: Vec<IdReveal>,
+) -> Result<(Vec<u8>, SessionIdTranscript), SessionIdError> {
+    // Our own nonce must be the one we kept.
+    let sender = kept.party_index;
+    if reveals
+        .iter()
+        .any(|reveal| reveal.sender == sender && reveal.nonce != kept.nonce)
+    {
+        return Err(SessionIdError::WrongReveal { sender });
+    }
+    let transcript = SessionIdTranscript {
+        context: kept.context,
+        commitments: kept.commitments,
+        reveals,
+    };
+    let session_id = transcript.session_id()?;
+    Ok((session_id, transcript))
+}
+
+impl SessionIdTranscript {
+    // The id the transcript stands for, once every reveal is checked
+    // against its commitment.
+    pub fn session_id(&self) -> Result<Vec<u8>, SessionIdError> {
+        let context = self.context.bytes();
+        let commitments =
+            by_sender(&self.context, &self.commitments, |c| c.sender)?;
+        let reveals = by_sender(&self.context, &self.reveals, |r| r.sender)?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&context);
+        hasher.update(b"id");
+        for (sender, reveal) in reveals {
+            let nonce = match hex::decode(&reveal.nonce) {
+                Ok(nonce) if nonce.len() == 32 => nonce,
+                _ => return Err(SessionIdError::WrongReveal { sender }),
+            };
+            if commitment(&context, sender, &nonce)
+                != commitments[&sender].commitment
+            {
+                return Err(SessionIdError::WrongReveal { sender });
+            }
+            hasher.update([sender]);
+            hasher.update(&nonce);
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+
+    fn check(
+        &self,
+        purpose: Purpose,
+        parameters: &Parameters,
+        party_index: u8,
+        parties: &[u8],
+        session_id: &[u8],
+        nonce: &str,
+    ) -> Result<(), SessionIdError> {
+        let mut parties = parties.to_vec();
+        parties.sort_unstable();
+        let mut context_parties = self.context.parties.clone();
+        context_parties.sort_unstable();
+        if self.context.purpose != purpose
+            || self.context.threshold != parameters.threshold
+            || self.context.share_count != parameters.share_count
+            || context_parties != parties
+        {
+            return Err(SessionIdError::InvalidContext {
+                description: String::from(
+                    "The transcript is for another session!",
+                ),
+            });
+        }
+        if self.session_id()? != session_id {
+            return Err(SessionIdError::Mismatch);
+        }
+        let ours = self.reveals.iter().any(|reveal| {
+            reveal.sender == party_index
+                && reveal.nonce.eq_ignore_ascii_case(nonce)
+        });
+        if !ours {
+            return Err(SessionIdError::NotOurs);
+        }
+        Ok(())
+    }
+
+    // A DKG is run by all the parties.
+    pub fn check_dkg(
+        &self,
+        session: &SessionData,
+        nonce: &str,
+    ) -> Result<(), SessionIdError> {
+        let parties: Vec<u8> = (1..=session.parameters.share_count).collect();
+        self.check(
+            Purpose::Dkg,
+            &session.parameters,
+            session.party_index,
+            &parties,
+            &session.session_id,
+            nonce,
+        )
+    }
+
+    pub fn check_sign(
+        &self,
+        party: &Party,
+        sign_data: &SignData,
+        nonce: &str,
+    ) -> Result<(), SessionIdError> {
+        let mut parties = sign_data.counterparties.clone();
+        parties.push(party.party_index);
+        self.check(
+            Purpose::Sign,
+            &party.parameters,
+            party.party_index,
+            &parties,
+            &sign_data.sign_id,
+            nonce,
+        )
+    }
+}
+
+// Without the strict-session-id feature, a transcript is only checked
+// when there is one. The nonce is the one kept from this run.
+pub fn require(
+    transcript: Option<&SessionIdTranscript>,
+    nonce: Option<&str>,
+    check: impl FnOnce(&SessionIdTranscript, &str) -> Result<(), SessionIdError>,
+) -> Result<(), SessionIdError> {
+    match transcript {
+        Some(transcript) => {
+            check(transcript, nonce.ok_or(SessionIdError::NotOurs)?)
+        }
+        None if cfg!(feature = "strict-session-id") => {
+            Err(SessionIdError::Required)
+        }
+        None => Ok(()),
+    }
+}
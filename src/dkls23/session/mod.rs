@@ -0,0 +1,229 @@
+pub mod abort;
+pub mod blame;
+pub mod dkg;
+pub mod echo;
+pub mod id;
+pub mod pair;
+pub mod seal;
+pub mod sign;
+pub mod store;
+
+use crate::dkls23::session::blame::BlameReport;
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::os::raw::c_char;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Sessions run one party of a protocol. They keep the state between the
+// phases, collect the messages of the other parties and only run a phase
+// once everything it needs has arrived.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SessionError {
+    // The call needs the session to be at another phase.
+    WrongPhase { current: u8, required: u8 },
+    // Some messages of the round are still missing.
+    NotReady { round: u8, missing: Vec<u8> },
+    // A message that is not from a counterparty or not for us.
+    Misaddressed { sender: u8, receiver: u8 },
+    // A second message from the same party in the same round.
+    Duplicate { sender: u8, round: u8 },
+    // A second message from the same party in the same round that is
+    // not the same as the first.
+    Equivocation { sender: u8, round: u8 },
+    // A message for a round we are not expecting.
+    UnexpectedRound { sender: u8, round: u8 },
+    // The messages do not agree with each other or with our state.
+    Inconsistent { description: String },
+    // A check of DKLs23 failed, with who is to blame for it.
+    Aborted { blame: BlameReport },
+    // The session expired or was aborted and takes no more calls.
+    Closed { closure: Closure },
+    // An abort message that is not from a counterparty of this session
+    // or whose signature does not verify.
+    InvalidAbort { sender: u8 },
+    // An identity key that cannot be parsed.
+    InvalidKey,
+}
+
+// Why a session was closed.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Closure {
+    // The phase after `phase` did not complete before the deadline, in
+    // milliseconds since the Unix epoch.
+    Expired { phase: u8, deadline: u64 },
+    // The party with the given index sent an abort message. It is our
+    // own index when we aborted.
+    Aborted { index: u8, reason: String },
+}
+
+// How long each phase may take, in milliseconds, counted from the end
+// of the phase before. The last value is used for the phases after it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Timeouts {
+    pub phases: Vec<u64>,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// The deadline of the phase a session is waiting for.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub(crate) struct Lifetime {
+    timeouts: Option<Timeouts>,
+    deadline: Option<u64>,
+}
+
+impl Lifetime {
+    pub(crate) fn new(timeouts: Timeouts) -> Lifetime {
+        Lifetime {
+            timeouts: Some(timeouts),
+            deadline: None,
+        }
+    }
+
+    pub(crate) fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    // Starts the clock for the phase after `phase`.
+    pub(crate) fn start(&mut self, phase: u8) {
+        self.deadline = self.timeouts.as_ref().and_then(|timeouts| {
+            let timeout = timeouts
+                .phases
+                .get(phase as usize)
+                .or(timeouts.phases.last())?;
+            Some(now_millis().saturating_add(*timeout))
+        });
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.deadline = None;
+    }
+
+    pub(crate) fn expired(&self, now: u64) -> Option<u64> {
+        self.deadline.filter(|&deadline| now > deadline)
+    }
+}
+
+// What the FFI returns when a step fails. Aborts come with their blame
+// report instead of ending the process.
+pub(crate) fn error_to_json(error: SessionError) -> *const c_char {
+    ErrorOut { error }.to_json()
+}
+
+// Every message is sent from one party to another. Broadcasts are sent
+// as one message per receiver.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Message<P> {
+    pub sender: u8,
+    pub receiver: u8,
+    pub payload: P,
+}
+
+pub trait Payload {
+    fn round(&self) -> u8;
+
+    // Checks the indices inside the payload against the envelope.
+    fn is_addressed(&self, sender: u8, receiver: u8) -> bool;
+
+    // Overwrites the secret values of the payload.
+    fn zeroize(&mut self) {}
+}
+
+// Messages received so far, by round and by sender. Nothing is removed
+// until the session ends, so a replayed message is always caught.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Inbox<P> {
+    rounds: BTreeMap<u8, BTreeMap<u8, P>>,
+}
+
+impl<P> Default for Inbox<P> {
+    fn default() -> Inbox<P> {
+        Inbox {
+            rounds: BTreeMap::new(),
+        }
+    }
+}
+
+impl<P: Payload + Clone + Serialize> Inbox<P> {
+    // Counterparties can be at most one phase ahead of us, so we
+    // accept messages up to the round after the current phase.
+    pub fn insert(
+        &mut self,
+        party_index: u8,
+        counterparties: &BTreeSet<u8>,
+        phase: u8,
+        last_round: u8,
+        message: Message<P>,
+    ) -> Result<(), SessionError> {
+        let Message {
+            sender,
+            receiver,
+            payload,
+        } = message;
+
+        if receiver != party_index
+            || !counterparties.contains(&sender)
+            || !payload.is_addressed(sender, receiver)
+        {
+            return Err(SessionError::Misaddressed { sender, receiver });
+        }
+
+        let round = payload.round();
+        if round == 0 || round > phase + 1 || round > last_round {
+            return Err(SessionError::UnexpectedRound { sender, round });
+        }
+
+        // The same message again is harmless, e.g. after a restart, but
+        // another one means the sender says different things to us.
+        let messages = self.rounds.entry(round).or_default();
+        if let Some(first) = messages.get(&sender) {
+            if serde_json::to_vec(first).ok()
+                == serde_json::to_vec(&payload).ok()
+            {
+                return Err(SessionError::Duplicate { sender, round });
+            }
+            return Err(SessionError::Equivocation { sender, round });
+        }
+        messages.insert(sender, payload);
+
+        Ok(())
+    }
+
+    pub fn missing(&self, round: u8, counterparties: &BTreeSet<u8>) -> Vec<u8> {
+        let messages = self.rounds.get(&round);
+        counterparties
+            .iter()
+            .filter(|sender| {
+                !messages.is_some_and(|messages| messages.contains_key(sender))
+            })
+            .copied()
+            .collect()
+    }
+
+    // All messages of a round, ordered by sender, once they have arrived.
+    pub fn complete(
+        &self,
+        round: u8,
+        counterparties: &BTreeSet<u8>,
+    ) -> Result<BTreeMap<u8, P>, SessionError> {
+        let missing = self.missing(round, counterparties);
+        if !missing.is_empty() {
+            return Err(SessionError::NotReady { round, missing });
+        }
+        Ok(self.rounds.get(&round).cloned().unwrap_or_default())
+    }
+
+    pub fn wipe(&mut self) {
+        for payload in self.rounds.values_mut().flat_map(|m| m.values_mut()) {
+            payload.zeroize();
+        }
+        self.rounds.clear();
+    }
+}
@@ -0,0 +1,203 @@
+use crate::dkls23::session::abort::{signing_key, verifying_key};
+use dkls23::utilities::rng;
+use k256::ecdsa::VerifyingKey;
+use k256::elliptic_curve::rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Pairing of two devices, so that they can trust the identity keys of
+// each other before they run a DKG. The keys travel over a channel that
+// may be controlled by an attacker, so both devices derive a short
+// authentication string from the keys and random nonces, and the users
+// compare it. Each device commits to its nonce before it sees the nonce
+// of the other, so a device in the middle cannot choose its nonces to
+// make both codes match, except by chance.
+//
+// Once the users confirm the codes match, the peer is pinned. Identity
+// keys given with pinned peers only accept those peers.
+
+const DOMAIN: &[u8] = b"libtss/pair/v1";
+
+const EMOJI: [&str; 64] = [
+    "🐶", "🐱", "🦁", "🐎", "🦄", "🐷", "🐘", "🐰", "🐼", "🐓", "🐧", "🐢",
+    "🐟", "🐙", "🦋", "🌷", "🌳", "🌵", "🍄", "🌏", "🌙", "☁️", "🔥", "🍌",
+    "🍎", "🍓", "🌽", "🍕", "🎂", "❤️", "😀", "🤖", "🎩", "👓", "🔧", "🎅",
+    "👍", "☂️", "⌛", "⏰", "🎁", "💡", "📕", "✏️", "📎", "✂️", "🔒", "🔑",
+    "🔨", "☎️", "🏁", "🚂", "🚲", "✈️", "🚀", "🏆", "⚽", "🎸", "🎺", "🔔",
+    "⚓", "🎧", "📁", "📌",
+];
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum PairingError {
+    // An identity key that cannot be parsed.
+    InvalidKey,
+    // The peer sent our own key back.
+    SameKey,
+    // A step without the message of the step before, or a kept state
+    // that was not made by commit.
+    OutOfOrder,
+    // The nonce that the peer revealed is not the one it committed to.
+    WrongReveal,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PairCommitment {
+    // The compressed SEC1 public key in hex.
+    pub public_key: String,
+    // SHA-256 in hex.
+    pub commitment: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PairReveal {
+    // 32 bytes in hex.
+    pub nonce: String,
+}
+
+// What a device keeps between the steps. The nonce must not be sent
+// before the commitment of the peer has arrived.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PairKept {
+    pub public_key: String,
+    pub nonce: String,
+    #[serde(default)]
+    pub peer: Option<PairCommitment>,
+}
+
+// The code for the users to compare, as six digits or seven emoji.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Sas {
+    pub digits: String,
+    pub emoji: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PinnedPeer {
+    // The compressed SEC1 public key in hex.
+    pub public_key: String,
+    // SHA-256 of the public key in hex, to show to the users later.
+    pub fingerprint: String,
+}
+
+impl PinnedPeer {
+    pub fn new(public_key: &VerifyingKey) -> PinnedPeer {
+        let bytes = public_key.to_encoded_point(true);
+        let mut hasher = Sha256::new();
+        hasher.update(DOMAIN);
+        hasher.update(b"fingerprint");
+        hasher.update(bytes.as_bytes());
+        PinnedPeer {
+            public_key: hex::encode(bytes.as_bytes()),
+            fingerprint: hex::encode(hasher.finalize()),
+        }
+    }
+
+    pub fn matches(&self, public_key: &VerifyingKey) -> bool {
+        verifying_key(&self.public_key)
+            .map(|pinned| &pinned == public_key)
+            .unwrap_or(false)
+    }
+}
+
+// Keys are compared and hashed in their compressed encoding, however
+// they were given.
+fn public_key(key: &str) -> Result<Vec<u8>, PairingError> {
+    let public_key =
+        verifying_key(key).map_err(|_| PairingError::InvalidKey)?;
+    Ok(public_key.to_encoded_point(true).as_bytes().to_vec())
+}
+
+fn commitment(public_key: &[u8], nonce: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(b"commit");
+    hasher.update(public_key);
+    hasher.update(nonce);
+    hex::encode(hasher.finalize())
+}
+
+// Both devices hash the same bytes, so the keys are sorted.
+fn sas(ours: (&[u8], &[u8]), theirs: (&[u8], &[u8])) -> Sas {
+    let (first, second) = if ours.0 < theirs.0 {
+        (ours, theirs)
+    } else {
+        (theirs, ours)
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(b"sas");
+    for (public_key, nonce) in [first, second] {
+        hasher.update(public_key);
+        hasher.update(nonce);
+    }
+    let hash = hasher.finalize();
+
+    let number = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+    let bits = hash[4..10]
+        .iter()
+        .fold(0u64, |bits, &byte| (bits << 8) | byte as u64);
+    let emoji = (0..7)
+        .map(|i| EMOJI[((bits >> (42 - 6 * i)) & 63) as usize].to_string())
+        .collect();
+    Sas {
+        digits: format!("{:06}", number % 1_000_000),
+        emoji,
+    }
+}
+
+// The key is our identity secret key in hex.
+pub fn commit(key: &str) -> Result<(PairKept, PairCommitment), PairingError> {
+    let key = signing_key(key).map_err(|_| PairingError::InvalidKey)?;
+    let public_key = key.verifying_key().to_encoded_point(true);
+
+    let mut nonce = [0u8; 32];
+    rng::get_rng().fill_bytes(&mut nonce);
+    let pair_commitment = PairCommitment {
+        public_key: hex::encode(public_key.as_bytes()),
+        commitment: commitment(public_key.as_bytes(), &nonce),
+    };
+    let kept = PairKept {
+        public_key: pair_commitment.public_key.clone(),
+        nonce: hex::encode(nonce),
+        peer: None,
+    };
+    Ok((kept, pair_commitment))
+}
+
+pub fn reveal(
+    mut kept: PairKept,
+    peer: PairCommitment,
+) -> Result<(PairKept, PairReveal), PairingError> {
+    if public_key(&peer.public_key)? == public_key(&kept.public_key)? {
+        return Err(PairingError::SameKey);
+    }
+    let pair_reveal = PairReveal {
+        nonce: kept.nonce.clone(),
+    };
+    kept.peer = Some(peer);
+    Ok((kept, pair_reveal))
+}
+
+// The peer is only to be pinned once the users confirm the codes match.
+pub fn finish(
+    kept: PairKept,
+    reveal: PairReveal,
+) -> Result<(Sas, PinnedPeer), PairingError> {
+    let peer = kept.peer.as_ref().ok_or(PairingError::OutOfOrder)?;
+    let peer_key = public_key(&peer.public_key)?;
+    let peer_nonce = match hex::decode(&reveal.nonce) {
+        Ok(nonce) if nonce.len() == 32 => nonce,
+        _ => return Err(PairingError::WrongReveal),
+    };
+    if commitment(&peer_key, &peer_nonce) != peer.commitment {
+        return Err(PairingError::WrongReveal);
+    }
+
+    let own_key = public_key(&kept.public_key)?;
+    let own_nonce =
+        hex::decode(&kept.nonce).map_err(|_| PairingError::OutOfOrder)?;
+    let sas = sas((&own_key, &own_nonce), (&peer_key, &peer_nonce));
+    let peer_key = verifying_key(&peer.public_key)
+        .map_err(|_| PairingError::InvalidKey)?;
+    Ok((sas, PinnedPeer::new(&peer_key)))
+}
@@ -0,0 +1,521 @@
+use crate::dkls23::session::abort::{signing_key, verifying_key};
+use crate::dkls23::session::pair::PinnedPeer;
+use crate::dkls23::session::{Message, Payload as SessionPayload};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use dkls23::protocols::PartiesMessage;
+use k256::ecdsa::signature::{Signer, Verifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{NonZeroScalar, ProjectivePoint, PublicKey};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Sealed messages, for the point-to-point messages that carry secrets.
+// Every party has a long-term identity key pair and knows the public
+// keys of the others. A message is encrypted with ChaCha20-Poly1305
+// under a key from ECDH between a fresh ephemeral key and the identity
+// key of the receiver, and signed with the identity key of the sender.
+// The session id, sender, receiver, phase and kind of the message are
+// bound to both, so a sealed message cannot be replayed into another
+// session, phase or slot.
+
+const DOMAIN: &[u8] = b"libtss/seal/v1";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SealError {
+    // An identity key that cannot be parsed.
+    InvalidKey,
+    // A party whose public key we do not have, or have not pinned.
+    UnknownPeer { index: u8 },
+    Unpinned { index: u8 },
+    // A message for another party, session, phase or kind.
+    Misaddressed { sender: u8, receiver: u8 },
+    WrongSession { sender: u8 },
+    WrongPhase { sender: u8, phase: u8 },
+    // The signature of the sender does not verify.
+    InvalidSignature { sender: u8 },
+    // The message was changed after it was sealed.
+    Decryption { sender: u8 },
+    Malformed { sender: u8, description: String },
+    // A second message from the same party, or a missing one.
+    Duplicate { sender: u8 },
+    Missing { parties: Vec<u8> },
+    // Plain messages where sealed ones are required, or no identity.
+    Required,
+}
+
+// The keys in hex: our 32-byte secret key and the SEC1 public keys of
+// the other parties, as for abort messages. With peers pinned from
+// pairing, by party index, every other party must be the one pinned at
+// its index. The sealed-only feature requires them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IdentityKeys {
+    pub key: String,
+    pub peers: BTreeMap<u8, String>,
+    #[serde(default)]
+    pub pinned: BTreeMap<u8, PinnedPeer>,
+}
+
+#[derive(Clone)]
+pub struct Identity {
+    index: u8,
+    key: SigningKey,
+    peers: BTreeMap<u8, VerifyingKey>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SealedMessage {
+    pub session_id: Vec<u8>,
+    pub sender: u8,
+    pub receiver: u8,
+    pub phase: u8,
+    // Which message of the phase this is.
+    pub kind: String,
+    // In hex: the compressed ephemeral public key, the AEAD nonce, the
+    // ciphertext and the ECDSA signature, r || s.
+    pub ephemeral: String,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub signature: String,
+}
+
+fn header(
+    session_id: &[u8],
+    sender: u8,
+    receiver: u8,
+    phase: u8,
+    kind: &str,
+    ephemeral: &[u8],
+) -> Vec<u8> {
+    let mut bytes = DOMAIN.to_vec();
+    bytes.extend_from_slice(&(session_id.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(session_id);
+    bytes.push(sender);
+    bytes.push(receiver);
+    bytes.push(phase);
+    bytes.extend_from_slice(&(kind.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(kind.as_bytes());
+    bytes.extend_from_slice(ephemeral);
+    bytes
+}
+
+fn aead_key(ephemeral: &[u8], receiver: &[u8], shared: &[u8]) -> Key {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(ephemeral);
+    hasher.update(receiver);
+    hasher.update(shared);
+    hasher.finalize()
+}
+
+// The x coordinate of secret * point.
+fn ecdh(secret: &NonZeroScalar, point: &PublicKey) -> Vec<u8> {
+    let shared = (point.to_projective() * **secret).to_affine();
+    shared.to_encoded_point(true).as_bytes()[1..].to_vec()
+}
+
+fn public_bytes(key: &VerifyingKey) -> Vec<u8> {
+    key.to_encoded_point(true).as_bytes().to_vec()
+}
+
+fn malformed(sender: u8, description: &str) -> SealError {
+    SealError::Malformed {
+        sender,
+        description: description.to_string(),
+    }
+}
+
+// A sealed message whose signature was checked, ready to decrypt.
+struct Unpacked {
+    sender: u8,
+    header: Vec<u8>,
+    ephemeral: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Unpacked {
+    fn new(
+        sealed: &SealedMessage,
+        sender_key: &VerifyingKey,
+    ) -> Result<Unpacked, SealError> {
+        let sender = sealed.sender;
+        let decode = |field: &str, value: &str| {
+            hex::decode(value).map_err(|_| malformed(sender, field))
+        };
+        let ephemeral = decode("ephemeral", &sealed.ephemeral)?;
+        let nonce = decode("nonce", &sealed.nonce)?;
+        let ciphertext = decode("ciphertext", &sealed.ciphertext)?;
+        let signature = decode("signature", &sealed.signature)?;
+        if nonce.len() != 12 {
+            return Err(malformed(sender, "nonce"));
+        }
+
+        let header = header(
+            &sealed.session_id,
+            sender,
+            sealed.receiver,
+            sealed.phase,
+            &sealed.kind,
+            &ephemeral,
+        );
+        let mut signed = header.clone();
+        signed.extend_from_slice(&nonce);
+        signed.extend_from_slice(&ciphertext);
+        let signature = Signature::from_slice(&signature)
+            .map_err(|_| SealError::InvalidSignature { sender })?;
+        sender_key
+            .verify(&signed, &signature)
+            .map_err(|_| SealError::InvalidSignature { sender })?;
+
+        Ok(Unpacked {
+            sender,
+            header,
+            ephemeral,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    fn decrypt(&self, key: &Key) -> Result<Vec<u8>, SealError> {
+        ChaCha20Poly1305::new(key)
+            .decrypt(
+                Nonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &self.header,
+                },
+            )
+            .map_err(|_| SealError::Decryption {
+                sender: self.sender,
+            })
+    }
+}
+
+// Opens a message with the key its receiver disclosed, once the
+// signature of its sender is checked. Gives the plaintext.
+pub fn open_disclosed(
+    sealed: &SealedMessage,
+    sender_key: &VerifyingKey,
+    key: &str,
+) -> Result<Vec<u8>, SealError> {
+    let unpacked = Unpacked::new(sealed, sender_key)?;
+    let key = hex::decode(key)
+        .ok()
+        .filter(|key| key.len() == 32)
+        .ok_or(SealError::InvalidKey)?;
+    unpacked.decrypt(Key::from_slice(&key))
+}
+
+impl Identity {
+    pub fn new(index: u8, keys: &IdentityKeys) -> Result<Identity, SealError> {
+        let key = signing_key(&keys.key).map_err(|_| SealError::InvalidKey)?;
+        let peers = keys
+            .peers
+            .iter()
+            .map(|(&peer, public_key)| {
+                verifying_key(public_key)
+                    .map(|public_key| (peer, public_key))
+                    .map_err(|_| SealError::InvalidKey)
+            })
+            .collect::<Result<BTreeMap<u8, VerifyingKey>, _>>()?;
+        if !keys.pinned.is_empty() || cfg!(feature = "sealed-only") {
+            if let Some((&index, _)) = peers.iter().find(|(index, key)| {
+                !keys
+                    .pinned
+                    .get(index)
+                    .is_some_and(|pinned| pinned.matches(key))
+            }) {
+                return Err(SealError::Unpinned { index });
+            }
+        }
+        Ok(Identity { index, key, peers })
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    fn peer(&self, index: u8) -> Result<&VerifyingKey, SealError> {
+        self.peers
+            .get(&index)
+            .ok_or(SealError::UnknownPeer { index })
+    }
+
+    // Signs messages that go in the clear, such as echoes.
+    pub fn sign(&self, message: &[u8]) -> String {
+        let signature: Signature = self.key.sign(message);
+        hex::encode(signature.to_bytes())
+    }
+
+    pub fn verify(
+        &self,
+        sender: u8,
+        message: &[u8],
+        signature: &str,
+    ) -> Result<(), SealError> {
+        let sender_key = self.peer(sender)?;
+        hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .filter(|signature| sender_key.verify(message, signature).is_ok())
+            .map(|_| ())
+            .ok_or(SealError::InvalidSignature { sender })
+    }
+
+    pub fn seal<T: Serialize>(
+        &self,
+        session_id: &[u8],
+        receiver: u8,
+        phase: u8,
+        kind: &str,
+        payload: &T,
+    ) -> Result<SealedMessage, SealError> {
+        let receiver_key = self.peer(receiver)?;
+        let ephemeral = NonZeroScalar::random(&mut OsRng);
+        let ephemeral_public =
+            (ProjectivePoint::GENERATOR * *ephemeral).to_affine();
+        let ephemeral_bytes =
+            ephemeral_public.to_encoded_point(true).as_bytes().to_vec();
+        let shared = ecdh(&ephemeral, &PublicKey::from(receiver_key));
+        let key =
+            aead_key(&ephemeral_bytes, &public_bytes(receiver_key), &shared);
+
+        let header = header(
+            session_id,
+            self.index,
+            receiver,
+            phase,
+            kind,
+            &ephemeral_bytes,
+        );
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(payload).unwrap();
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &header,
+                },
+            )
+            .unwrap();
+
+        let mut signed = header;
+        signed.extend_from_slice(&nonce);
+        signed.extend_from_slice(&ciphertext);
+        let signature: Signature = self.key.sign(&signed);
+
+        Ok(SealedMessage {
+            session_id: session_id.to_vec(),
+            sender: self.index,
+            receiver,
+            phase,
+            kind: kind.to_string(),
+            ephemeral: hex::encode(ephemeral_bytes),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    // Checks the message is for us, in this session, phase and kind, and
+    // that it comes from the party it names, then decrypts it.
+    pub fn open<T: DeserializeOwned>(
+        &self,
+        session_id: &[u8],
+        phase: u8,
+        kind: &str,
+        sealed: &SealedMessage,
+    ) -> Result<T, SealError> {
+        let sender = sealed.sender;
+        if sealed.receiver != self.index || sealed.kind != kind {
+            return Err(SealError::Misaddressed {
+                sender,
+                receiver: sealed.receiver,
+            });
+        }
+        if sealed.session_id != session_id {
+            return Err(SealError::WrongSession { sender });
+        }
+        if sealed.phase != phase {
+            return Err(SealError::WrongPhase {
+                sender,
+                phase: sealed.phase,
+            });
+        }
+        let unpacked = Unpacked::new(sealed, self.peer(sender)?)?;
+        let key = self.aead_key(&unpacked.ephemeral, sender)?;
+        let plaintext = unpacked.decrypt(&key)?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|error| malformed(sender, &error.to_string()))
+    }
+
+    fn aead_key(&self, ephemeral: &[u8], sender: u8) -> Result<Key, SealError> {
+        let point = PublicKey::from_sec1_bytes(ephemeral)
+            .map_err(|_| malformed(sender, "ephemeral"))?;
+        let secret = *self.key.as_nonzero_scalar();
+        let shared = ecdh(&secret, &point);
+        Ok(aead_key(
+            ephemeral,
+            &public_bytes(self.key.verifying_key()),
+            &shared,
+        ))
+    }
+
+    // The key that opens a message we received, in hex, so that others
+    // can read it and check that its sender sent it. It opens no other
+    // message, since every message has its own ephemeral key.
+    pub fn disclose(
+        &self,
+        sealed: &SealedMessage,
+    ) -> Result<String, SealError> {
+        if sealed.receiver != self.index {
+            return Err(SealError::Misaddressed {
+                sender: sealed.sender,
+                receiver: sealed.receiver,
+            });
+        }
+        let ephemeral = hex::decode(&sealed.ephemeral)
+            .map_err(|_| malformed(sealed.sender, "ephemeral"))?;
+        let key = self.aead_key(&ephemeral, sealed.sender)?;
+        Ok(hex::encode(key))
+    }
+
+    // Opens one message of the kind from each of `senders`, and returns
+    // them by sender.
+    pub fn open_all<T: DeserializeOwned>(
+        &self,
+        session_id: &[u8],
+        phase: u8,
+        kind: &str,
+        senders: &[u8],
+        sealed: &[SealedMessage],
+    ) -> Result<BTreeMap<u8, T>, SealError> {
+        let mut opened = BTreeMap::new();
+        for message in sealed
+            .iter()
+            .filter(|message| message.phase == phase && message.kind == kind)
+        {
+            if !senders.contains(&message.sender) {
+                return Err(SealError::UnknownPeer {
+                    index: message.sender,
+                });
+            }
+            let payload = self.open(session_id, phase, kind, message)?;
+            if opened.insert(message.sender, payload).is_some() {
+                return Err(SealError::Duplicate {
+                    sender: message.sender,
+                });
+            }
+        }
+        let missing: Vec<u8> = senders
+            .iter()
+            .filter(|sender| !opened.contains_key(sender))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            return Err(SealError::Missing { parties: missing });
+        }
+        Ok(opened)
+    }
+
+    // Seals a message of a session for its receiver, in the round of
+    // its payload. Sessions send broadcasts as one message per receiver,
+    // so every message of theirs can be sealed.
+    pub fn seal_message<P: SessionPayload + Serialize>(
+        &self,
+        session_id: &[u8],
+        kind: &str,
+        message: &Message<P>,
+    ) -> Result<SealedMessage, SealError> {
+        let round = message.payload.round();
+        self.seal(session_id, message.receiver, round, kind, message)
+    }
+
+    // The inverse of seal_message, which also checks that the message
+    // inside is the one it was sealed as.
+    pub fn open_message<P: SessionPayload + DeserializeOwned>(
+        &self,
+        session_id: &[u8],
+        kind: &str,
+        sealed: &SealedMessage,
+    ) -> Result<Message<P>, SealError> {
+        let message: Message<P> =
+            self.open(session_id, sealed.phase, kind, sealed)?;
+        if message.sender != sealed.sender || message.receiver != self.index {
+            return Err(SealError::Misaddressed {
+                sender: message.sender,
+                receiver: message.receiver,
+            });
+        }
+        if message.payload.round() != sealed.phase {
+            return Err(SealError::WrongPhase {
+                sender: sealed.sender,
+                phase: message.payload.round(),
+            });
+        }
+        Ok(message)
+    }
+
+    // Seals messages that name their parties, each for its receiver.
+    pub fn seal_transmits<T: Serialize>(
+        &self,
+        session_id: &[u8],
+        phase: u8,
+        kind: &str,
+        transmits: &[T],
+        parties: impl Fn(&T) -> &PartiesMessage,
+    ) -> Result<Vec<SealedMessage>, SealError> {
+        transmits
+            .iter()
+            .map(|transmit| {
+                let receiver = parties(transmit).receiver;
+                self.seal(session_id, receiver, phase, kind, transmit)
+            })
+            .collect()
+    }
+
+    // The inverse of seal_transmits, which also checks that the parties
+    // inside each message are the ones it was sealed for.
+    pub fn open_transmits<T: DeserializeOwned>(
+        &self,
+        session_id: &[u8],
+        phase: u8,
+        kind: &str,
+        senders: &[u8],
+        sealed: &[SealedMessage],
+        parties: impl Fn(&T) -> &PartiesMessage,
+    ) -> Result<Vec<T>, SealError> {
+        let opened = self.open_all(session_id, phase, kind, senders, sealed)?;
+        opened
+            .into_iter()
+            .map(|(sender, transmit)| {
+                let named = parties(&transmit);
+                if named.sender != sender || named.receiver != self.index {
+                    return Err(SealError::Misaddressed {
+                        sender: named.sender,
+                        receiver: named.receiver,
+                    });
+                }
+                Ok(transmit)
+            })
+            .collect()
+    }
+}
+
+// The identity for a phase, if any. With the sealed-only feature, every
+// phase that sends or receives secrets needs one.
+pub fn require_identity(
+    index: u8,
+    keys: Option<&IdentityKeys>,
+) -> Result<Option<Identity>, SealError> {
+    match keys {
+        Some(keys) => Identity::new(index, keys).map(Some),
+        None if cfg!(feature = "sealed-only") => Err(SealError::Required),
+        None => Ok(None),
+    }
+}
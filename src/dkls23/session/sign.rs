@@ -0,0 +1,598 @@
+use crate::dkls23::protocols::signing::{
+    Phase1Out, Phase2Out, Phase3Out, Phase4Out,
+};
+use crate::dkls23::session::abort::AbortMessage;
+use crate::dkls23::session::blame::{BlameReport, Evidence, FailedCheck};
+use crate::dkls23::session::{
+    now_millis, Closure, Inbox, Lifetime, Message, Payload, SessionError,
+    Timeouts,
+};
+use dkls23::protocols::signing::{
+    Broadcast3to4, KeepPhase1to2, KeepPhase2to3, SignData, TransmitPhase1to2,
+    TransmitPhase2to3, UniqueKeep1to2, UniqueKeep2to3,
+};
+use dkls23::protocols::{Abort, Party};
+use dkls23::utilities::multiplication::MulDataToKeepReceiver;
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use k256::elliptic_curve::zeroize::Zeroize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Number of communication rounds of the signing protocol.
+pub const SIGN_ROUNDS: u8 = 3;
+
+// What sealed sign messages are called.
+pub const SIGN_KIND: &str = "sign";
+
+// What a signer sends to each counterparty after phases 1, 2 and 3.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Deserialize, Serialize)]
+pub enum SignPayload {
+    Round1(TransmitPhase1to2),
+    Round2(TransmitPhase2to3),
+    Round3 {
+        x_coord: String,
+        broadcast: Broadcast3to4,
+    },
+}
+
+pub type SignMessage = Message<SignPayload>;
+
+impl Payload for SignPayload {
+    fn round(&self) -> u8 {
+        match self {
+            SignPayload::Round1(_) => 1,
+            SignPayload::Round2(_) => 2,
+            SignPayload::Round3 { .. } => 3,
+        }
+    }
+
+    fn is_addressed(&self, sender: u8, receiver: u8) -> bool {
+        match self {
+            SignPayload::Round1(transmit) => {
+                transmit.parties.sender == sender
+                    && transmit.parties.receiver == receiver
+            }
+            SignPayload::Round2(transmit) => {
+                transmit.parties.sender == sender
+                    && transmit.parties.receiver == receiver
+            }
+            SignPayload::Round3 { .. } => true,
+        }
+    }
+
+    // Psi is only for its receiver.
+    fn zeroize(&mut self) {
+        if let SignPayload::Round2(transmit) = self {
+            transmit.psi.zeroize();
+        }
+    }
+}
+
+// What a signer keeps from one phase to the next.
+#[derive(Clone, Deserialize, Serialize)]
+pub enum SignState {
+    Created,
+    Phase1Done {
+        unique_kept: UniqueKeep1to2,
+        kept: BTreeMap<u8, KeepPhase1to2>,
+    },
+    Phase2Done {
+        unique_kept: UniqueKeep2to3,
+        kept: BTreeMap<u8, KeepPhase2to3>,
+    },
+    Phase3Done {
+        x_coord: String,
+    },
+    Finished {
+        signature: Signature,
+    },
+    // Expired or aborted after the given phase. Nothing is kept.
+    Closed {
+        phase: u8,
+        closure: Closure,
+    },
+}
+
+impl SignState {
+    pub fn phase(&self) -> u8 {
+        match self {
+            SignState::Created => 0,
+            SignState::Phase1Done { .. } => 1,
+            SignState::Phase2Done { .. } => 2,
+            SignState::Phase3Done { .. } => 3,
+            SignState::Finished { .. } => 4,
+            SignState::Closed { phase, .. } => *phase,
+        }
+    }
+
+    // Overwrites the nonces, masks and key share kept between phases.
+    pub fn zeroize(&mut self) {
+        match self {
+            SignState::Phase1Done { unique_kept, kept } => {
+                unique_kept.instance_key.zeroize();
+                unique_kept.inversion_mask.zeroize();
+                unique_kept.zeta.zeroize();
+                for keep in kept.values_mut() {
+                    keep.salt.zeroize();
+                    keep.chi.zeroize();
+                    zeroize_mul(&mut keep.mul_keep);
+                }
+            }
+            SignState::Phase2Done { unique_kept, kept } => {
+                unique_kept.instance_key.zeroize();
+                unique_kept.inversion_mask.zeroize();
+                unique_kept.key_share.zeroize();
+                for keep in kept.values_mut() {
+                    keep.c_u.zeroize();
+                    keep.c_v.zeroize();
+                    keep.chi.zeroize();
+                    zeroize_mul(&mut keep.mul_keep);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn zeroize_mul(keep: &mut MulDataToKeepReceiver) {
+    keep.b.zeroize();
+    keep.choice_bits.zeroize();
+    keep.extended_seeds.zeroize();
+    keep.chi_tilde.zeroize();
+    keep.chi_hat.zeroize();
+}
+
+// The x coordinate is needed to verify the signature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Signature {
+    pub signature: String,
+    pub rec_id: u8,
+    pub x_coord: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SignSession {
+    party: Party,
+    sign_data: SignData,
+    normalize: bool,
+    state: SignState,
+    // Our own broadcast of phase 3, which phase 4 needs with the others.
+    broadcast: Option<Broadcast3to4>,
+    inbox: Inbox<SignPayload>,
+    lifetime: Lifetime,
+}
+
+impl SignSession {
+    pub fn new(
+        party: Party,
+        sign_data: SignData,
+        normalize: bool,
+    ) -> SignSession {
+        SignSession::with_state(party, sign_data, normalize, SignState::Created)
+    }
+
+    // Used by the FFI, where the caller keeps the state between phases.
+    pub(crate) fn with_state(
+        party: Party,
+        sign_data: SignData,
+        normalize: bool,
+        state: SignState,
+    ) -> SignSession {
+        SignSession {
+            party,
+            sign_data,
+            normalize,
+            state,
+            broadcast: None,
+            inbox: Inbox::default(),
+            lifetime: Lifetime::default(),
+        }
+    }
+
+    // Every phase from now on must complete within its timeout.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> SignSession {
+        self.lifetime = Lifetime::new(timeouts);
+        self.lifetime.start(self.phase());
+        self
+    }
+
+    pub fn party(&self) -> &Party {
+        &self.party
+    }
+
+    pub fn sign_data(&self) -> &SignData {
+        &self.sign_data
+    }
+
+    pub fn state(&self) -> &SignState {
+        &self.state
+    }
+
+    pub fn phase(&self) -> u8 {
+        self.state.phase()
+    }
+
+    pub fn counterparties(&self) -> BTreeSet<u8> {
+        self.sign_data.counterparties.iter().copied().collect()
+    }
+
+    // When the phase we are waiting for must complete.
+    pub fn deadline(&self) -> Option<u64> {
+        self.lifetime.deadline()
+    }
+
+    pub fn closure(&self) -> Option<&Closure> {
+        match &self.state {
+            SignState::Closed { closure, .. } => Some(closure),
+            _ => None,
+        }
+    }
+
+    // Closes the session if its deadline has passed. Called before
+    // every step, but hosts can also call it from a timer.
+    pub fn check_open(&mut self) -> Result<(), SessionError> {
+        if let Some(deadline) = self.lifetime.expired(now_millis()) {
+            let phase = self.phase();
+            self.close(Closure::Expired { phase, deadline });
+        }
+        match self.closure() {
+            Some(closure) => Err(SessionError::Closed {
+                closure: closure.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Overwrites the nonces and everything else kept so far. The key
+    // share itself belongs to the caller and is kept.
+    fn close(&mut self, closure: Closure) {
+        if self.closure().is_some() {
+            return;
+        }
+        let phase = self.phase();
+        self.state.zeroize();
+        self.state = SignState::Closed { phase, closure };
+        self.broadcast = None;
+        self.inbox.wipe();
+        self.lifetime.stop();
+    }
+
+    // Gives up on the session. The message should be sent to every
+    // counterparty.
+    pub fn abort(&mut self, key: &SigningKey, reason: &str) -> AbortMessage {
+        let own = self.party.party_index;
+        let message = AbortMessage::new(
+            key,
+            &self.sign_data.sign_id,
+            own,
+            self.phase(),
+            reason,
+        );
+        self.close(Closure::Aborted {
+            index: own,
+            reason: reason.to_string(),
+        });
+        message
+    }
+
+    // Closes the session when a counterparty aborted it. The key is the
+    // identity key of the sender.
+    pub fn receive_abort(
+        &mut self,
+        message: &AbortMessage,
+        key: &VerifyingKey,
+    ) -> Result<(), SessionError> {
+        if message.session_id != self.sign_data.sign_id
+            || !self.counterparties().contains(&message.sender)
+            || !message.verify(key)
+        {
+            return Err(SessionError::InvalidAbort {
+                sender: message.sender,
+            });
+        }
+        self.close(Closure::Aborted {
+            index: message.sender,
+            reason: message.reason.clone(),
+        });
+        Ok(())
+    }
+
+    fn blame(
+        &self,
+        phase: u8,
+        check: FailedCheck,
+        abort: Abort,
+        evidence: Vec<Evidence>,
+    ) -> SessionError {
+        SessionError::Aborted {
+            blame: BlameReport::new(
+                &self.sign_data.sign_id,
+                phase,
+                check,
+                abort,
+                &self.sign_data.counterparties,
+                evidence,
+            ),
+        }
+    }
+
+    fn wrong_phase(&self, required: u8) -> SessionError {
+        SessionError::WrongPhase {
+            current: self.phase(),
+            required,
+        }
+    }
+
+    pub fn receive(
+        &mut self,
+        message: SignMessage,
+    ) -> Result<(), SessionError> {
+        self.check_open()?;
+        let counterparties = self.counterparties();
+        self.inbox.insert(
+            self.party.party_index,
+            &counterparties,
+            self.phase(),
+            SIGN_ROUNDS,
+            message,
+        )
+    }
+
+    // Whether advance() can run the next phase.
+    pub fn is_ready(&self) -> bool {
+        if self.closure().is_some() {
+            return false;
+        }
+        let phase = self.phase();
+        phase == 0
+            || (phase <= SIGN_ROUNDS
+                && self.inbox.missing(phase, &self.counterparties()).is_empty())
+    }
+
+    // Runs phase 1.
+    pub fn start(&mut self) -> Result<Vec<SignMessage>, SessionError> {
+        let Phase1Out { transmit, .. } = self.phase1()?;
+        Ok(self.envelop(transmit, |transmit| {
+            (transmit.parties.receiver, SignPayload::Round1(transmit))
+        }))
+    }
+
+    // Runs the next phase with the messages of the last round.
+    pub fn advance(&mut self) -> Result<Vec<SignMessage>, SessionError> {
+        self.check_open()?;
+        let counterparties = self.counterparties();
+        match self.phase() {
+            0 => self.start(),
+            1 => {
+                let received: Vec<TransmitPhase1to2> = self
+                    .inbox
+                    .complete(1, &counterparties)?
+                    .into_values()
+                    .filter_map(|payload| match payload {
+                        SignPayload::Round1(transmit) => Some(transmit),
+                        _ => None,
+                    })
+                    .collect();
+                let Phase2Out { transmit, .. } = self.phase2(&received)?;
+                Ok(self.envelop(transmit, |transmit| {
+                    (transmit.parties.receiver, SignPayload::Round2(transmit))
+                }))
+            }
+            2 => {
+                let received: Vec<TransmitPhase2to3> = self
+                    .inbox
+                    .complete(2, &counterparties)?
+                    .into_values()
+                    .filter_map(|payload| match payload {
+                        SignPayload::Round2(transmit) => Some(transmit),
+                        _ => None,
+                    })
+                    .collect();
+                let Phase3Out { x_coord, broadcast } =
+                    self.phase3(&received)?;
+                let messages = counterparties
+                    .into_iter()
+                    .map(|receiver| SignMessage {
+                        sender: self.party.party_index,
+                        receiver,
+                        payload: SignPayload::Round3 {
+                            x_coord: x_coord.clone(),
+                            broadcast: broadcast.clone(),
+                        },
+                    })
+                    .collect();
+                Ok(messages)
+            }
+            3 => {
+                let own_x_coord = match &self.state {
+                    SignState::Phase3Done { x_coord } => x_coord.clone(),
+                    _ => return Err(self.wrong_phase(3)),
+                };
+                let own = self.party.party_index;
+                let mut broadcasts = BTreeMap::new();
+                broadcasts.insert(
+                    own,
+                    self.broadcast.clone().ok_or(
+                        SessionError::Inconsistent {
+                            description: String::from("Missing own broadcast!"),
+                        },
+                    )?,
+                );
+                for (sender, payload) in
+                    self.inbox.complete(3, &counterparties)?
+                {
+                    if let SignPayload::Round3 { x_coord, broadcast } = payload
+                    {
+                        if x_coord != own_x_coord {
+                            return Err(SessionError::Inconsistent {
+                                description: format!(
+                                    "Party {} sent another x coordinate!",
+                                    sender
+                                ),
+                            });
+                        }
+                        broadcasts.insert(sender, broadcast);
+                    }
+                }
+                let received: Vec<Broadcast3to4> =
+                    broadcasts.into_values().collect();
+                self.phase4(&received)?;
+                Ok(Vec::new())
+            }
+            _ => Err(self.wrong_phase(SIGN_ROUNDS)),
+        }
+    }
+
+    pub fn finish(self) -> Result<Signature, SessionError> {
+        match self.state {
+            SignState::Finished { signature } => Ok(signature),
+            SignState::Closed { closure, .. } => {
+                Err(SessionError::Closed { closure })
+            }
+            state => Err(SessionError::WrongPhase {
+                current: state.phase(),
+                required: 4,
+            }),
+        }
+    }
+
+    fn envelop<T>(
+        &self,
+        transmits: Vec<T>,
+        payload: impl Fn(T) -> (u8, SignPayload),
+    ) -> Vec<SignMessage> {
+        transmits
+            .into_iter()
+            .map(|transmit| {
+                let (receiver, payload) = payload(transmit);
+                SignMessage {
+                    sender: self.party.party_index,
+                    receiver,
+                    payload,
+                }
+            })
+            .collect()
+    }
+
+    // The phases themselves. They take what the other signers sent as
+    // DKLs23 expects it and return our output as the FFI does.
+    pub(crate) fn phase1(&mut self) -> Result<Phase1Out, SessionError> {
+        self.check_open()?;
+        if self.phase() != 0 {
+            return Err(self.wrong_phase(0));
+        }
+
+        let (unique_keep, keep, transmit) =
+            self.party.sign_phase1(&self.sign_data);
+
+        self.state = SignState::Phase1Done {
+            unique_kept: unique_keep.clone(),
+            kept: keep.clone(),
+        };
+        self.lifetime.start(1);
+        Ok(Phase1Out {
+            unique_keep,
+            keep,
+            transmit,
+        })
+    }
+
+    pub(crate) fn phase2(
+        &mut self,
+        received: &[TransmitPhase1to2],
+    ) -> Result<Phase2Out, SessionError> {
+        self.check_open()?;
+        let (unique_kept, kept) = match &self.state {
+            SignState::Phase1Done { unique_kept, kept } => (unique_kept, kept),
+            _ => return Err(self.wrong_phase(1)),
+        };
+
+        let (unique_keep, keep, transmit) = self
+            .party
+            .sign_phase2(&self.sign_data, unique_kept, kept, received)
+            .map_err(|abort| {
+                let evidence = received
+                    .iter()
+                    .map(|message| {
+                        let sender = message.parties.sender;
+                        Evidence::new(sender, 1, SIGN_KIND, message)
+                    })
+                    .collect();
+                self.blame(2, FailedCheck::Signing, abort, evidence)
+            })?;
+
+        self.state = SignState::Phase2Done {
+            unique_kept: unique_keep.clone(),
+            kept: keep.clone(),
+        };
+        self.lifetime.start(2);
+        Ok(Phase2Out {
+            unique_keep,
+            keep,
+            transmit,
+        })
+    }
+
+    pub(crate) fn phase3(
+        &mut self,
+        received: &[TransmitPhase2to3],
+    ) -> Result<Phase3Out, SessionError> {
+        self.check_open()?;
+        let (unique_kept, kept) = match &self.state {
+            SignState::Phase2Done { unique_kept, kept } => (unique_kept, kept),
+            _ => return Err(self.wrong_phase(2)),
+        };
+
+        let (x_coord, broadcast) = self
+            .party
+            .sign_phase3(&self.sign_data, unique_kept, kept, received)
+            .map_err(|abort| {
+                let evidence = received
+                    .iter()
+                    .map(|message| {
+                        let sender = message.parties.sender;
+                        Evidence::new(sender, 2, SIGN_KIND, message)
+                    })
+                    .collect();
+                self.blame(3, FailedCheck::Signing, abort, evidence)
+            })?;
+
+        self.broadcast = Some(broadcast.clone());
+        self.state = SignState::Phase3Done {
+            x_coord: x_coord.clone(),
+        };
+        self.lifetime.start(3);
+        Ok(Phase3Out { x_coord, broadcast })
+    }
+
+    pub(crate) fn phase4(
+        &mut self,
+        received: &[Broadcast3to4],
+    ) -> Result<Phase4Out, SessionError> {
+        self.check_open()?;
+        let x_coord = match &self.state {
+            SignState::Phase3Done { x_coord } => x_coord.clone(),
+            _ => return Err(self.wrong_phase(3)),
+        };
+
+        // The broadcasts do not say who sent them, so there is no
+        // evidence to single out.
+        let (signature, rec_id) = self
+            .party
+            .sign_phase4(&self.sign_data, &x_coord, received, self.normalize)
+            .map_err(|abort| {
+                self.blame(4, FailedCheck::Signature, abort, Vec::new())
+            })?;
+
+        self.state = SignState::Finished {
+            signature: Signature {
+                signature: signature.clone(),
+                rec_id,
+                x_coord,
+            },
+        };
+        self.lifetime.stop();
+        Ok(Phase4Out { signature, rec_id })
+    }
+}
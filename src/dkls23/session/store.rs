@@ -0,0 +1,265 @@
+use crate::dkls23::session::abort::AbortMessage;
+use crate::dkls23::session::dkg::{DkgMessage, DkgSession};
+use crate::dkls23::session::sign::{SignMessage, SignSession};
+use crate::dkls23::session::{Closure, SessionError};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+// Sessions are checkpointed to one file per session id, so a party can
+// go on after its process was killed between two phases. The files hold
+// key material and are encrypted with ChaCha20-Poly1305 under a key
+// chosen by the caller, with the session id as associated data so that
+// a file cannot be passed off as another session.
+
+const VERSION: u8 = 1;
+const NONCE_SIZE: usize = 12;
+const EXTENSION: &str = "session";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum StoreError {
+    // Ids may only contain ASCII letters, digits, '-' and '_'.
+    InvalidId { id: String },
+    // The key must be 32 bytes.
+    InvalidKey,
+    NotFound { id: String },
+    // A new session would overwrite the checkpoint of another one.
+    Exists { id: String },
+    // The file was changed, or the key or the id is wrong.
+    Decryption { id: String },
+    Malformed { description: String },
+    Io { description: String },
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(error: std::io::Error) -> StoreError {
+        StoreError::Io {
+            description: error.to_string(),
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Deserialize, Serialize)]
+pub enum Checkpoint {
+    Dkg {
+        session: DkgSession,
+        outbox: Vec<DkgMessage>,
+    },
+    Sign {
+        session: SignSession,
+        outbox: Vec<SignMessage>,
+    },
+}
+
+impl Checkpoint {
+    pub fn phase(&self) -> u8 {
+        match self {
+            Checkpoint::Dkg { session, .. } => session.phase(),
+            Checkpoint::Sign { session, .. } => session.phase(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        match self {
+            Checkpoint::Dkg { session, .. } => session.is_ready(),
+            Checkpoint::Sign { session, .. } => session.is_ready(),
+        }
+    }
+
+    pub fn deadline(&self) -> Option<u64> {
+        match self {
+            Checkpoint::Dkg { session, .. } => session.deadline(),
+            Checkpoint::Sign { session, .. } => session.deadline(),
+        }
+    }
+
+    pub fn closure(&self) -> Option<&Closure> {
+        match self {
+            Checkpoint::Dkg { session, .. } => session.closure(),
+            Checkpoint::Sign { session, .. } => session.closure(),
+        }
+    }
+
+    pub fn check_open(&mut self) -> Result<(), SessionError> {
+        match self {
+            Checkpoint::Dkg { session, .. } => session.check_open(),
+            Checkpoint::Sign { session, .. } => session.check_open(),
+        }
+    }
+
+    // A closed session has nothing left to send either.
+    pub fn abort(&mut self, key: &SigningKey, reason: &str) -> AbortMessage {
+        match self {
+            Checkpoint::Dkg { session, outbox } => {
+                outbox.clear();
+                session.abort(key, reason)
+            }
+            Checkpoint::Sign { session, outbox } => {
+                outbox.clear();
+                session.abort(key, reason)
+            }
+        }
+    }
+
+    pub fn receive_abort(
+        &mut self,
+        message: &AbortMessage,
+        key: &VerifyingKey,
+    ) -> Result<(), SessionError> {
+        match self {
+            Checkpoint::Dkg { session, outbox } => {
+                session.receive_abort(message, key)?;
+                outbox.clear();
+            }
+            Checkpoint::Sign { session, outbox } => {
+                session.receive_abort(message, key)?;
+                outbox.clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct SessionStore {
+    dir: PathBuf,
+    cipher: ChaCha20Poly1305,
+}
+
+impl SessionStore {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        key: &[u8],
+    ) -> Result<SessionStore, StoreError> {
+        if key.len() != 32 {
+            return Err(StoreError::InvalidKey);
+        }
+        Ok(SessionStore {
+            dir: dir.into(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+        })
+    }
+
+    fn path(&self, id: &str, extension: &str) -> Result<PathBuf, StoreError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(StoreError::InvalidId { id: id.to_string() });
+        }
+        Ok(self.dir.join(format!("{}.{}", id, extension)))
+    }
+
+    pub fn exists(&self, id: &str) -> Result<bool, StoreError> {
+        Ok(self.path(id, EXTENSION)?.exists())
+    }
+
+    // The file is written next to the old one and renamed over it, so
+    // a checkpoint is either the previous one or the new one, never a
+    // mix of both.
+    pub fn save<T: Serialize>(
+        &self,
+        id: &str,
+        value: &T,
+    ) -> Result<(), StoreError> {
+        let path = self.path(id, EXTENSION)?;
+        let tmp_path = self.path(id, "tmp")?;
+
+        let plaintext = serde_json::to_vec(value).map_err(|error| {
+            StoreError::Malformed {
+                description: error.to_string(),
+            }
+        })?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| StoreError::Malformed {
+                description: String::from("Encryption failed!"),
+            })?;
+
+        fs::create_dir_all(&self.dir)?;
+        // The temporary file is only readable by its owner from the
+        // start. One left behind by a killed process is replaced.
+        let _ = fs::remove_file(&tmp_path);
+        let written = (|| {
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let mut file = options.open(&tmp_path)?;
+            file.write_all(&[VERSION])?;
+            file.write_all(&nonce)?;
+            file.write_all(&ciphertext)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &path)
+        })();
+        if let Err(error) = written {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(error.into());
+        }
+
+        // Makes the rename itself durable. Not every platform lets a
+        // directory be opened, and the data is already on disk.
+        if let Ok(dir) = File::open(&self.dir) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, id: &str) -> Result<T, StoreError> {
+        let path = self.path(id, EXTENSION)?;
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Err(StoreError::NotFound { id: id.to_string() })
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        if bytes.len() < 1 + NONCE_SIZE || bytes[0] != VERSION {
+            return Err(StoreError::Malformed {
+                description: String::from("Unknown checkpoint format!"),
+            });
+        }
+        let nonce = Nonce::from_slice(&bytes[1..1 + NONCE_SIZE]);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: &bytes[1 + NONCE_SIZE..],
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| StoreError::Decryption { id: id.to_string() })?;
+
+        serde_json::from_slice(&plaintext).map_err(|error| {
+            StoreError::Malformed {
+                description: error.to_string(),
+            }
+        })
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), StoreError> {
+        match fs::remove_file(self.path(id, EXTENSION)?) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::dkls23::transport::{Transport, TransportError};
+use std::future::{ready, Future};
+use std::os::raw::c_void;
+use std::time::{Duration, Instant};
+
+// A transport made of functions of the host, for running a whole
+// protocol from one FFI call. The callbacks are called on the thread
+// of that call and may block.
+//
+//   send(user_data, receiver, bytes, length)
+//     returns 0, or a negative code on failure.
+//   recv(user_data, timeout_ms, sender, buffer, capacity, length)
+//     waits up to timeout_ms for a message, then returns 0 with the
+//     message copied to buffer and its length and sender set, 1 if no
+//     message came, 2 if the message does not fit in capacity (length
+//     is set to its size and the host keeps it for the next call), or a
+//     negative code on failure.
+//   cancelled(user_data)
+//     optional, returns nonzero to stop the run.
+
+pub const RECV_OK: i32 = 0;
+pub const RECV_EMPTY: i32 = 1;
+pub const RECV_TOO_SMALL: i32 = 2;
+
+pub const MAX_MESSAGE: usize = 1 << 24;
+const INITIAL_CAPACITY: usize = 1 << 16;
+// How long a single recv may wait without a deadline, so that the
+// cancel hook is still checked.
+const RECV_SLICE: Duration = Duration::from_millis(500);
+
+pub type SendFn = extern "C" fn(*mut c_void, u8, *const u8, usize) -> i32;
+pub type RecvFn =
+    extern "C" fn(*mut c_void, u32, *mut u8, *mut u8, usize, *mut usize) -> i32;
+pub type CancelledFn = extern "C" fn(*mut c_void) -> i32;
+
+#[repr(C)]
+pub struct DklsCallbacks {
+    pub user_data: *mut c_void,
+    pub send: SendFn,
+    pub recv: RecvFn,
+    pub cancelled: Option<CancelledFn>,
+}
+
+pub struct CallbackTransport<'a> {
+    callbacks: &'a DklsCallbacks,
+    deadline: Option<Instant>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> CallbackTransport<'a> {
+    pub fn new(
+        callbacks: &'a DklsCallbacks,
+        timeout: Option<Duration>,
+    ) -> CallbackTransport<'a> {
+        CallbackTransport {
+            callbacks,
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            buffer: vec![0; INITIAL_CAPACITY],
+        }
+    }
+
+    fn check(&self) -> Result<(), TransportError> {
+        if let Some(cancelled) = self.callbacks.cancelled {
+            if cancelled(self.callbacks.user_data) != 0 {
+                return Err(TransportError::Cancelled);
+            }
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+        {
+            return Err(TransportError::Timeout);
+        }
+        Ok(())
+    }
+
+    fn send_now(
+        &mut self,
+        receiver: u8,
+        message: &[u8],
+    ) -> Result<(), TransportError> {
+        self.check()?;
+        let code = (self.callbacks.send)(
+            self.callbacks.user_data,
+            receiver,
+            message.as_ptr(),
+            message.len(),
+        );
+        if code < 0 {
+            return Err(TransportError::Host { code });
+        }
+        Ok(())
+    }
+
+    fn receive_now(&mut self) -> Result<(u8, Vec<u8>), TransportError> {
+        loop {
+            self.check()?;
+            let wait = match self.deadline {
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(RECV_SLICE),
+                None => RECV_SLICE,
+            };
+            let mut sender = 0u8;
+            let mut length = 0usize;
+            let code = (self.callbacks.recv)(
+                self.callbacks.user_data,
+                wait.as_millis() as u32,
+                &mut sender,
+                self.buffer.as_mut_ptr(),
+                self.buffer.len(),
+                &mut length,
+            );
+            let too_large = TransportError::TooLarge {
+                party: sender,
+                length,
+            };
+            match code {
+                RECV_OK if length <= self.buffer.len() => {
+                    return Ok((sender, self.buffer[..length].to_vec()))
+                }
+                RECV_OK => return Err(too_large),
+                RECV_EMPTY => continue,
+                RECV_TOO_SMALL => {
+                    if length > MAX_MESSAGE || length <= self.buffer.len() {
+                        return Err(too_large);
+                    }
+                    self.buffer.resize(length, 0);
+                }
+                code => return Err(TransportError::Host { code }),
+            }
+        }
+    }
+}
+
+// The callbacks block, so the futures are ready when they are returned.
+impl Transport for CallbackTransport<'_> {
+    fn send(
+        &mut self,
+        receiver: u8,
+        message: Vec<u8>,
+    ) -> impl Future<Output = Result<(), TransportError>> + Send {
+        ready(self.send_now(receiver, &message))
+    }
+
+    fn receive(
+        &mut self,
+    ) -> impl Future<Output = Result<(u8, Vec<u8>), TransportError>> + Send
+    {
+        ready(self.receive_now())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::dkls23::transport::{Transport, TransportError};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::{poll_fn, Future};
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+// Transports for parties that run in the same process, as in tests.
+// Every party has an inbox, and the transports of the others push into
+// it. It needs no runtime, so any executor can drive it.
+
+#[derive(Default)]
+struct Queue {
+    messages: VecDeque<(u8, Vec<u8>)>,
+    // Transports that can still send to this inbox.
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+type Inbox = Arc<Mutex<Queue>>;
+
+pub struct MemoryTransport {
+    index: u8,
+    inbox: Inbox,
+    peers: BTreeMap<u8, Inbox>,
+}
+
+// One transport for each index, connected to all the others.
+pub fn memory_transports(indices: &[u8]) -> BTreeMap<u8, MemoryTransport> {
+    let inboxes: BTreeMap<u8, Inbox> = indices
+        .iter()
+        .map(|&index| {
+            let queue = Queue {
+                senders: indices.len() - 1,
+                ..Queue::default()
+            };
+            (index, Arc::new(Mutex::new(queue)))
+        })
+        .collect();
+
+    inboxes
+        .iter()
+        .map(|(&index, inbox)| {
+            let peers = inboxes
+                .iter()
+                .filter(|(&peer, _)| peer != index)
+                .map(|(&peer, inbox)| (peer, inbox.clone()))
+                .collect();
+            let transport = MemoryTransport {
+                index,
+                inbox: inbox.clone(),
+                peers,
+            };
+            (index, transport)
+        })
+        .collect()
+}
+
+impl Transport for MemoryTransport {
+    fn send(
+        &mut self,
+        receiver: u8,
+        message: Vec<u8>,
+    ) -> impl Future<Output = Result<(), TransportError>> + Send {
+        let result = match self.peers.get(&receiver) {
+            Some(inbox) => {
+                let mut queue = inbox.lock().unwrap();
+                queue.messages.push_back((self.index, message));
+                if let Some(waker) = queue.waker.take() {
+                    waker.wake();
+                }
+                Ok(())
+            }
+            None => Err(TransportError::UnknownParty { index: receiver }),
+        };
+        async { result }
+    }
+
+    fn receive(
+        &mut self,
+    ) -> impl Future<Output = Result<(u8, Vec<u8>), TransportError>> + Send
+    {
+        let inbox = self.inbox.clone();
+        poll_fn(move |context| {
+            let mut queue = inbox.lock().unwrap();
+            if let Some(message) = queue.messages.pop_front() {
+                return Poll::Ready(Ok(message));
+            }
+            if queue.senders == 0 {
+                return Poll::Ready(Err(TransportError::Closed));
+            }
+            queue.waker = Some(context.waker().clone());
+            Poll::Pending
+        })
+    }
+}
+
+// The others stop waiting for us once we are gone.
+impl Drop for MemoryTransport {
+    fn drop(&mut self) {
+        for inbox in self.peers.values() {
+            let mut queue = inbox.lock().unwrap();
+            queue.senders -= 1;
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
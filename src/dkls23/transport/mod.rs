@@ -0,0 +1,306 @@
+pub mod callback;
+pub mod memory;
+#[cfg(feature = "tcp")]
+pub mod tcp;
+
+use crate::dkls23::session::dkg::{DkgSession, DKG_KIND, DKG_ROUNDS};
+use crate::dkls23::session::seal::{
+    require_identity, IdentityKeys, SealError, SealedMessage,
+};
+use crate::dkls23::session::sign::{
+    SignSession, Signature, SIGN_KIND, SIGN_ROUNDS,
+};
+use crate::dkls23::session::{Message, Payload, SessionError};
+use dkls23::protocols::dkg::SessionData;
+use dkls23::protocols::signing::SignData;
+use dkls23::protocols::Party;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+// Runs a whole DKG or signature over a transport, so that the caller
+// does not have to wire the phases. The messages of the sessions are
+// sent as JSON, and the transport only moves bytes between parties.
+// It does not authenticate anyone: the sender it reports is the one
+// the messages are checked against. With identity keys, every message
+// is sealed for its receiver instead, and only sealed messages from the
+// party the transport reports are accepted.
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum TransportError {
+    // Every counterparty is gone and no message is left.
+    Closed,
+    // A party we have no connection to.
+    UnknownParty { index: u8 },
+    Io { description: String },
+    // A frame larger than the transport accepts, from or for a party.
+    TooLarge { party: u8, length: usize },
+    // The run took longer than it was given, or the host stopped it.
+    Timeout,
+    Cancelled,
+    // A callback of the host failed with its own code.
+    Host { code: i32 },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RunError {
+    Transport(TransportError),
+    Session(SessionError),
+    Seal(SealError),
+    // Bytes that are not a message of the session, or a message that
+    // claims another sender than the one it came from.
+    InvalidMessage { sender: u8, description: String },
+}
+
+impl From<TransportError> for RunError {
+    fn from(error: TransportError) -> RunError {
+        RunError::Transport(error)
+    }
+}
+
+impl From<SessionError> for RunError {
+    fn from(error: SessionError) -> RunError {
+        RunError::Session(error)
+    }
+}
+
+impl From<SealError> for RunError {
+    fn from(error: SealError) -> RunError {
+        RunError::Seal(error)
+    }
+}
+
+pub trait Transport {
+    fn send(
+        &mut self,
+        receiver: u8,
+        message: Vec<u8>,
+    ) -> impl Future<Output = Result<(), TransportError>> + Send;
+
+    // The next message from any party, with the index of its sender.
+    fn receive(
+        &mut self,
+    ) -> impl Future<Output = Result<(u8, Vec<u8>), TransportError>> + Send;
+}
+
+// What the driver needs from DkgSession and SignSession.
+trait Driven {
+    type Payload: Payload + Serialize + DeserializeOwned;
+    type Output;
+    const ROUNDS: u8;
+    const KIND: &'static str;
+
+    fn party_index(&self) -> u8;
+    fn session_id(&self) -> &[u8];
+    fn phase(&self) -> u8;
+    fn is_ready(&self) -> bool;
+    fn check_open(&mut self) -> Result<(), SessionError>;
+    fn receive(
+        &mut self,
+        message: Message<Self::Payload>,
+    ) -> Result<(), SessionError>;
+    fn advance(&mut self) -> Result<Vec<Message<Self::Payload>>, SessionError>;
+    fn finish(self) -> Result<Self::Output, SessionError>;
+}
+
+impl Driven for DkgSession {
+    type Payload = crate::dkls23::session::dkg::DkgPayload;
+    type Output = Party;
+    const ROUNDS: u8 = DKG_ROUNDS;
+    const KIND: &'static str = DKG_KIND;
+
+    fn party_index(&self) -> u8 {
+        self.data().party_index
+    }
+
+    fn session_id(&self) -> &[u8] {
+        &self.data().session_id
+    }
+
+    fn phase(&self) -> u8 {
+        DkgSession::phase(self)
+    }
+
+    fn is_ready(&self) -> bool {
+        DkgSession::is_ready(self)
+    }
+
+    fn check_open(&mut self) -> Result<(), SessionError> {
+        DkgSession::check_open(self)
+    }
+
+    fn receive(
+        &mut self,
+        message: Message<Self::Payload>,
+    ) -> Result<(), SessionError> {
+        DkgSession::receive(self, message)
+    }
+
+    fn advance(&mut self) -> Result<Vec<Message<Self::Payload>>, SessionError> {
+        DkgSession::advance(self)
+    }
+
+    fn finish(self) -> Result<Party, SessionError> {
+        DkgSession::finish(self)
+    }
+}
+
+impl Driven for SignSession {
+    type Payload = crate::dkls23::session::sign::SignPayload;
+    type Output = Signature;
+    const ROUNDS: u8 = SIGN_ROUNDS;
+    const KIND: &'static str = SIGN_KIND;
+
+    fn party_index(&self) -> u8 {
+        self.party().party_index
+    }
+
+    fn session_id(&self) -> &[u8] {
+        &self.sign_data().sign_id
+    }
+
+    fn phase(&self) -> u8 {
+        SignSession::phase(self)
+    }
+
+    fn is_ready(&self) -> bool {
+        SignSession::is_ready(self)
+    }
+
+    fn check_open(&mut self) -> Result<(), SessionError> {
+        SignSession::check_open(self)
+    }
+
+    fn receive(
+        &mut self,
+        message: Message<Self::Payload>,
+    ) -> Result<(), SessionError> {
+        SignSession::receive(self, message)
+    }
+
+    fn advance(&mut self) -> Result<Vec<Message<Self::Payload>>, SessionError> {
+        SignSession::advance(self)
+    }
+
+    fn finish(self) -> Result<Signature, SessionError> {
+        SignSession::finish(self)
+    }
+}
+
+fn parse<T: DeserializeOwned>(sender: u8, bytes: &[u8]) -> Result<T, RunError> {
+    serde_json::from_slice(bytes).map_err(|error| RunError::InvalidMessage {
+        sender,
+        description: error.to_string(),
+    })
+}
+
+async fn drive<S: Driven, T: Transport>(
+    mut session: S,
+    keys: Option<&IdentityKeys>,
+    transport: &mut T,
+) -> Result<S::Output, RunError> {
+    let identity = require_identity(session.party_index(), keys)?;
+    let session_id = session.session_id().to_vec();
+    while session.phase() <= S::ROUNDS {
+        while !session.is_ready() {
+            session.check_open()?;
+            let (sender, bytes) = transport.receive().await?;
+            let message: Message<S::Payload> = match &identity {
+                Some(identity) => {
+                    let sealed: SealedMessage = parse(sender, &bytes)?;
+                    identity.open_message(&session_id, S::KIND, &sealed)?
+                }
+                None => parse(sender, &bytes)?,
+            };
+            if message.sender != sender {
+                return Err(RunError::InvalidMessage {
+                    sender,
+                    description: format!(
+                        "The message claims to be from party {}!",
+                        message.sender
+                    ),
+                });
+            }
+            session.receive(message)?;
+        }
+
+        for message in session.advance()? {
+            let bytes = match &identity {
+                Some(identity) => serde_json::to_vec(&identity.seal_message(
+                    &session_id,
+                    S::KIND,
+                    &message,
+                )?),
+                None => serde_json::to_vec(&message),
+            }
+            .map_err(|error| RunError::InvalidMessage {
+                sender: message.sender,
+                description: error.to_string(),
+            })?;
+            transport.send(message.receiver, bytes).await?;
+        }
+    }
+    Ok(session.finish()?)
+}
+
+// Without identity keys, which the sealed-only feature refuses.
+pub async fn run_dkg<T: Transport>(
+    session: SessionData,
+    transport: &mut T,
+) -> Result<Party, RunError> {
+    drive(DkgSession::new(session), None, transport).await
+}
+
+pub async fn run_dkg_sealed<T: Transport>(
+    session: SessionData,
+    identity: &IdentityKeys,
+    transport: &mut T,
+) -> Result<Party, RunError> {
+    drive(DkgSession::new(session), Some(identity), transport).await
+}
+
+pub async fn run_sign<T: Transport>(
+    party: Party,
+    sign_data: SignData,
+    normalize: bool,
+    transport: &mut T,
+) -> Result<Signature, RunError> {
+    let session = SignSession::new(party, sign_data, normalize);
+    drive(session, None, transport).await
+}
+
+pub async fn run_sign_sealed<T: Transport>(
+    party: Party,
+    sign_data: SignData,
+    normalize: bool,
+    identity: &IdentityKeys,
+    transport: &mut T,
+) -> Result<Signature, RunError> {
+    let session = SignSession::new(party, sign_data, normalize);
+    drive(session, Some(identity), transport).await
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Runs a future on the current thread, for callers without a runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::dkls23::transport::{Transport, TransportError};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+
+// A full mesh of TCP connections. Every party listens, connects to the
+// parties with a lower index and accepts the ones with a higher index.
+// A connection starts with the index of the party that opened it, and
+// then carries frames: the length as 4 big-endian bytes and the message.
+// Nothing is authenticated, so this is for trusted networks, or under
+// an authenticated channel.
+
+pub const MAX_FRAME: usize = 1 << 24;
+
+// How long an accepted connection has to send the index of its party.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TcpTransport {
+    writers: BTreeMap<u8, OwnedWriteHalf>,
+    received: mpsc::UnboundedReceiver<Result<(u8, Vec<u8>), TransportError>>,
+    deadline: Option<Instant>,
+}
+
+fn io_error(error: std::io::Error) -> TransportError {
+    TransportError::Io {
+        description: error.to_string(),
+    }
+}
+
+async fn until<T>(
+    deadline: Option<Instant>,
+    future: impl Future<Output = Result<T, TransportError>>,
+) -> Result<T, TransportError> {
+    match deadline {
+        Some(deadline) => time::timeout_at(deadline, future)
+            .await
+            .unwrap_or(Err(TransportError::Timeout)),
+        None => future.await,
+    }
+}
+
+async fn read_frame(
+    reader: &mut OwnedReadHalf,
+    sender: u8,
+) -> Result<Option<Vec<u8>>, TransportError> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Ok(None)
+        }
+        Err(error) => return Err(io_error(error)),
+    }
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME {
+        return Err(TransportError::TooLarge {
+            party: sender,
+            length,
+        });
+    }
+    let mut frame = vec![0u8; length];
+    reader.read_exact(&mut frame).await.map_err(io_error)?;
+    Ok(Some(frame))
+}
+
+impl TcpTransport {
+    // `peers` has the address of every other party. The listener must
+    // already be bound, so that parties can start in any order.
+    pub async fn connect(
+        index: u8,
+        listener: TcpListener,
+        peers: BTreeMap<u8, SocketAddr>,
+    ) -> Result<TcpTransport, TransportError> {
+        let mut streams = BTreeMap::new();
+        for (&peer, addr) in peers.range(..index) {
+            let mut stream =
+                TcpStream::connect(addr).await.map_err(io_error)?;
+            stream.write_all(&[index]).await.map_err(io_error)?;
+            streams.insert(peer, stream);
+        }
+        // A connection that does not give its index in time, or gives
+        // one we do not wait for or already have, is dropped, and we keep
+        // accepting the others.
+        while streams.len() < peers.len() {
+            let (mut stream, _) = listener.accept().await.map_err(io_error)?;
+            let mut peer = [0u8; 1];
+            let handshake = stream.read_exact(&mut peer);
+            if !matches!(
+                time::timeout(HANDSHAKE_TIMEOUT, handshake).await,
+                Ok(Ok(_))
+            ) {
+                continue;
+            }
+            let peer = peer[0];
+            if peer <= index
+                || !peers.contains_key(&peer)
+                || streams.contains_key(&peer)
+            {
+                continue;
+            }
+            streams.insert(peer, stream);
+        }
+
+        let (sender, received) = mpsc::unbounded_channel();
+        let mut writers = BTreeMap::new();
+        for (peer, stream) in streams {
+            let (mut reader, writer) = stream.into_split();
+            writers.insert(peer, writer);
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                loop {
+                    match read_frame(&mut reader, peer).await {
+                        Ok(Some(frame)) => {
+                            if sender.send(Ok((peer, frame))).is_err() {
+                                return;
+                            }
+                        }
+                        Ok(None) => return,
+                        Err(error) => {
+                            let _ = sender.send(Err(error));
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(TcpTransport {
+            writers,
+            received,
+            deadline: None,
+        })
+    }
+
+    // Bounds the whole run, like the timeout of the callback transport.
+    // Sending or receiving after it fails with Timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> TcpTransport {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+}
+
+impl Transport for TcpTransport {
+    async fn send(
+        &mut self,
+        receiver: u8,
+        message: Vec<u8>,
+    ) -> Result<(), TransportError> {
+        let writer = self
+            .writers
+            .get_mut(&receiver)
+            .ok_or(TransportError::UnknownParty { index: receiver })?;
+        if message.len() > MAX_FRAME {
+            return Err(TransportError::TooLarge {
+                party: receiver,
+                length: message.len(),
+            });
+        }
+        until(self.deadline, async {
+            let length = (message.len() as u32).to_be_bytes();
+            writer.write_all(&length).await.map_err(io_error)?;
+            writer.write_all(&message).await.map_err(io_error)
+        })
+        .await
+    }
+
+    async fn receive(&mut self) -> Result<(u8, Vec<u8>), TransportError> {
+        until(self.deadline, async {
+            self.received
+                .recv()
+                .await
+                .unwrap_or(Err(TransportError::Closed))
+        })
+        .await
+    }
+}
@@ -0,0 +1,165 @@
+use crate::dkls23::utilities::bip32::hash160;
+use bech32::{hrp, Bech32, Hrp};
+use dkls23::protocols::derivation::ErrorDeriv;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::AffinePoint;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+// Version bytes for base58check Bitcoin addresses.
+const P2PKH_MAINNET: u8 = 0x00;
+const P2PKH_TESTNET: u8 = 0x6F;
+const P2SH_MAINNET: u8 = 0x05;
+const P2SH_TESTNET: u8 = 0xC4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+// Single address format, for when only one address per key is needed.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AddressKind {
+    Eth,
+    P2pkh { network: Network },
+    P2shP2wpkh { network: Network },
+    P2wpkh { network: Network },
+    Cosmos { prefix: String },
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct BitcoinAddresses {
+    pub p2pkh: String,
+    pub p2sh_p2wpkh: String,
+    pub p2wpkh: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Addresses {
+    pub pk_compressed: String,
+    pub pk_uncompressed: String,
+    pub eth_address: String,
+    pub btc_mainnet: BitcoinAddresses,
+    pub btc_testnet: BitcoinAddresses,
+    pub cosmos_address: Option<String>,
+}
+
+fn base58check(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend(payload);
+    bs58::encode(data).with_check().into_string()
+}
+
+fn key_hash(pk: &AffinePoint) -> [u8; 20] {
+    hash160(pk.to_encoded_point(true).as_bytes())
+}
+
+// Ethereum address with the mixed-case checksum from EIP-55.
+pub fn eth_address(pk: &AffinePoint) -> String {
+    let uncompressed = pk.to_encoded_point(false);
+    let hash = Keccak256::digest(&uncompressed.as_bytes()[1..]);
+    let address = hex::encode(&hash[12..]);
+
+    let checksum = Keccak256::digest(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (checksum[i / 2] >> (4 * (1 - i % 2))) & 0x0F;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    format!("0x{}", checksummed)
+}
+
+pub fn p2pkh_address(pk: &AffinePoint, network: Network) -> String {
+    let version = match network {
+        Network::Mainnet => P2PKH_MAINNET,
+        Network::Testnet => P2PKH_TESTNET,
+    };
+    base58check(version, &key_hash(pk))
+}
+
+pub fn p2sh_p2wpkh_address(pk: &AffinePoint, network: Network) -> String {
+    let version = match network {
+        Network::Mainnet => P2SH_MAINNET,
+        Network::Testnet => P2SH_TESTNET,
+    };
+
+    // The redeem script is OP_0 <20-byte key hash>.
+    let mut redeem_script = vec![0x00, 0x14];
+    redeem_script.extend(key_hash(pk));
+
+    base58check(version, &hash160(&redeem_script))
+}
+
+pub fn p2wpkh_address(pk: &AffinePoint, network: Network) -> String {
+    let hrp = match network {
+        Network::Mainnet => hrp::BC,
+        Network::Testnet => hrp::TB,
+    };
+    bech32::segwit::encode_v0(hrp, &key_hash(pk))
+        .expect("a 20-byte witness program is always valid")
+}
+
+// Cosmos SDK style address: bech32 of the key hash with a chain prefix.
+pub fn cosmos_address(
+    pk: &AffinePoint,
+    prefix: &str,
+) -> Result<String, ErrorDeriv> {
+    let hrp = Hrp::parse(prefix)
+        .map_err(|_| ErrorDeriv::new("Invalid bech32 prefix!"))?;
+    bech32::encode::<Bech32>(hrp, &key_hash(pk))
+        .map_err(|_| ErrorDeriv::new("Invalid bech32 prefix!"))
+}
+
+impl AddressKind {
+    pub fn address(&self, pk: &AffinePoint) -> Result<String, ErrorDeriv> {
+        match self {
+            AddressKind::Eth => Ok(eth_address(pk)),
+            AddressKind::P2pkh { network } => Ok(p2pkh_address(pk, *network)),
+            AddressKind::P2shP2wpkh { network } => {
+                Ok(p2sh_p2wpkh_address(pk, *network))
+            }
+            AddressKind::P2wpkh { network } => Ok(p2wpkh_address(pk, *network)),
+            AddressKind::Cosmos { prefix } => cosmos_address(pk, prefix),
+        }
+    }
+}
+
+impl BitcoinAddresses {
+    pub fn new(pk: &AffinePoint, network: Network) -> BitcoinAddresses {
+        BitcoinAddresses {
+            p2pkh: p2pkh_address(pk, network),
+            p2sh_p2wpkh: p2sh_p2wpkh_address(pk, network),
+            p2wpkh: p2wpkh_address(pk, network),
+        }
+    }
+}
+
+impl Addresses {
+    pub fn new(
+        pk: &AffinePoint,
+        cosmos_prefix: Option<&str>,
+    ) -> Result<Addresses, ErrorDeriv> {
+        let cosmos_address = match cosmos_prefix {
+            Some(prefix) => Some(cosmos_address(pk, prefix)?),
+            None => None,
+        };
+
+        Ok(Addresses {
+            pk_compressed: hex::encode(pk.to_encoded_point(true).as_bytes()),
+            pk_uncompressed: hex::encode(pk.to_encoded_point(false).as_bytes()),
+            eth_address: eth_address(pk),
+            btc_mainnet: BitcoinAddresses::new(pk, Network::Mainnet),
+            btc_testnet: BitcoinAddresses::new(pk, Network::Testnet),
+            cosmos_address,
+        })
+    }
+}
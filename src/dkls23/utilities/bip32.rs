@@ -0,0 +1,352 @@
+use crate::dkls23::utilities::path::{validate_path, DerivationError};
+use dkls23::protocols::derivation::{
+    ChainCode, DerivData, ErrorDeriv, Fingerprint,
+};
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::elliptic_curve::PrimeField;
+use k256::{AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar};
+use ripemd::Ripemd160;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+// Version bytes for serialized extended keys (BIP-32).
+pub const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+pub const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xAD, 0xE4];
+pub const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xCF];
+pub const TPRV_VERSION: [u8; 4] = [0x04, 0x35, 0x83, 0x94];
+
+// Version bytes for public keys from SLIP-132.
+pub const YPUB_VERSION: [u8; 4] = [0x04, 0x9D, 0x7C, 0xB2];
+pub const ZPUB_VERSION: [u8; 4] = [0x04, 0xB2, 0x47, 0x46];
+pub const UPUB_VERSION: [u8; 4] = [0x04, 0x4A, 0x52, 0x62];
+pub const VPUB_VERSION: [u8; 4] = [0x04, 0x5F, 0x1C, 0xF6];
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq,
+)]
+pub enum ExtendedKeyVersion {
+    #[default]
+    Xpub,
+    Ypub,
+    Zpub,
+    Tpub,
+    Upub,
+    Vpub,
+    Custom([u8; 4]),
+}
+
+// Limits for non-hardened derivation, as in DKLs23.
+pub const MAX_DEPTH: u8 = 255;
+pub const MAX_CHILD_NUMBER: u32 = 0x7FFFFFFF;
+pub const HARDENED_OFFSET: u32 = 0x80000000;
+
+// Length of a serialized extended key, without the checksum.
+const EXTENDED_KEY_LEN: usize = 78;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExtendedPublicKey {
+    pub depth: u8,
+    pub parent_fingerprint: Fingerprint,
+    pub child_number: u32,
+    pub chain_code: ChainCode,
+    pub pk: AffinePoint,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ExtendedPrivateKey {
+    pub depth: u8,
+    pub parent_fingerprint: Fingerprint,
+    pub child_number: u32,
+    pub chain_code: ChainCode,
+    pub secret_key: Scalar,
+}
+
+// Fields of a serialized extended key, before interpreting the key bytes.
+struct RawExtendedKey {
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: Fingerprint,
+    child_number: u32,
+    chain_code: ChainCode,
+    key: [u8; 33],
+}
+
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+pub fn fingerprint(pk: &AffinePoint) -> Fingerprint {
+    let hash = hash160(pk.to_encoded_point(true).as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn encode_extended_key(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: &Fingerprint,
+    child_number: u32,
+    chain_code: &ChainCode,
+    key: &[u8],
+) -> String {
+    let mut data = Vec::with_capacity(EXTENDED_KEY_LEN);
+    data.extend(version);
+    data.push(depth);
+    data.extend(parent_fingerprint);
+    data.extend(child_number.to_be_bytes());
+    data.extend(chain_code);
+    data.extend(key);
+
+    bs58::encode(data).with_check().into_string()
+}
+
+fn decode_extended_key(encoded: &str) -> Result<RawExtendedKey, ErrorDeriv> {
+    let data = bs58::decode(encoded)
+        .with_check(None)
+        .into_vec()
+        .map_err(|_| ErrorDeriv::new("Invalid base58check encoding!"))?;
+
+    if data.len() != EXTENDED_KEY_LEN {
+        return Err(ErrorDeriv::new("Invalid extended key length!"));
+    }
+
+    let raw = RawExtendedKey {
+        version: data[0..4].try_into().unwrap(),
+        depth: data[4],
+        parent_fingerprint: data[5..9].try_into().unwrap(),
+        child_number: u32::from_be_bytes(data[9..13].try_into().unwrap()),
+        chain_code: data[13..45].try_into().unwrap(),
+        key: data[45..78].try_into().unwrap(),
+    };
+
+    if raw.depth == 0
+        && (raw.parent_fingerprint != [0; 4] || raw.child_number != 0)
+    {
+        return Err(ErrorDeriv::new("Invalid master key metadata!"));
+    }
+
+    Ok(raw)
+}
+
+impl ExtendedKeyVersion {
+    pub fn to_bytes(&self) -> [u8; 4] {
+        match self {
+            ExtendedKeyVersion::Xpub => XPUB_VERSION,
+            ExtendedKeyVersion::Ypub => YPUB_VERSION,
+            ExtendedKeyVersion::Zpub => ZPUB_VERSION,
+            ExtendedKeyVersion::Tpub => TPUB_VERSION,
+            ExtendedKeyVersion::Upub => UPUB_VERSION,
+            ExtendedKeyVersion::Vpub => VPUB_VERSION,
+            ExtendedKeyVersion::Custom(bytes) => *bytes,
+        }
+    }
+
+    pub fn from_bytes(bytes: [u8; 4]) -> ExtendedKeyVersion {
+        match bytes {
+            XPUB_VERSION => ExtendedKeyVersion::Xpub,
+            YPUB_VERSION => ExtendedKeyVersion::Ypub,
+            ZPUB_VERSION => ExtendedKeyVersion::Zpub,
+            TPUB_VERSION => ExtendedKeyVersion::Tpub,
+            UPUB_VERSION => ExtendedKeyVersion::Upub,
+            VPUB_VERSION => ExtendedKeyVersion::Vpub,
+            _ => ExtendedKeyVersion::Custom(bytes),
+        }
+    }
+}
+
+impl ExtendedPublicKey {
+    pub fn encode(&self, version: [u8; 4]) -> String {
+        encode_extended_key(
+            version,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            self.pk.to_encoded_point(true).as_bytes(),
+        )
+    }
+
+    pub fn decode(
+        encoded: &str,
+    ) -> Result<(ExtendedPublicKey, [u8; 4]), ErrorDeriv> {
+        let raw = decode_extended_key(encoded)?;
+
+        if raw.key[0] == 0 {
+            return Err(ErrorDeriv::new("Not an extended public key!"));
+        }
+
+        Ok((
+            ExtendedPublicKey {
+                depth: raw.depth,
+                parent_fingerprint: raw.parent_fingerprint,
+                child_number: raw.child_number,
+                chain_code: raw.chain_code,
+                pk: parse_public_key(&raw.key)?,
+            },
+            raw.version,
+        ))
+    }
+
+    // Public child derivation (CKDpub from BIP-32). It gives the same
+    // public key and chain code as DerivData::derive_child, so it can
+    // be used by parties that hold no share at all.
+    pub fn derive_child(
+        &self,
+        child_number: u32,
+    ) -> Result<ExtendedPublicKey, DerivationError> {
+        if self.depth == MAX_DEPTH {
+            return Err(DerivationError::DepthOverflow {
+                depth: self.depth,
+                length: 1,
+            });
+        }
+        if child_number > MAX_CHILD_NUMBER {
+            return Err(DerivationError::HardenedNotSupported {
+                index: child_number,
+            });
+        }
+
+        let mut hmac = Hmac::<Sha512>::new_from_slice(&self.chain_code)
+            .expect("HMAC can take key of any size");
+        hmac.update(self.pk.to_encoded_point(true).as_bytes());
+        hmac.update(&child_number.to_be_bytes());
+        let result = hmac.finalize().into_bytes();
+
+        let tweak: [u8; 32] = result[..32].try_into().unwrap();
+        let tweak =
+            Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(tweak)))
+                .ok_or(DerivationError::InvalidChild {
+                    index: child_number,
+                })?;
+
+        let pk = (ProjectivePoint::GENERATOR * tweak + self.pk).to_affine();
+        if pk == AffinePoint::IDENTITY {
+            return Err(DerivationError::InvalidChild {
+                index: child_number,
+            });
+        }
+
+        Ok(ExtendedPublicKey {
+            depth: self.depth + 1,
+            parent_fingerprint: fingerprint(&self.pk),
+            child_number,
+            chain_code: result[32..].try_into().unwrap(),
+            pk,
+        })
+    }
+
+    pub fn derive_from_path(
+        &self,
+        path: &str,
+    ) -> Result<ExtendedPublicKey, DerivationError> {
+        let mut key = self.clone();
+        for child_number in validate_path(path, self.depth, false)? {
+            key = key.derive_child(child_number)?;
+        }
+        Ok(key)
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        fingerprint(&self.pk)
+    }
+}
+
+impl From<&DerivData> for ExtendedPublicKey {
+    fn from(data: &DerivData) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: data.depth,
+            parent_fingerprint: data.parent_fingerprint,
+            child_number: data.child_number,
+            chain_code: data.chain_code,
+            pk: data.pk,
+        }
+    }
+}
+
+impl ExtendedPrivateKey {
+    // Master key generation from BIP-32.
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedPrivateKey, ErrorDeriv> {
+        let mut hmac = Hmac::<Sha512>::new_from_slice(b"Bitcoin seed")
+            .expect("HMAC can take key of any size");
+        hmac.update(seed);
+        let result = hmac.finalize().into_bytes();
+
+        let secret_key: [u8; 32] = result[..32].try_into().unwrap();
+        let chain_code: ChainCode = result[32..].try_into().unwrap();
+
+        Ok(ExtendedPrivateKey {
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: 0,
+            chain_code,
+            secret_key: parse_secret_key(&secret_key)?,
+        })
+    }
+
+    pub fn encode(&self, version: [u8; 4]) -> String {
+        let mut key = vec![0u8];
+        key.extend(self.secret_key.to_bytes());
+
+        encode_extended_key(
+            version,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        )
+    }
+
+    pub fn decode(
+        encoded: &str,
+    ) -> Result<(ExtendedPrivateKey, [u8; 4]), ErrorDeriv> {
+        let raw = decode_extended_key(encoded)?;
+
+        if raw.key[0] != 0 {
+            return Err(ErrorDeriv::new("Not an extended private key!"));
+        }
+        let secret_key: [u8; 32] = raw.key[1..].try_into().unwrap();
+
+        Ok((
+            ExtendedPrivateKey {
+                depth: raw.depth,
+                parent_fingerprint: raw.parent_fingerprint,
+                child_number: raw.child_number,
+                chain_code: raw.chain_code,
+                secret_key: parse_secret_key(&secret_key)?,
+            },
+            raw.version,
+        ))
+    }
+
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            pk: (ProjectivePoint::GENERATOR * self.secret_key).to_affine(),
+        }
+    }
+}
+
+// The secret key must be a non-zero scalar smaller than the group order.
+fn parse_secret_key(bytes: &[u8; 32]) -> Result<Scalar, ErrorDeriv> {
+    let secret_key =
+        Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(*bytes)))
+            .ok_or(ErrorDeriv::new("Secret key is out of range!"))?;
+
+    if secret_key == Scalar::ZERO {
+        return Err(ErrorDeriv::new("Secret key is zero!"));
+    }
+
+    Ok(secret_key)
+}
+
+pub fn parse_public_key(bytes: &[u8]) -> Result<AffinePoint, ErrorDeriv> {
+    let encoded = EncodedPoint::from_bytes(bytes)
+        .map_err(|_| ErrorDeriv::new("Invalid public key encoding!"))?;
+
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded))
+        .ok_or(ErrorDeriv::new("Invalid public key!"))
+}
@@ -8,6 +8,7 @@ use crate::dkls23::protocols::dkg::{
     Phase1In, Phase1Out, Phase2In, Phase2Out, Phase3In, Phase3Out, Phase4In,
     Phase4Out,
 };
+use crate::dkls23::session::seal::SealedMessage;
 
 pub trait CJson: Serialize {
     fn to_json(&self) -> *const c_char {
@@ -26,6 +27,25 @@ pub trait CJson: Serialize {
     }
 }
 
+// Returned instead of the usual output when a call fails with an error
+// that the caller can act on.
+#[derive(Deserialize, Serialize)]
+pub struct ErrorOut<E> {
+    pub error: E,
+}
+
+impl<E: Serialize> CJson for ErrorOut<E> {}
+
+// The output of a phase whose point-to-point messages were sealed. They
+// are taken out of the usual fields and returned in `sealed`.
+#[derive(Deserialize, Serialize)]
+pub struct SealedOut<T> {
+    #[serde(flatten)]
+    pub out: T,
+    pub sealed: Vec<SealedMessage>,
+}
+
+impl<T: Serialize> CJson for SealedOut<T> {}
 impl CJson for SessionData {}
 impl CJson for Phase1In {}
 impl CJson for Phase1Out {}
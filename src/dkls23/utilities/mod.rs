@@ -1 +1,4 @@
+pub mod address;
+pub mod bip32;
 pub mod cjson;
+pub mod path;
@@ -0,0 +1,143 @@
+use crate::dkls23::utilities::bip32::{
+    HARDENED_OFFSET, MAX_CHILD_NUMBER, MAX_DEPTH,
+};
+use dkls23::protocols::derivation::ErrorDeriv;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Reasons for which a derivation path or a derivation step is rejected.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DerivationError {
+    // The path is not of the form "m/0/1/2".
+    MalformedPath { segment: String },
+    // A hardened index where only non-hardened ones can be derived.
+    HardenedNotSupported { index: u32 },
+    // The path goes past the maximum depth of 255.
+    DepthOverflow { depth: u8, length: usize },
+    // An index that does not fit in 31 bits.
+    IndexOutOfRange { segment: String },
+    // The child is not a valid key (very unlikely, try the next index).
+    InvalidChild { index: u32 },
+    // Any other failure, e.g. from the DKLs23 crate.
+    Other { description: String },
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationError::MalformedPath { segment } => {
+                write!(f, "Invalid path format at \"{}\"!", segment)
+            }
+            DerivationError::HardenedNotSupported { index } => write!(
+                f,
+                "Hardened derivation is not supported (index {})!",
+                index
+            ),
+            DerivationError::DepthOverflow { depth, length } => write!(
+                f,
+                "A path of length {} from depth {} exceeds the maximum depth!",
+                length, depth
+            ),
+            DerivationError::IndexOutOfRange { segment } => {
+                write!(f, "Index \"{}\" is out of range!", segment)
+            }
+            DerivationError::InvalidChild { index } => {
+                write!(f, "Invalid child {}, try the next index!", index)
+            }
+            DerivationError::Other { description } => {
+                write!(f, "{}", description)
+            }
+        }
+    }
+}
+
+impl From<ErrorDeriv> for DerivationError {
+    fn from(error: ErrorDeriv) -> DerivationError {
+        DerivationError::Other {
+            description: error.description,
+        }
+    }
+}
+
+impl From<DerivationError> for ErrorDeriv {
+    fn from(error: DerivationError) -> ErrorDeriv {
+        ErrorDeriv::new(&error.to_string())
+    }
+}
+
+fn parse_segment(segment: &str) -> Result<u32, DerivationError> {
+    let (number, hardened) = match segment
+        .strip_suffix('\'')
+        .or_else(|| segment.strip_suffix('h'))
+    {
+        Some(number) => (number, true),
+        None => (segment, false),
+    };
+
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DerivationError::MalformedPath {
+            segment: String::from(segment),
+        });
+    }
+    let child_number = number
+        .parse::<u32>()
+        .ok()
+        .filter(|&child_number| child_number <= MAX_CHILD_NUMBER)
+        .ok_or(DerivationError::IndexOutOfRange {
+            segment: String::from(segment),
+        })?;
+
+    if hardened {
+        Ok(child_number + HARDENED_OFFSET)
+    } else {
+        Ok(child_number)
+    }
+}
+
+// Parses paths like "m/44'/60'/0'/0". Hardened indices are marked with
+// ' or h and are returned with the hardened bit set.
+pub fn parse_hardened_path(path: &str) -> Result<Vec<u32>, DerivationError> {
+    let mut segments = path.split('/');
+
+    match segments.next() {
+        Some("m") => {}
+        segment => {
+            return Err(DerivationError::MalformedPath {
+                segment: String::from(segment.unwrap_or_default()),
+            })
+        }
+    }
+
+    segments.map(parse_segment).collect()
+}
+
+// Parses paths like "m/0/1/2". Only non-hardened indices are accepted.
+pub fn parse_path(path: &str) -> Result<Vec<u32>, DerivationError> {
+    let path = parse_hardened_path(path)?;
+    match path.iter().find(|&&index| index > MAX_CHILD_NUMBER) {
+        Some(&index) => Err(DerivationError::HardenedNotSupported { index }),
+        None => Ok(path),
+    }
+}
+
+// Checks that a path can be derived from a key at the given depth.
+pub fn validate_path(
+    path: &str,
+    depth: u8,
+    allow_hardened: bool,
+) -> Result<Vec<u32>, DerivationError> {
+    let path = if allow_hardened {
+        parse_hardened_path(path)?
+    } else {
+        parse_path(path)?
+    };
+
+    if path.len() > (MAX_DEPTH - depth) as usize {
+        return Err(DerivationError::DepthOverflow {
+            depth,
+            length: path.len(),
+        });
+    }
+
+    Ok(path)
+}
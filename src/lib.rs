@@ -1,2 +1,3 @@
 pub mod dkls23;
+pub mod musig2;
 pub use k256;
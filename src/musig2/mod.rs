@@ -0,0 +1,2 @@
+pub mod protocols;
+pub mod utilities;
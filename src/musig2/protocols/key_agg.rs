@@ -0,0 +1,110 @@
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::musig2::protocols::ErrorMusig2;
+use crate::musig2::utilities::hashes::{
+    cbytes, tagged_hash, tagged_hash_as_scalar, xbytes,
+};
+use k256::{AffinePoint, ProjectivePoint, Scalar};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Key aggregation structs
+#[derive(Deserialize, Serialize)]
+pub struct KeySortIn {
+    pub pks: Vec<AffinePoint>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct KeySortOut {
+    pub pks: Vec<AffinePoint>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct KeyAggIn {
+    pub pks: Vec<AffinePoint>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct KeyAggOut {
+    pub agg_pk: AffinePoint,
+    pub x_only_pk: String,
+}
+
+impl CJson for KeySortIn {}
+impl CJson for KeySortOut {}
+impl CJson for KeyAggIn {}
+impl CJson for KeyAggOut {}
+
+// Sorts the public keys lexicographically by their compressed encoding.
+// All signers must use the same order, so sorting is a convenient way
+// to agree on it without an extra round.
+pub fn key_sort(pks: &[AffinePoint]) -> Vec<AffinePoint> {
+    let mut sorted = pks.to_vec();
+    sorted.sort_by_key(cbytes);
+    sorted
+}
+
+fn hash_keys(pks: &[AffinePoint]) -> [u8; 32] {
+    let bytes: Vec<u8> = pks.iter().flat_map(cbytes).collect();
+    tagged_hash("KeyAgg list", &bytes)
+}
+
+// The first key in the list that differs from pks[0]. Its coefficient
+// is one (the "second key" optimization from BIP-327).
+fn second_key(pks: &[AffinePoint]) -> Option<AffinePoint> {
+    pks.iter().find(|pk| **pk != pks[0]).copied()
+}
+
+pub fn key_agg_coefficient(pks: &[AffinePoint], pk: &AffinePoint) -> Scalar {
+    if second_key(pks) == Some(*pk) {
+        return Scalar::ONE;
+    }
+
+    let mut msg = hash_keys(pks).to_vec();
+    msg.extend(cbytes(pk));
+    tagged_hash_as_scalar("KeyAgg coefficient", &msg)
+}
+
+pub fn key_agg(pks: &[AffinePoint]) -> Result<AffinePoint, ErrorMusig2> {
+    if pks.is_empty() {
+        return Err(ErrorMusig2::new("No public keys to aggregate!"));
+    }
+
+    let mut agg_pk = ProjectivePoint::IDENTITY;
+    for pk in pks {
+        if *pk == AffinePoint::IDENTITY {
+            return Err(ErrorMusig2::new("Invalid public key!"));
+        }
+        agg_pk += ProjectivePoint::from(*pk) * key_agg_coefficient(pks, pk);
+    }
+
+    if agg_pk == ProjectivePoint::IDENTITY {
+        return Err(ErrorMusig2::new("Aggregated key is the identity!"));
+    }
+
+    Ok(agg_pk.to_affine())
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_key_sort(
+    key_sort_json_in: *const c_char,
+) -> *const c_char {
+    let key_sort_in: KeySortIn = KeySortIn::from_json(key_sort_json_in);
+    let pks = key_sort(&key_sort_in.pks);
+
+    KeySortOut { pks }.to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_key_agg(
+    key_agg_json_in: *const c_char,
+) -> *const c_char {
+    let key_agg_in: KeyAggIn = KeyAggIn::from_json(key_agg_json_in);
+    match key_agg(&key_agg_in.pks) {
+        Err(error) => ErrorOut { error }.to_json(),
+        Ok(agg_pk) => KeyAggOut {
+            agg_pk,
+            x_only_pk: hex::encode(xbytes(&agg_pk)),
+        }
+        .to_json(),
+    }
+}
@@ -0,0 +1,18 @@
+pub mod key_agg;
+pub mod nonce;
+pub mod signing;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ErrorMusig2 {
+    pub description: String,
+}
+
+impl ErrorMusig2 {
+    pub fn new(description: &str) -> ErrorMusig2 {
+        ErrorMusig2 {
+            description: String::from(description),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::musig2::protocols::ErrorMusig2;
+use crate::musig2::utilities::hashes::{
+    cbytes, tagged_hash, tagged_hash_as_scalar, xbytes,
+};
+use dkls23::utilities::rng;
+use k256::elliptic_curve::rand_core::RngCore;
+use k256::elliptic_curve::zeroize::Zeroize;
+use k256::{AffinePoint, ProjectivePoint, Scalar};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+// The secret nonce must be used for a single partial signature.
+// Signing twice with the same secret nonce leaks the secret key, so it
+// cannot be copied or serialized, and it is wiped when dropped.
+pub struct SecNonce {
+    pub k1: Scalar,
+    pub k2: Scalar,
+    pub pk: AffinePoint,
+}
+
+impl Drop for SecNonce {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
+// The secret nonces of musig2_nonce_gen never leave the library. The
+// host gets an id for each one instead, and musig2_partial_sign takes
+// the nonce out with it, so that it cannot be used a second time.
+static SEC_NONCES: Mutex<BTreeMap<String, SecNonce>> =
+    Mutex::new(BTreeMap::new());
+
+fn keep_sec_nonce(sec_nonce: SecNonce) -> String {
+    let mut id = [0u8; 16];
+    rng::get_rng().fill_bytes(&mut id);
+    let nonce_id = hex::encode(id);
+    SEC_NONCES
+        .lock()
+        .unwrap()
+        .insert(nonce_id.clone(), sec_nonce);
+    nonce_id
+}
+
+pub(crate) fn take_sec_nonce(nonce_id: &str) -> Result<SecNonce, ErrorMusig2> {
+    SEC_NONCES
+        .lock()
+        .unwrap()
+        .remove(nonce_id)
+        .ok_or(ErrorMusig2::new("Unknown or already used nonce!"))
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PubNonce {
+    pub r1: AffinePoint,
+    pub r2: AffinePoint,
+}
+
+impl SecNonce {
+    pub fn is_valid(&self) -> bool {
+        !bool::from(self.k1.is_zero()) && !bool::from(self.k2.is_zero())
+    }
+}
+
+// Sum of all public nonces. Unlike a PubNonce, its points
+// may be the identity.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct AggNonce {
+    pub r1: AffinePoint,
+    pub r2: AffinePoint,
+}
+
+impl AggNonce {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [cbytes(&self.r1), cbytes(&self.r2)].concat()
+    }
+}
+
+// Nonce structs
+#[derive(Deserialize, Serialize)]
+pub struct NonceGenIn {
+    pub secret_key: Option<Scalar>,
+    pub pk: AffinePoint,
+    pub agg_pk: Option<AffinePoint>,
+    pub msg: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct NonceGenOut {
+    // Stands for the secret nonce in musig2_partial_sign.
+    pub nonce_id: String,
+    pub pub_nonce: PubNonce,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct NonceAggIn {
+    pub pub_nonces: Vec<PubNonce>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct NonceAggOut {
+    pub agg_nonce: AggNonce,
+}
+
+impl CJson for NonceGenIn {}
+impl CJson for NonceGenOut {}
+impl CJson for NonceAggIn {}
+impl CJson for NonceAggOut {}
+
+fn nonce_hash(
+    rand: &[u8; 32],
+    pk: &AffinePoint,
+    agg_pk: Option<&[u8; 32]>,
+    msg: Option<&[u8]>,
+    extra_in: &[u8],
+    i: u8,
+) -> Scalar {
+    let pk_bytes = cbytes(pk);
+    let agg_pk_bytes = agg_pk.map(|q| q.to_vec()).unwrap_or_default();
+
+    let mut data = rand.to_vec();
+    data.push(pk_bytes.len() as u8);
+    data.extend(pk_bytes);
+    data.push(agg_pk_bytes.len() as u8);
+    data.extend(agg_pk_bytes);
+    match msg {
+        None => data.push(0),
+        Some(msg) => {
+            data.push(1);
+            data.extend((msg.len() as u64).to_be_bytes());
+            data.extend(msg);
+        }
+    }
+    data.extend((extra_in.len() as u32).to_be_bytes());
+    data.extend(extra_in);
+    data.push(i);
+
+    tagged_hash_as_scalar("MuSig/nonce", &data)
+}
+
+// NonceGen from BIP-327. The secret key, the aggregated key and the
+// message are optional and only strengthen the randomness.
+pub fn nonce_gen(
+    secret_key: Option<&Scalar>,
+    pk: &AffinePoint,
+    agg_pk: Option<&AffinePoint>,
+    msg: Option<&[u8]>,
+) -> (SecNonce, PubNonce) {
+    let mut rand = [0u8; 32];
+    rng::get_rng().fill_bytes(&mut rand);
+    let agg_pk = agg_pk.map(xbytes);
+    let nonces =
+        nonce_gen_internal(&rand, secret_key, pk, agg_pk.as_ref(), msg, None);
+    rand.zeroize();
+    nonces
+}
+
+// NonceGen with the randomness given, as in the test vectors of BIP-327.
+// The aggregated key is x-only. `rand` must never be used twice.
+pub fn nonce_gen_internal(
+    rand: &[u8; 32],
+    secret_key: Option<&Scalar>,
+    pk: &AffinePoint,
+    agg_pk: Option<&[u8; 32]>,
+    msg: Option<&[u8]>,
+    extra_in: Option<&[u8]>,
+) -> (SecNonce, PubNonce) {
+    let mut rand = *rand;
+    if let Some(secret_key) = secret_key {
+        let aux = tagged_hash("MuSig/aux", &rand);
+        let sk_bytes = secret_key.to_bytes();
+        for (byte, (sk_byte, aux_byte)) in
+            rand.iter_mut().zip(sk_bytes.iter().zip(aux.iter()))
+        {
+            *byte = sk_byte ^ aux_byte;
+        }
+    }
+
+    let extra_in = extra_in.unwrap_or_default();
+    let k1 = nonce_hash(&rand, pk, agg_pk, msg, extra_in, 0);
+    let k2 = nonce_hash(&rand, pk, agg_pk, msg, extra_in, 1);
+    rand.zeroize();
+
+    let r1 = (ProjectivePoint::GENERATOR * k1).to_affine();
+    let r2 = (ProjectivePoint::GENERATOR * k2).to_affine();
+
+    (SecNonce { k1, k2, pk: *pk }, PubNonce { r1, r2 })
+}
+
+pub fn nonce_agg(pub_nonces: &[PubNonce]) -> Result<AggNonce, ErrorMusig2> {
+    if pub_nonces.is_empty() {
+        return Err(ErrorMusig2::new("No public nonces to aggregate!"));
+    }
+
+    let mut r1 = ProjectivePoint::IDENTITY;
+    let mut r2 = ProjectivePoint::IDENTITY;
+    for pub_nonce in pub_nonces {
+        if pub_nonce.r1 == AffinePoint::IDENTITY
+            || pub_nonce.r2 == AffinePoint::IDENTITY
+        {
+            return Err(ErrorMusig2::new("Invalid public nonce!"));
+        }
+        r1 += ProjectivePoint::from(pub_nonce.r1);
+        r2 += ProjectivePoint::from(pub_nonce.r2);
+    }
+
+    Ok(AggNonce {
+        r1: r1.to_affine(),
+        r2: r2.to_affine(),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_nonce_gen(
+    nonce_gen_json_in: *const c_char,
+) -> *const c_char {
+    let nonce_gen_in: NonceGenIn = NonceGenIn::from_json(nonce_gen_json_in);
+    let (sec_nonce, pub_nonce) = nonce_gen(
+        nonce_gen_in.secret_key.as_ref(),
+        &nonce_gen_in.pk,
+        nonce_gen_in.agg_pk.as_ref(),
+        nonce_gen_in.msg.as_deref(),
+    );
+
+    NonceGenOut {
+        nonce_id: keep_sec_nonce(sec_nonce),
+        pub_nonce,
+    }
+    .to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_nonce_agg(
+    nonce_agg_json_in: *const c_char,
+) -> *const c_char {
+    let nonce_agg_in: NonceAggIn = NonceAggIn::from_json(nonce_agg_json_in);
+    match nonce_agg(&nonce_agg_in.pub_nonces) {
+        Err(error) => ErrorOut { error }.to_json(),
+        Ok(agg_nonce) => NonceAggOut { agg_nonce }.to_json(),
+    }
+}
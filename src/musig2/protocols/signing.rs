@@ -0,0 +1,337 @@
+use crate::dkls23::utilities::cjson::{CJson, ErrorOut};
+use crate::musig2::protocols::key_agg::{key_agg, key_agg_coefficient};
+use crate::musig2::protocols::nonce::{
+    take_sec_nonce, AggNonce, PubNonce, SecNonce,
+};
+use crate::musig2::protocols::ErrorMusig2;
+use crate::musig2::utilities::hashes::{
+    has_even_y, tagged_hash_as_scalar, xbytes,
+};
+use k256::elliptic_curve::point::DecompressPoint;
+use k256::elliptic_curve::subtle::Choice;
+use k256::elliptic_curve::PrimeField;
+use k256::{AffinePoint, FieldBytes, ProjectivePoint, Scalar};
+use serde::{Deserialize, Serialize};
+use std::os::raw::c_char;
+
+// Sign structs
+#[derive(Deserialize, Serialize)]
+pub struct PartialSignIn {
+    pub secret_key: Scalar,
+    // From musig2_nonce_gen. The secret nonce is used up by this call,
+    // whether it succeeds or not.
+    pub nonce_id: String,
+    pub agg_nonce: AggNonce,
+    pub pks: Vec<AffinePoint>,
+    pub msg: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartialSignOut {
+    pub partial_sig: Scalar,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartialVerifyIn {
+    pub partial_sig: Scalar,
+    pub pub_nonce: PubNonce,
+    pub pk: AffinePoint,
+    pub agg_nonce: AggNonce,
+    pub pks: Vec<AffinePoint>,
+    pub msg: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartialVerifyOut {
+    pub valid: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartialSigAggIn {
+    pub partial_sigs: Vec<Scalar>,
+    pub agg_nonce: AggNonce,
+    pub pks: Vec<AffinePoint>,
+    pub msg: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PartialSigAggOut {
+    pub signature: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VerifyIn {
+    pub msg: Vec<u8>,
+    pub x_only_pk: String,
+    pub signature: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct VerifyOut {
+    pub valid: bool,
+}
+
+impl CJson for PartialSignIn {}
+impl CJson for PartialSignOut {}
+impl CJson for PartialVerifyIn {}
+impl CJson for PartialVerifyOut {}
+impl CJson for PartialSigAggIn {}
+impl CJson for PartialSigAggOut {}
+impl CJson for VerifyIn {}
+impl CJson for VerifyOut {}
+
+// Values shared by every signer of the session (GetSessionValues in BIP-327).
+struct SessionValues {
+    agg_pk: AffinePoint,
+    b: Scalar,
+    r: AffinePoint,
+    e: Scalar,
+}
+
+fn session_values(
+    agg_nonce: &AggNonce,
+    pks: &[AffinePoint],
+    msg: &[u8],
+) -> Result<SessionValues, ErrorMusig2> {
+    let agg_pk = key_agg(pks)?;
+
+    let mut data = agg_nonce.to_bytes();
+    data.extend(xbytes(&agg_pk));
+    data.extend(msg);
+    let b = tagged_hash_as_scalar("MuSig/noncecoef", &data);
+
+    let mut r = ProjectivePoint::from(agg_nonce.r1)
+        + ProjectivePoint::from(agg_nonce.r2) * b;
+    if r == ProjectivePoint::IDENTITY {
+        r = ProjectivePoint::GENERATOR;
+    }
+    let r = r.to_affine();
+
+    let e = challenge(&xbytes(&r), &xbytes(&agg_pk), msg);
+
+    Ok(SessionValues { agg_pk, b, r, e })
+}
+
+fn challenge(r: &[u8; 32], x_only_pk: &[u8; 32], msg: &[u8]) -> Scalar {
+    let mut data = r.to_vec();
+    data.extend(x_only_pk);
+    data.extend(msg);
+    tagged_hash_as_scalar("BIP0340/challenge", &data)
+}
+
+// Negation factor applied to the secret keys so that the aggregated key
+// has an even y-coordinate, as required by BIP-340.
+fn key_parity(agg_pk: &AffinePoint) -> Scalar {
+    if has_even_y(agg_pk) {
+        Scalar::ONE
+    } else {
+        -Scalar::ONE
+    }
+}
+
+pub fn partial_sign(
+    sec_nonce: &SecNonce,
+    secret_key: &Scalar,
+    agg_nonce: &AggNonce,
+    pks: &[AffinePoint],
+    msg: &[u8],
+) -> Result<Scalar, ErrorMusig2> {
+    if !sec_nonce.is_valid() {
+        return Err(ErrorMusig2::new("Invalid secret nonce!"));
+    }
+
+    let pk = (ProjectivePoint::GENERATOR * secret_key).to_affine();
+    if pk != sec_nonce.pk {
+        return Err(ErrorMusig2::new(
+            "Secret nonce was generated for another key!",
+        ));
+    }
+    if !pks.contains(&pk) {
+        return Err(ErrorMusig2::new("Signer key is not in the key list!"));
+    }
+
+    let values = session_values(agg_nonce, pks, msg)?;
+
+    let (k1, k2) = if has_even_y(&values.r) {
+        (sec_nonce.k1, sec_nonce.k2)
+    } else {
+        (-sec_nonce.k1, -sec_nonce.k2)
+    };
+
+    let a = key_agg_coefficient(pks, &pk);
+    let d = key_parity(&values.agg_pk) * secret_key;
+
+    let partial_sig = k1 + values.b * k2 + values.e * a * d;
+
+    // We check our own partial signature before releasing it.
+    let pub_nonce = PubNonce {
+        r1: (ProjectivePoint::GENERATOR * sec_nonce.k1).to_affine(),
+        r2: (ProjectivePoint::GENERATOR * sec_nonce.k2).to_affine(),
+    };
+    if !partial_verify(&partial_sig, &pub_nonce, &pk, agg_nonce, pks, msg) {
+        return Err(ErrorMusig2::new("Partial signature is invalid!"));
+    }
+
+    Ok(partial_sig)
+}
+
+pub fn partial_verify(
+    partial_sig: &Scalar,
+    pub_nonce: &PubNonce,
+    pk: &AffinePoint,
+    agg_nonce: &AggNonce,
+    pks: &[AffinePoint],
+    msg: &[u8],
+) -> bool {
+    if !pks.contains(pk) {
+        return false;
+    }
+
+    let values = match session_values(agg_nonce, pks, msg) {
+        Ok(values) => values,
+        Err(_) => return false,
+    };
+
+    let mut r = ProjectivePoint::from(pub_nonce.r1)
+        + ProjectivePoint::from(pub_nonce.r2) * values.b;
+    if !has_even_y(&values.r) {
+        r = -r;
+    }
+
+    let a = key_agg_coefficient(pks, pk);
+    let g = key_parity(&values.agg_pk);
+
+    ProjectivePoint::GENERATOR * partial_sig
+        == r + ProjectivePoint::from(*pk) * (values.e * a * g)
+}
+
+pub fn partial_sig_agg(
+    partial_sigs: &[Scalar],
+    agg_nonce: &AggNonce,
+    pks: &[AffinePoint],
+    msg: &[u8],
+) -> Result<[u8; 64], ErrorMusig2> {
+    if partial_sigs.len() != pks.len() {
+        return Err(ErrorMusig2::new(
+            "Number of partial signatures and keys differ!",
+        ));
+    }
+
+    let values = session_values(agg_nonce, pks, msg)?;
+    let s: Scalar = partial_sigs.iter().sum();
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&xbytes(&values.r));
+    signature[32..].copy_from_slice(&s.to_bytes());
+
+    Ok(signature)
+}
+
+// BIP-340 verification.
+pub fn verify_schnorr_signature(
+    msg: &[u8],
+    x_only_pk: &[u8; 32],
+    signature: &[u8; 64],
+) -> bool {
+    let pk =
+        AffinePoint::decompress(&FieldBytes::from(*x_only_pk), Choice::from(0));
+    let pk = match Option::<AffinePoint>::from(pk) {
+        Some(pk) => pk,
+        None => return false,
+    };
+
+    let r: [u8; 32] = signature[..32].try_into().unwrap();
+    let s_bytes: [u8; 32] = signature[32..].try_into().unwrap();
+    let s = Scalar::from_repr(FieldBytes::from(s_bytes));
+    let s = match Option::<Scalar>::from(s) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let e = challenge(&r, x_only_pk, msg);
+    let point = (ProjectivePoint::GENERATOR * s
+        - ProjectivePoint::from(pk) * e)
+        .to_affine();
+
+    point != AffinePoint::IDENTITY && has_even_y(&point) && xbytes(&point) == r
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_partial_sign(
+    partial_sign_json_in: *const c_char,
+) -> *const c_char {
+    let partial_sign_in: PartialSignIn =
+        PartialSignIn::from_json(partial_sign_json_in);
+    let result =
+        take_sec_nonce(&partial_sign_in.nonce_id).and_then(|sec_nonce| {
+            partial_sign(
+                &sec_nonce,
+                &partial_sign_in.secret_key,
+                &partial_sign_in.agg_nonce,
+                &partial_sign_in.pks,
+                &partial_sign_in.msg,
+            )
+        });
+    match result {
+        Err(error) => ErrorOut { error }.to_json(),
+        Ok(partial_sig) => PartialSignOut { partial_sig }.to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_partial_verify(
+    partial_verify_json_in: *const c_char,
+) -> *const c_char {
+    let partial_verify_in: PartialVerifyIn =
+        PartialVerifyIn::from_json(partial_verify_json_in);
+    let valid = partial_verify(
+        &partial_verify_in.partial_sig,
+        &partial_verify_in.pub_nonce,
+        &partial_verify_in.pk,
+        &partial_verify_in.agg_nonce,
+        &partial_verify_in.pks,
+        &partial_verify_in.msg,
+    );
+    PartialVerifyOut { valid }.to_json()
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_partial_sig_agg(
+    partial_sig_agg_json_in: *const c_char,
+) -> *const c_char {
+    let partial_sig_agg_in: PartialSigAggIn =
+        PartialSigAggIn::from_json(partial_sig_agg_json_in);
+    match partial_sig_agg(
+        &partial_sig_agg_in.partial_sigs,
+        &partial_sig_agg_in.agg_nonce,
+        &partial_sig_agg_in.pks,
+        &partial_sig_agg_in.msg,
+    ) {
+        Err(error) => ErrorOut { error }.to_json(),
+        Ok(signature) => PartialSigAggOut {
+            signature: hex::encode(signature),
+        }
+        .to_json(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn musig2_verify_schnorr_signature(
+    verify_json_in: *const c_char,
+) -> *const c_char {
+    let verify_in: VerifyIn = VerifyIn::from_json(verify_json_in);
+    let x_only_pk: Option<[u8; 32]> = hex::decode(&verify_in.x_only_pk)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok());
+    let signature: Option<[u8; 64]> = hex::decode(&verify_in.signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok());
+
+    let valid = match (x_only_pk, signature) {
+        (Some(x_only_pk), Some(signature)) => {
+            verify_schnorr_signature(&verify_in.msg, &x_only_pk, &signature)
+        }
+        _ => false,
+    };
+    VerifyOut { valid }.to_json()
+}
@@ -0,0 +1,40 @@
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::point::AffineCoordinates;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{AffinePoint, FieldBytes, Scalar, U256};
+use sha2::{Digest, Sha256};
+
+pub type HashOutput = [u8; 32];
+
+// Tagged hash from BIP-340: SHA256(SHA256(tag) || SHA256(tag) || msg).
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> HashOutput {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(msg);
+    hasher.finalize().into()
+}
+
+pub fn tagged_hash_as_scalar(tag: &str, msg: &[u8]) -> Scalar {
+    let bytes = FieldBytes::from(tagged_hash(tag, msg));
+    <Scalar as Reduce<U256>>::reduce_bytes(&bytes)
+}
+
+// Compressed SEC1 encoding. The point at infinity is encoded
+// as 33 zero bytes, as in the cbytes_ext function from BIP-327.
+pub fn cbytes(point: &AffinePoint) -> Vec<u8> {
+    if *point == AffinePoint::IDENTITY {
+        return vec![0u8; 33];
+    }
+    point.to_encoded_point(true).as_bytes().to_vec()
+}
+
+pub fn xbytes(point: &AffinePoint) -> [u8; 32] {
+    point.x().into()
+}
+
+pub fn has_even_y(point: &AffinePoint) -> bool {
+    !bool::from(point.y_is_odd())
+}